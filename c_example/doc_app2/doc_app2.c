@@ -13,7 +13,6 @@
 #include <assert.h>
 #include <string.h>
 #include <stdio.h>
-#include <unistd.h>
 
 #include <springql.h>
 
@@ -138,7 +137,7 @@ int main()
     fprintf(stderr, "waiting JSON records in tcp/54300...\n");
 
     SpringSinkRow *row;
-    bool is_err = false;
+    SpringPopStatus status;
     while (1)
     {
 #define TS_LEN 128
@@ -148,7 +147,7 @@ int main()
 
         // Fetching rows from q_avg_all.
         {
-            row = spring_pop_non_blocking(pipeline, "q_avg_all", &is_err);
+            row = spring_pop_timeout(pipeline, "q_avg_all", 50, &status);
             if (row)
             {
                 int r = spring_column_text(row, 0, (char *)ts, TS_LEN);
@@ -163,12 +162,12 @@ int main()
             }
             else
             {
-                assert(!is_err);
+                assert(status != SPRING_POP_STATUS_ERROR);
             }
         }
 
         // Fetching rows from q_avg_by_symbol.
-        row = spring_pop_non_blocking(pipeline, "q_avg_by_symbol", &is_err);
+        row = spring_pop_timeout(pipeline, "q_avg_by_symbol", 50, &status);
         if (row)
         {
             int r = spring_column_text(row, 0, (char *)ts, TS_LEN);
@@ -186,11 +185,8 @@ int main()
         }
         else
         {
-            assert(!is_err);
+            assert(status != SPRING_POP_STATUS_ERROR);
         }
-
-        // Avoid busy sleep.
-        usleep(100000);
     }
 
     ret = spring_close(pipeline);
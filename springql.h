@@ -8,6 +8,50 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * Number of buckets in `SpringLatencyHistogram`.
+ */
+#define SPRING_LATENCY_BUCKETS 16
+
+/**
+ * Type of a C struct member to encode into a column.
+ *
+ */
+typedef enum SpringColumnType {
+  /**
+   * `short` member. Encoded into a SMALLINT column.
+   */
+  SPRING_COLUMN_TYPE_SHORT = 0,
+  /**
+   * `int` member. Encoded into an INTEGER column.
+   */
+  SPRING_COLUMN_TYPE_INT = 1,
+  /**
+   * `long` member. Encoded into a BIGINT column.
+   */
+  SPRING_COLUMN_TYPE_LONG = 2,
+  /**
+   * `unsigned int` member. Encoded into an UNSIGNED INTEGER column.
+   */
+  SPRING_COLUMN_TYPE_UNSIGNED_INT = 3,
+  /**
+   * `float` member. Encoded into a FLOAT column.
+   */
+  SPRING_COLUMN_TYPE_FLOAT = 4,
+  /**
+   * `bool` member. Encoded into a BOOLEAN column.
+   */
+  SPRING_COLUMN_TYPE_BOOL = 5,
+  /**
+   * `char[len]` member. Encoded into a TEXT column up to the first NUL byte (or `len` bytes).
+   */
+  SPRING_COLUMN_TYPE_TEXT = 6,
+  /**
+   * `uint8_t[len]` member. Encoded into a BLOB column.
+   */
+  SPRING_COLUMN_TYPE_BLOB = 7,
+} SpringColumnType;
+
 /**
  * Errno (error number) to be returned erroneous functions.
  */
@@ -29,6 +73,18 @@ typedef enum SpringErrno {
   InvalidConfig = -11,
   Null = -12,
   Time = -13,
+  /**
+   * Blocking pop is interrupted
+   */
+  Interrupted = -14,
+  /**
+   * Source queue is full (see `SpringOverflowPolicy`)
+   */
+  WouldBlock = -15,
+  /**
+   * Invalid handle (already freed or never created)
+   */
+  CInvalidHandle = -125,
   /**
    * Insufficient buffer size
    */
@@ -39,16 +95,145 @@ typedef enum SpringErrno {
   CNull = -127,
 } SpringErrno;
 
+/**
+ * Health of a component (worker thread or task) in a pipeline.
+ *
+ */
+typedef enum SpringHealthStatus {
+  /**
+   * Running without recent failures.
+   */
+  SPRING_HEALTH_STATUS_ALIVE = 0,
+  /**
+   * A source reader which has read no rows for the stall threshold, without an error.
+   */
+  SPRING_HEALTH_STATUS_IDLE = 1,
+  /**
+   * No progress for the stall threshold.
+   */
+  SPRING_HEALTH_STATUS_STALLED = 2,
+  /**
+   * Dead by a panic, or got an error and has not made progress since then.
+   */
+  SPRING_HEALTH_STATUS_FAILED = 3,
+} SpringHealthStatus;
+
+/**
+ * Level of a log record.
+ *
+ */
+typedef enum SpringLogLevel {
+  /**
+   * No records (only used as a maximum level).
+   */
+  SPRING_LOG_LEVEL_OFF = 0,
+  SPRING_LOG_LEVEL_ERROR = 1,
+  SPRING_LOG_LEVEL_WARN = 2,
+  SPRING_LOG_LEVEL_INFO = 3,
+  SPRING_LOG_LEVEL_DEBUG = 4,
+  SPRING_LOG_LEVEL_TRACE = 5,
+} SpringLogLevel;
+
+/**
+ * Where the built-in logger writes to.
+ *
+ */
+typedef enum SpringLogTarget {
+  /**
+   * Standard error.
+   */
+  SPRING_LOG_TARGET_STDERR = 0,
+  /**
+   * A file rotated by size.
+   */
+  SPRING_LOG_TARGET_FILE = 1,
+} SpringLogTarget;
+
+/**
+ * Memory state of a pipeline, decided by memory usage against `memory.upper_limit_bytes` in the config.
+ *
+ */
+typedef enum SpringMemoryState {
+  /**
+   * Memory usage is low enough.
+   */
+  SPRING_MEMORY_STATE_MODERATE = 0,
+  /**
+   * Over `memory.moderate_to_severe_percent` (back under `memory.severe_to_moderate_percent`).
+   * The internal scheduler is changed to exhibit memory-resilience.
+   */
+  SPRING_MEMORY_STATE_SEVERE = 1,
+  /**
+   * Over `memory.severe_to_critical_percent` (back under `memory.critical_to_severe_percent`).
+   * All intermediate rows are purged to release memory.
+   */
+  SPRING_MEMORY_STATE_CRITICAL = 2,
+} SpringMemoryState;
+
+/**
+ * What to do when a row is pushed to a full in-memory source queue.
+ *
+ * Set by `spring_queue_set_overflow_policy()` or `OVERFLOW_POLICY` option of `CREATE SOURCE READER ... TYPE IN_MEMORY_QUEUE`.
+ *
+ */
+typedef enum SpringOverflowPolicy {
+  /**
+   * Wait for room up to the block timeout, and then fail with `WouldBlock`.
+   */
+  SPRING_OVERFLOW_POLICY_BLOCK = 0,
+  /**
+   * Drop the oldest row in the queue to make room.
+   */
+  SPRING_OVERFLOW_POLICY_DROP_OLDEST = 1,
+  /**
+   * Drop the pushed row.
+   */
+  SPRING_OVERFLOW_POLICY_DROP_NEWEST = 2,
+  /**
+   * Fail with `WouldBlock` immediately.
+   */
+  SPRING_OVERFLOW_POLICY_FAIL = 3,
+} SpringOverflowPolicy;
+
+/**
+ * Result of a pop with timeout.
+ *
+ */
+typedef enum SpringPopStatus {
+  /**
+   * A row is popped.
+   */
+  SPRING_POP_STATUS_ROW = 0,
+  /**
+   * No row arrived until the timeout elapsed.
+   */
+  SPRING_POP_STATUS_TIMEOUT = 1,
+  /**
+   * Error occurred. Check spring_last_err() for details.
+   */
+  SPRING_POP_STATUS_ERROR = -1,
+} SpringPopStatus;
+
 /**
  * Configuration.
  */
 typedef struct SpringConfig SpringConfig;
 
+/**
+ * Detail of an error, taken by `spring_last_err_detail()`.
+ */
+typedef struct SpringError SpringError;
+
 /**
  * Pipeline (dataflow definition) in SpringQL.
  */
 typedef struct SpringPipeline SpringPipeline;
 
+/**
+ * Handle of an in memory queue, validated by `spring_queue_open()`. Only holds the queue name.
+ */
+typedef struct SpringQueue SpringQueue;
+
 /**
  * Row object to pop from an in memory queue.
  */
@@ -64,6 +249,239 @@ typedef struct SpringSourceRow SpringSourceRow;
  */
 typedef struct SpringSourceRowBuilder SpringSourceRowBuilder;
 
+/**
+ * Callback to receive rows from an in memory sink queue.
+ *
+ * `row` must be freed by `spring_sink_row_close()`.
+ */
+typedef void (*SpringSinkRowCallback)(struct SpringSinkRow *row, void *user_data);
+
+/**
+ * Statistics of an in memory queue on the C client side, filled by `spring_queue_stats()`.
+ *
+ * Counters only include rows which went through this client. They are not statistics of the queue inside SpringQL-core:
+ * SpringQL-core does not expose rows or bytes held in its in memory queues.
+ * Rows and bytes held in the queues between tasks are in the metrics report (see `spring_pipeline_metrics()`).
+ */
+typedef struct SpringQueueStats {
+  /**
+   * Number of rows held on the C client side.
+   *
+   * - Sink queues are buffered (up to 1024 rows) after the first blocking pop, `spring_queue_fd()` or `spring_subscribe()`.
+   * - Source queues are staged with an overflow policy (see `spring_queue_set_overflow_policy()`).
+   *
+   * Always 0 for other queues.
+   */
+  uint64_t client_depth;
+  /**
+   * Total number of rows pushed by `spring_push()`, `spring_push_batch()` or `spring_queue_push()`.
+   */
+  uint64_t rows_pushed;
+  /**
+   * Total number of rows popped by any pop function or delivered to a subscriber.
+   */
+  uint64_t rows_popped;
+  /**
+   * Total number of rows dropped on the C client side by the overflow policy of a source queue (see `spring_queue_set_overflow_policy()`).
+   *
+   * Always 0 for sink queues. Rows purged inside SpringQL-core on memory pressure are not counted.
+   */
+  uint64_t client_rows_dropped;
+} SpringQueueStats;
+
+/**
+ * A C struct member to encode into a column.
+ */
+typedef struct SpringStructColumn {
+  /**
+   * Column name. NUL-terminated string.
+   */
+  const char *name;
+  /**
+   * Type of the member. One of `SpringColumnType`.
+   */
+  int column_type;
+  /**
+   * Offset of the member from the head of the struct (`offsetof()`).
+   */
+  uintptr_t offset;
+  /**
+   * Size of the member in bytes. Only used for `TEXT` and `BLOB`.
+   */
+  uintptr_t len;
+} SpringStructColumn;
+
+/**
+ * Layout descriptor of a C struct.
+ */
+typedef struct SpringStructLayout {
+  /**
+   * Array of members to encode into columns.
+   */
+  const struct SpringStructColumn *columns;
+  /**
+   * Number of elements in `columns`.
+   */
+  uintptr_t n_columns;
+} SpringStructLayout;
+
+/**
+ * Callback to receive errors in background tasks.
+ *
+ * Called from the worker thread which got the error, while the worker waits for the callback to return.
+ *
+ * # Parameters
+ *
+ * - `errno`: Errno of the error. `Unknown` for a panic.
+ * - `component`: Name of the worker thread which got the error (e.g. `SourceWorker`).
+ * - `message`: Message of the error, including the foreign host if any.
+ * - `n_pipelines`: Number of pipelines open in the process when the error occurred. The error belongs to one of them
+ *   (see `spring_poll_background_error()`). `1` means that the error is of the pipeline receiving it.
+ * - `user_data`: The pointer passed to `spring_set_background_error_callback()` as-is.
+ *
+ * All strings are valid only during the call.
+ */
+typedef void (*SpringBackgroundErrorCallback)(enum SpringErrno errno, const char *component, const char *message, uint32_t n_pipelines, void *user_data);
+
+/**
+ * Histogram of latency from ingestion to pop of rows in an in memory sink queue, got by `spring_latency_histogram()`.
+ */
+typedef struct SpringLatencyHistogram {
+  /**
+   * Number of rows whose latency is recorded.
+   */
+  uint64_t count;
+  /**
+   * Sum of latency in nanoseconds.
+   */
+  uint64_t sum_nsec;
+  /**
+   * Minimum latency in nanoseconds. 0 if `count` is 0.
+   */
+  uint64_t min_nsec;
+  /**
+   * Maximum latency in nanoseconds.
+   */
+  uint64_t max_nsec;
+  /**
+   * Number of rows popped without a valid ingest time (e.g. NULL or not a timestamp).
+   */
+  uint64_t rows_untraced;
+  /**
+   * Number of rows in each latency range (not cumulative). Upper bounds of the buckets are
+   * 50us, 100us, 250us, 500us, 1ms, 2.5ms, 5ms, 10ms, 25ms, 50ms, 100ms, 250ms, 500ms, 1s, 2.5s and infinity.
+   */
+  uint64_t buckets[SPRING_LATENCY_BUCKETS];
+} SpringLatencyHistogram;
+
+/**
+ * Callback to receive log records.
+ *
+ * Called from any thread, including ones internally created by SpringQL.
+ *
+ * # Parameters
+ *
+ * - `level`: Level of the record.
+ * - `target`: Module path which emitted the record (e.g. `springql_core::stream_engine`).
+ * - `message`: Formatted message.
+ * - `file`: Source file which emitted the record. NULL if unknown.
+ * - `line`: Line number in `file`. 0 if unknown.
+ * - `user_data`: The pointer passed to `spring_set_log_callback()` as-is.
+ *
+ * All strings are valid only during the call.
+ */
+typedef void (*SpringLogCallback)(enum SpringLogLevel level, const char *target, const char *message, const char *file, int line, void *user_data);
+
+/**
+ * Callback to receive memory state changes.
+ *
+ * Called from a library-owned thread.
+ *
+ * # Parameters
+ *
+ * - `state`: The new memory state.
+ * - `used_bytes`: Memory usage in bytes.
+ * - `upper_limit_bytes`: `memory.upper_limit_bytes` in the config.
+ * - `user_data`: The pointer passed to `spring_set_memory_state_callback()` as-is.
+ */
+typedef void (*SpringMemoryStateCallback)(enum SpringMemoryState state, uint64_t used_bytes, uint64_t upper_limit_bytes, void *user_data);
+
+/**
+ * Summary of pipeline health, got by `spring_pipeline_health()`.
+ *
+ * `spring_pipeline_health_json()` gives the status and last error of each component.
+ */
+typedef struct SpringHealthReport {
+  /**
+   * The worst status among components.
+   */
+  enum SpringHealthStatus status;
+  /**
+   * Number of components (worker threads and tasks).
+   */
+  uint32_t n_components;
+  /**
+   * Number of `Alive` components.
+   */
+  uint32_t n_alive;
+  /**
+   * Number of `Idle` components.
+   */
+  uint32_t n_idle;
+  /**
+   * Number of `Stalled` components.
+   */
+  uint32_t n_stalled;
+  /**
+   * Number of `Failed` components.
+   */
+  uint32_t n_failed;
+} SpringHealthReport;
+
+/**
+ * Summary of pipeline metrics, got by `spring_pipeline_metrics()`.
+ *
+ * `spring_pipeline_metrics_json()` gives the breakdown per task and queue.
+ *
+ * SpringQL-core reports only the average gain (bytes per second) of each task and rows and bytes held in each queue.
+ * Rows processed by each pump, rows read by each source reader or written by each sink writer, and errors per task are not available.
+ * Errors of source readers and sink writers are reported per worker thread by `spring_pipeline_health_json()` and `spring_poll_background_error()`.
+ */
+typedef struct SpringPipelineMetrics {
+  /**
+   * Milliseconds since SpringQL-core reported the metrics below. `-1` if not reported yet.
+   */
+  int64_t report_age_msec;
+  /**
+   * Number of tasks (sources, pumps and sinks).
+   */
+  uint64_t n_tasks;
+  /**
+   * Number of rows in queues between tasks.
+   */
+  uint64_t queued_rows;
+  /**
+   * Number of rows waiting for their windows to close.
+   */
+  uint64_t window_rows;
+  /**
+   * Bytes of rows in queues between tasks (the memory usage compared with `memory.upper_limit_bytes`).
+   */
+  uint64_t memory_used_bytes;
+  /**
+   * `memory.upper_limit_bytes` in the config.
+   */
+  uint64_t memory_upper_limit_bytes;
+  /**
+   * Rows pushed into in memory queues through this client.
+   */
+  uint64_t rows_pushed;
+  /**
+   * Rows popped from in memory queues through this client.
+   */
+  uint64_t rows_popped;
+} SpringPipelineMetrics;
+
 /**
  * Returns default configuration.
  *
@@ -81,29 +499,42 @@ struct SpringConfig *spring_config_default(void);
  *
  * - `overwrite_config_toml`: TOML format configuration to overwrite default.
  *   See <https://springql.github.io/deployment/configuration> for TOML format and configuration values.
+ *   `[client]` section is read by this library: `enable_metrics_receiver` (boolean, false by default, see `spring_open()`).
  *
- * # Panics
+ * # Returns
  *
- * Currently, the process aborts when:
+ * - non-NULL: on success.
+ * - NULL: on failure. Check spring_last_err() for details.
  *
- * - `overwrite_config_toml` includes invalid key and/or value.
- * - `overwrite_config_toml` is not valid as TOML.
+ * # Errors
+ *
+ * - `InvalidFormat`: `overwrite_config_toml` is not valid as TOML.
+ * - `InvalidConfig`: `overwrite_config_toml` includes invalid value.
+ * - `CNull`: `overwrite_config_toml` is a NULL pointer.
  */
 struct SpringConfig *spring_config_toml(const char *overwrite_config_toml);
 
 /**
  * Frees heap occupied by a `SpringConfig`.
  *
+ * Must not be called while another thread is still using `config`: it is not reference-counted.
+ *
  * # Returns
  *
  * - `Ok`: on success.
  * - `CNull`: `config` is a NULL pointer.
+ * - `CInvalidHandle`: `config` is already freed.
  */
 enum SpringErrno spring_config_close(struct SpringConfig *config);
 
 /**
  * Creates and open an in-process stream pipeline.
  *
+ * If `client.enable_metrics_receiver` is true in the config (`[client]` section in `spring_config_toml()`),
+ * metrics reports from SpringQL-core (every `web_console.report_interval_msec`) are received by an HTTP endpoint
+ * on `127.0.0.1` bound by this function, for `spring_pipeline_metrics()`, `spring_memory_usage()` and `spring_pipeline_health()`.
+ * The endpoint is not authenticated. It is not bound if `web_console.enable_report_post` is true.
+ *
  * # Returns
  *
  * - non-NULL: on success
@@ -111,17 +542,23 @@ enum SpringErrno spring_config_close(struct SpringConfig *config);
  *
  * # Errors
  *
- * No errors are expected currently.
+ * - `CNull`: `config` is a NULL pointer.
+ * - `CInvalidHandle`: `config` is already freed.
  */
 struct SpringPipeline *spring_open(const struct SpringConfig *config);
 
 /**
  * Frees heap occupied by a `SpringPipeline`.
  *
+ * Waits for the background error callback (see `spring_set_background_error_callback()`) running on other threads to return.
+ *
+ * Must not be called while another thread is still using `pipeline`: it is not reference-counted.
+ *
  * # Returns
  *
  * - `Ok`: on success.
  * - `CNull`: `pipeline` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
  */
 enum SpringErrno spring_close(struct SpringPipeline *pipeline);
 
@@ -137,15 +574,16 @@ enum SpringErrno spring_close(struct SpringPipeline *pipeline);
  *   - Other semantic errors.
  * - `InvalidOption`:
  *   - `OPTIONS` in `CREATE` statement includes invalid key or value.
+ * - `CNull`: `pipeline` or `sql` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
  */
 enum SpringErrno spring_command(const struct SpringPipeline *pipeline, const char *sql);
 
 /**
  * Pop a row from an in memory queue. This is a blocking function.
  *
- * Do not call this function from threads.
- * If you need to pop from multiple in-memory queues using threads, use `spring_pop_non_blocking()`.
- * See: <https://github.com/SpringQL/SpringQL/issues/125>
+ * This function can be called from multiple threads, for the same queue or different queues.
+ * While waiting for a row, other functions (including `spring_push()`) are not blocked.
  *
  * # Returns
  *
@@ -155,6 +593,9 @@ enum SpringErrno spring_command(const struct SpringPipeline *pipeline, const cha
  * # Errors
  *
  * - `Unavailable`: queue named `queue` does not exist.
+ * - `Interrupted`: interrupted by `spring_pipeline_interrupt()` or `spring_queue_interrupt()`.
+ * - `CNull`: `pipeline` or `queue` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
  */
 struct SpringSinkRow *spring_pop(const struct SpringPipeline *pipeline, const char *queue);
 
@@ -168,28 +609,388 @@ struct SpringSinkRow *spring_pop(const struct SpringPipeline *pipeline, const ch
  *
  * # Errors
  *
- * - `Unavailable`: queue named `queue` does not exist.
+ * - `Unavailable`: queue named `queue` does not exist.
+ * - `CNull`: `pipeline`, `queue` or `is_err` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+struct SpringSinkRow *spring_pop_non_blocking(const struct SpringPipeline *pipeline,
+                                              const char *queue,
+                                              bool *is_err);
+
+/**
+ * Pop a row from an in memory queue, waiting until a row arrives or `timeout_ms` elapses.
+ *
+ * # Parameters
+ *
+ * - `timeout_ms`: How long to wait for a row in milliseconds. Negative value waits forever.
+ * - `status`: A pointer to store whether a row is popped, the timeout elapsed, or an error occurred.
+ *
+ * # Returns
+ *
+ * - non-NULL: Successfully get a row (`status` is `SPRING_POP_STATUS_ROW`).
+ * - NULL: No row arrived until `timeout_ms` elapsed (`status` is `SPRING_POP_STATUS_TIMEOUT`),
+ *   or error occurred (`status` is `SPRING_POP_STATUS_ERROR`; check spring_last_err() for details).
+ *
+ * # Errors
+ *
+ * - `Unavailable`: queue named `queue` does not exist.
+ * - `Interrupted`: interrupted by `spring_pipeline_interrupt()` or `spring_queue_interrupt()`.
+ * - `CNull`: `pipeline`, `queue` or `status` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+struct SpringSinkRow *spring_pop_timeout(const struct SpringPipeline *pipeline,
+                                         const char *queue,
+                                         int timeout_ms,
+                                         enum SpringPopStatus *status);
+
+/**
+ * Pop rows from an in memory queue at once.
+ *
+ * Blocks until at least a row arrives or `timeout_ms` elapses, then returns the rows already in the queue (up to `max`).
+ *
+ * # Parameters
+ *
+ * - `out_rows`: Array of `max` elements to store popped rows. Each row must be freed by `spring_sink_row_close()`.
+ * - `max`: Maximum number of rows to pop. Must be positive.
+ * - `timeout_ms`: How long to wait for the first row in milliseconds. Negative value waits forever.
+ *
+ * # Returns
+ *
+ * - `> 0`: Number of rows stored in `out_rows`.
+ * - `0`: No row arrived until `timeout_ms` elapsed.
+ * - `< 0`: SpringErrno. Check spring_last_err() for details.
+ *
+ * # Errors
+ *
+ * - `InvalidOption`: `max` is not positive.
+ * - `Unavailable`: queue named `queue` does not exist, or too many handles are live (the popped rows are dropped).
+ * - `Interrupted`: interrupted by `spring_pipeline_interrupt()` or `spring_queue_interrupt()`.
+ * - `CNull`: `pipeline`, `queue` or `out_rows` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+int spring_pop_batch(const struct SpringPipeline *pipeline,
+                     const char *queue,
+                     struct SpringSinkRow **out_rows,
+                     int max,
+                     int timeout_ms);
+
+/**
+ * Get a file descriptor which becomes readable whenever an in memory sink queue has rows.
+ *
+ * Pass the file descriptor to `poll()`, `epoll` or any event loop and call `spring_pop_non_blocking()` (or other pop functions) when it is readable.
+ *
+ * The file descriptor is owned by the pipeline. Do not read from, write to, or close it.
+ * It is valid until `spring_close()`.
+ *
+ * # Returns
+ *
+ * - `>= 0`: The file descriptor.
+ * - `< 0`: SpringErrno. Check spring_last_err() for details.
+ *
+ * # Errors
+ *
+ * - `Unavailable`: queue named `queue` does not exist.
+ * - `CNull`: `pipeline` or `queue` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+int spring_queue_fd(const struct SpringPipeline *pipeline,
+                    const char *queue);
+
+/**
+ * Subscribe an in memory sink queue.
+ *
+ * Rows in `queue` are delivered to `callback` one by one on a thread owned by the library,
+ * so you do not need to call pop functions (and should not call them for `queue`) while subscribing.
+ *
+ * Do not call `spring_close()` from `callback`.
+ *
+ * # Parameters
+ *
+ * - `callback`: Called with each row and `user_data`. The row must be freed by `spring_sink_row_close()`.
+ * - `user_data`: Passed to `callback` as-is.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`:
+ *   - queue named `queue` does not exist.
+ *   - `queue` is already subscribed.
+ * - `CNull`: `pipeline`, `queue` or `callback` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_subscribe(const struct SpringPipeline *pipeline,
+                                  const char *queue,
+                                  SpringSinkRowCallback callback,
+                                  void *user_data);
+
+/**
+ * Unsubscribe an in memory sink queue subscribed by `spring_subscribe()`.
+ *
+ * Returns after the last call to the callback finishes (except when called from the callback itself).
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`: `queue` is not subscribed.
+ * - `CNull`: `pipeline` or `queue` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_unsubscribe(const struct SpringPipeline *pipeline,
+                                    const char *queue);
+
+/**
+ * Interrupt blocking pops (`spring_pop()`, `spring_pop_timeout()` and `spring_pop_batch()`) on all queues in the pipeline.
+ *
+ * Pops blocked at the time and ones called afterwards return with `Interrupted` error,
+ * so that threads blocked by them can be joined before `spring_close()`.
+ * Subscriptions by `spring_subscribe()` also stop delivering rows.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `CNull`: `pipeline` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_pipeline_interrupt(const struct SpringPipeline *pipeline);
+
+/**
+ * Interrupt blocking pops (`spring_pop()`, `spring_pop_timeout()` and `spring_pop_batch()`) waiting for `queue`.
+ *
+ * Pops blocked at the time return with `Interrupted` error. Pops called afterwards are not affected.
+ * Does nothing if no blocking pop has ever been called for `queue`.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`: queue named `queue` is not created by `spring_command()` of this pipeline.
+ * - `CNull`: `pipeline` or `queue` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_queue_interrupt(const struct SpringPipeline *pipeline,
+                                        const char *queue);
+
+/**
+ * Push a row into an in memory queue. This is a non-blocking function.
+ *
+ * `row` is freed internally, even if this function fails (e.g. `pipeline` is a NULL pointer).
+ *
+ * By default, in-memory source queues have no capacity limit and this function never blocks nor drops rows.
+ * With an overflow policy (see `spring_queue_set_overflow_policy()`), a full queue blocks,
+ * drops a row or fails with `WouldBlock`.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`: queue named `queue` does not exist.
+ * - `WouldBlock`: the queue is full and its overflow policy rejects `row`.
+ * - `CNull`: `pipeline`, `queue` or `row` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` or `row` is already freed.
+ */
+enum SpringErrno spring_push(const struct SpringPipeline *pipeline,
+                             const char *queue,
+                             struct SpringSourceRow *row);
+
+/**
+ * Push rows into an in memory queue at once. This is a non-blocking function.
+ *
+ * All of `rows` are freed internally, even if this function fails (e.g. `pipeline` is a NULL pointer)
+ * or some of them fail to be pushed. Only if `rows` itself is a NULL pointer, no rows are freed.
+ *
+ * # Parameters
+ *
+ * - `rows`: Array of rows to push.
+ * - `n`: Number of rows in `rows`.
+ * - `statuses`: Array of `n` elements to store the result of each row. Can be NULL if not needed.
+ *
+ * # Returns
+ *
+ * - `Ok`: all rows are successfully pushed.
+ * - `Unavailable`: queue named `queue` does not exist.
+ * - `WouldBlock`: the queue is full and its overflow policy rejects a row.
+ * - Other errno: the result of the first row failed to be pushed. Check `statuses` for each row's result.
+ * - `CNull`: `pipeline`, `queue` or `rows` is a NULL pointer, or an element of `rows` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` or an element of `rows` is already freed.
+ */
+enum SpringErrno spring_push_batch(const struct SpringPipeline *pipeline,
+                                   const char *queue,
+                                   struct SpringSourceRow *const *rows,
+                                   int n,
+                                   enum SpringErrno *statuses);
+
+/**
+ * Push a row written in JSON into an in memory queue. This is a non-blocking function.
+ *
+ * Same as `spring_source_row_from_json()` followed by `spring_push()`.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `InvalidFormat`: JSON string is invalid.
+ * - `Unavailable`: queue named `queue` does not exist.
+ * - `WouldBlock`: the queue is full and its overflow policy rejects the row.
+ * - `CNull`: `pipeline`, `queue` or `json` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_push_json(const struct SpringPipeline *pipeline,
+                                  const char *queue,
+                                  const char *json);
+
+/**
+ * Push rows written in newline-delimited JSON into an in memory queue. This is a non-blocking function.
+ *
+ * Each line is pushed as a row in order. Empty lines are skipped.
+ * Stops at the first line failed to be pushed. Rows in the previous lines are already pushed at that time.
+ *
+ * # Parameters
+ *
+ * - `buf`: Newline-delimited JSON. Does not need to be NUL-terminated.
+ * - `len`: Length of `buf` in bytes.
+ * - `failed_line`: A pointer to store the 1-origin line number failed to be pushed (0 on success). Can be NULL if not needed.
+ *
+ * # Returns
+ *
+ * - `Ok`: all lines are successfully pushed.
+ * - `InvalidFormat`: a line is invalid JSON.
+ * - `Unavailable`: queue named `queue` does not exist.
+ * - `WouldBlock`: the queue is full and its overflow policy rejects a row.
+ * - `CNull`: `pipeline` or `queue` is a NULL pointer, or `buf` is a NULL pointer while `len > 0`.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_push_json_lines(const struct SpringPipeline *pipeline,
+                                        const char *queue,
+                                        const char *buf,
+                                        int len,
+                                        int *failed_line);
+
+/**
+ * Get statistics of an in memory queue (either source or sink).
+ *
+ * Counters only include rows which went through this client. See `SpringQueueStats` for each field
+ * and what SpringQL-core cannot provide.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`: queue named `queue` is not created by `spring_command()` of this pipeline.
+ * - `CNull`: `pipeline`, `queue` or `stats` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_queue_stats(const struct SpringPipeline *pipeline,
+                                    const char *queue,
+                                    struct SpringQueueStats *stats);
+
+/**
+ * Validate the name of an in memory queue (either source or sink) and get a handle for it.
+ *
+ * The queue must be created by `spring_command()` of this pipeline (`CREATE SOURCE READER` or `CREATE SINK WRITER`
+ * with `TYPE IN_MEMORY_QUEUE`), so that a typo fails here instead of mid-stream.
+ * Checking the name does not touch rows in the queue.
+ *
+ * The handle only holds the queue name. SpringQL-core has no API to resolve a queue in advance,
+ * so handle-based functions (`spring_queue_push()`, `spring_queue_pop()` and `spring_queue_pop_non_blocking()`)
+ * still look up the queue by name (and allocate it) in SpringQL-core on every row. They save converting the C string.
+ *
+ * # Returns
+ *
+ * - non-NULL: on success. Must be freed by `spring_queue_close()` before `spring_close()`.
+ * - NULL: on failure. Check spring_last_err() for details.
+ *
+ * # Errors
+ *
+ * - `Unavailable`: queue named `name` is not created by `spring_command()` of this pipeline.
+ * - `CNull`: `pipeline` or `name` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+struct SpringQueue *spring_queue_open(const struct SpringPipeline *pipeline,
+                                      const char *name);
+
+/**
+ * Frees heap occupied by a `SpringQueue`.
+ *
+ * Must not be called while another thread is still using `queue`: it is not reference-counted.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `CNull`: `queue` is a NULL pointer.
+ * - `CInvalidHandle`: `queue` is already freed.
+ */
+enum SpringErrno spring_queue_close(struct SpringQueue *queue);
+
+/**
+ * Push a row into an in memory queue resolved by `spring_queue_open()`. This is a non-blocking function.
+ *
+ * `row` is freed internally.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `WouldBlock`: the queue is full and its overflow policy rejects `row`.
+ * - `CNull`: `pipeline`, `queue` or `row` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline`, `queue` or `row` is already freed.
+ */
+enum SpringErrno spring_queue_push(const struct SpringPipeline *pipeline,
+                                   const struct SpringQueue *queue,
+                                   struct SpringSourceRow *row);
+
+/**
+ * Pop a row from an in memory queue resolved by `spring_queue_open()`. This is a blocking function.
+ *
+ * See `spring_pop()` for details.
+ *
+ * # Returns
+ *
+ * - non-NULL: on success
+ * - NULL: on failure. Check spring_last_err() for details.
+ *
+ * # Errors
+ *
+ * - `Interrupted`: interrupted by `spring_pipeline_interrupt()` or `spring_queue_interrupt()`.
+ * - `CNull`: `pipeline` or `queue` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` or `queue` is already freed.
+ */
+struct SpringSinkRow *spring_queue_pop(const struct SpringPipeline *pipeline,
+                                       const struct SpringQueue *queue);
+
+/**
+ * Pop a row from an in memory queue resolved by `spring_queue_open()`. This is a non-blocking function.
+ *
+ * # Returns
+ *
+ * - non-NULL: Successfully get a row.
+ * - NULL: Error occurred if `is_err` is true (check spring_last_err() for details). Otherwise, any row is not in the queue.
+ *
+ * # Errors
+ *
+ * - `CNull`: `pipeline`, `queue` or `is_err` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` or `queue` is already freed.
  */
-struct SpringSinkRow *spring_pop_non_blocking(const struct SpringPipeline *pipeline,
-                                              const char *queue,
-                                              bool *is_err);
+struct SpringSinkRow *spring_queue_pop_non_blocking(const struct SpringPipeline *pipeline,
+                                                    const struct SpringQueue *queue,
+                                                    bool *is_err);
 
 /**
- * Push a row into an in memory queue. This is a non-blocking function.
- *
- * `row` is freed internally.
+ * Create a source row from JSON string
  *
  * # Returns
  *
- * - `Ok`: on success.
- * - `Unavailable`: queue named `queue` does not exist.
+ * - non-NULL: Successfully created a row.
+ * - NULL: Error occurred.
+ *
+ * # Errors
+ *
+ * - `InvalidFormat`: JSON string is invalid.
+ * - `CNull`: `json` is a NULL pointer.
  */
-enum SpringErrno spring_push(const struct SpringPipeline *pipeline,
-                             const char *queue,
-                             struct SpringSourceRow *row);
+struct SpringSourceRow *spring_source_row_from_json(const char *json);
 
 /**
- * Create a source row from JSON string
+ * Create a source row from a C struct.
+ *
+ * # Parameters
+ *
+ * - `layout`: Layout descriptor of the struct pointed by `s`. Each member in `layout` is encoded into a column.
+ * - `s`: Pointer to the struct to encode. The struct may be packed.
  *
  * # Returns
  *
@@ -198,9 +999,12 @@ enum SpringErrno spring_push(const struct SpringPipeline *pipeline,
  *
  * # Errors
  *
- * - `InvalidFormat`: JSON string is invalid.
+ * - `Sql`: the same column name appears twice in `layout`.
+ * - `InvalidOption`: `column_type` of a column in `layout` is not a `SpringColumnType`.
+ * - `CNull`: `layout` or `s` is a NULL pointer, or `layout` has a NULL pointer in `columns` or a column name.
  */
-struct SpringSourceRow *spring_source_row_from_json(const char *json);
+struct SpringSourceRow *spring_source_row_from_struct(const struct SpringStructLayout *layout,
+                                                      const void *s);
 
 /**
  * Start creating a source row using a builder.
@@ -231,6 +1035,8 @@ struct SpringSourceRowBuilder *spring_source_row_builder(void);
  * # Errors
  *
  * - `Sql`: `column_name` is already added to the builder.
+ * - `CNull`: `builder` or `column_name` is a NULL pointer, or `v` is a NULL pointer while `v_len > 0`.
+ * - `CInvalidHandle`: `builder` is already freed.
  */
 struct SpringSourceRowBuilder *spring_source_row_add_column_blob(struct SpringSourceRowBuilder *builder,
                                                                  const char *column_name,
@@ -244,17 +1050,21 @@ struct SpringSourceRowBuilder *spring_source_row_add_column_blob(struct SpringSo
  *
  * # Returns
  *
- * SpringSourceRow
+ * - non-NULL: SpringSourceRow
+ * - NULL: `builder` is a NULL pointer (`CNull`) or already freed (`CInvalidHandle`).
  */
 struct SpringSourceRow *spring_source_row_build(struct SpringSourceRowBuilder *builder);
 
 /**
  * Frees heap occupied by a `SpringSinkRow`.
  *
+ * Must not be called while another thread is still using `row`: it is not reference-counted.
+ *
  * # Returns
  *
  * - `Ok`: on success.
- * - `CNull`: `pipeline` is a NULL pointer.
+ * - `CNull`: `row` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
  */
 enum SpringErrno spring_sink_row_close(struct SpringSinkRow *row);
 
@@ -273,7 +1083,9 @@ enum SpringErrno spring_sink_row_close(struct SpringSinkRow *row);
  * - `Unavailable`:
  *   - Column pointed by `i_col` is already fetched.
  *   - `i_col` is out of range.
- * - `CNull`: Column value is NULL.
+ * - `Null`: Column value is NULL.
+ * - `CNull`: `row` or `out` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
  */
 enum SpringErrno spring_column_short(const struct SpringSinkRow *row, uint16_t i_col, short *out);
 
@@ -292,7 +1104,9 @@ enum SpringErrno spring_column_short(const struct SpringSinkRow *row, uint16_t i
  * - `Unavailable`:
  *   - Column pointed by `i_col` is already fetched.
  *   - `i_col` is out of range.
- * - `CNull`: Column value is NULL.
+ * - `Null`: Column value is NULL.
+ * - `CNull`: `row` or `out` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
  */
 enum SpringErrno spring_column_int(const struct SpringSinkRow *row, uint16_t i_col, int *out);
 
@@ -311,7 +1125,9 @@ enum SpringErrno spring_column_int(const struct SpringSinkRow *row, uint16_t i_c
  * - `Unavailable`:
  *   - Column pointed by `i_col` is already fetched.
  *   - `i_col` is out of range.
- * - `CNull`: Column value is NULL.
+ * - `Null`: Column value is NULL.
+ * - `CNull`: `row` or `out` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
  */
 enum SpringErrno spring_column_long(const struct SpringSinkRow *row, uint16_t i_col, long *out);
 
@@ -330,7 +1146,9 @@ enum SpringErrno spring_column_long(const struct SpringSinkRow *row, uint16_t i_
  * - `Unavailable`:
  *   - Column pointed by `i_col` is already fetched.
  *   - `i_col` is out of range.
- * - `CNull`: Column value is NULL.
+ * - `Null`: Column value is NULL.
+ * - `CNull`: `row` or `out` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
  */
 enum SpringErrno spring_column_unsigned_int(const struct SpringSinkRow *row,
                                             uint16_t i_col,
@@ -352,7 +1170,9 @@ enum SpringErrno spring_column_unsigned_int(const struct SpringSinkRow *row,
  * - `Unavailable`:
  *   - Column pointed by `i_col` is already fetched.
  *   - `i_col` is out of range.
- * - `CNull`: Column value is NULL.
+ * - `Null`: Column value is NULL.
+ * - `CNull`: `row` or `out` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
  */
 int spring_column_text(const struct SpringSinkRow *row, uint16_t i_col, char *out, int out_len);
 
@@ -372,7 +1192,9 @@ int spring_column_text(const struct SpringSinkRow *row, uint16_t i_col, char *ou
  * - `Unavailable`:
  *   - Column pointed by `i_col` is already fetched.
  *   - `i_col` is out of range.
- * - `CNull`: Column value is NULL.
+ * - `Null`: Column value is NULL.
+ * - `CNull`: `row` or `out` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
  */
 int spring_column_blob(const struct SpringSinkRow *row, uint16_t i_col, void *out, int out_len);
 
@@ -391,7 +1213,9 @@ int spring_column_blob(const struct SpringSinkRow *row, uint16_t i_col, void *ou
  * - `Unavailable`:
  *   - Column pointed by `i_col` is already fetched.
  *   - `i_col` is out of range.
- * - `CNull`: Column value is NULL.
+ * - `Null`: Column value is NULL.
+ * - `CNull`: `row` or `out` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
  */
 enum SpringErrno spring_column_bool(const struct SpringSinkRow *row, uint16_t i_col, bool *out);
 
@@ -410,10 +1234,200 @@ enum SpringErrno spring_column_bool(const struct SpringSinkRow *row, uint16_t i_
  * - `Unavailable`:
  *   - Column pointed by `i_col` is already fetched.
  *   - `i_col` is out of range.
- * - `CNull`: Column value is NULL.
+ * - `Null`: Column value is NULL.
+ * - `CNull`: `row` or `out` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
  */
 enum SpringErrno spring_column_float(const struct SpringSinkRow *row, uint16_t i_col, float *out);
 
+/**
+ * Take the oldest error in background tasks, which `spring_last_err()` cannot tell.
+ *
+ * Background tasks (source readers, pumps and sink writers) run on worker threads of SpringQL-core.
+ * When a task fails (e.g. a `NET_SERVER` source reader receives a malformed JSON, or a sink writer loses its connection),
+ * the worker thread reports the error and continues, and a panic kills the worker thread.
+ * Up to 256 errors are kept from `spring_open()` until polled (the oldest one is dropped on overflow).
+ *
+ * Errors are captured from log records of SpringQL-core, so they are not captured
+ * if another logger than this library's is installed. SpringQL-core does not tell which pipeline a worker thread belongs to,
+ * so all pipelines in the process receive the same errors, with the number of pipelines open when the error occurred.
+ *
+ * # Parameters
+ *
+ * - `errno`: A pointer to store the errno of the error (`Ok` if there is none). `Unknown` for a panic. Can be NULL if not needed.
+ * - `n_pipelines`: A pointer to store the number of pipelines open in the process when the error occurred (`0` if there is no error).
+ *   The error belongs to one of them, and `1` means that the error is of `pipeline`. Can be NULL if not needed.
+ * - `buf`: A buffer to store `<component>: <message>` with a trailing NUL,
+ *   where `<component>` is the name of the worker thread which got the error (e.g. `SourceWorker: ForeignIo { .. }`).
+ *
+ * # Returns
+ *
+ * - `> 0`: the length of the string stored in `buf`. The error is removed.
+ * - `0`: there is no error to take.
+ * - `CNull`: `pipeline` or `buf` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ * - `CInsufficient`: `buf_len` is too small to store the string (with a trailing NUL). The error is kept.
+ */
+int spring_poll_background_error(const struct SpringPipeline *pipeline,
+                                 enum SpringErrno *errno,
+                                 uint32_t *n_pipelines,
+                                 char *buf,
+                                 int buf_len);
+
+/**
+ * Set a callback called whenever a background task gets an error, instead of keeping it for `spring_poll_background_error()`.
+ *
+ * See `spring_poll_background_error()` for errors to be received.
+ * Replaces the previous callback. Waits for the previous callback running on other threads to return,
+ * so that its `user_data` can be freed after this function returns (a callback replacing itself is not waited for).
+ * `spring_close()` also waits for the callback.
+ *
+ * # Parameters
+ *
+ * - `cb`: Callback to receive errors. NULL to unset (errors are kept for `spring_poll_background_error()` again).
+ * - `user_data`: Passed to `cb` as-is.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `CNull`: `pipeline` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_set_background_error_callback(const struct SpringPipeline *pipeline,
+                                                      SpringBackgroundErrorCallback cb,
+                                                      void *user_data);
+
+/**
+ * Get the name of an errno (e.g. `"Sql"` for `Sql`).
+ *
+ * # Returns
+ *
+ * Static NUL-terminated string. Must not be freed.
+ * `"(unknown errno)"` if `errno_` is not a `SpringErrno`.
+ */
+const char *spring_errno_name(int errno_);
+
+/**
+ * Get a human-readable description of an errno.
+ *
+ * # Returns
+ *
+ * Static NUL-terminated string. Must not be freed.
+ * `"(unknown errno)"` if `errno_` is not a `SpringErrno`.
+ */
+const char *spring_strerror(int errno_);
+
+/**
+ * Get the detail of the most recent error.
+ *
+ * Unlike `spring_last_err()`, this function does not clear the most recent error.
+ *
+ * # Returns
+ *
+ * - non-NULL: the detail of the most recent error. Must be freed by `spring_error_free()`.
+ * - NULL: if there are no recent errors.
+ */
+struct SpringError *spring_last_err_detail(void);
+
+/**
+ * Frees heap occupied by a `SpringError`.
+ *
+ * Must not be called while another thread is still using `err`: it is not reference-counted.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `CNull`: `err` is a NULL pointer.
+ * - `CInvalidHandle`: `err` is already freed.
+ */
+enum SpringErrno spring_error_free(struct SpringError *err);
+
+/**
+ * Get the errno of an error.
+ *
+ * # Returns
+ *
+ * - `CNull`: `err` is a NULL pointer.
+ * - `CInvalidHandle`: `err` is already freed.
+ * - Other errno: the errno of `err`.
+ */
+enum SpringErrno spring_error_errno(const struct SpringError *err);
+
+/**
+ * Get the top-level message of an error.
+ *
+ * # Returns
+ *
+ * - `> 0`: the length of the message.
+ * - `CNull`: `err` or `buf` is a NULL pointer.
+ * - `CInvalidHandle`: `err` is already freed.
+ * - `CInsufficient`: `buf_len` is too small to store the message (with a trailing NUL).
+ */
+int spring_error_message(const struct SpringError *err, char *buf, int buf_len);
+
+/**
+ * Get the number of causes of an error.
+ *
+ * # Returns
+ *
+ * - `>= 0`: the number of causes.
+ * - `CNull`: `err` is a NULL pointer.
+ * - `CInvalidHandle`: `err` is already freed.
+ */
+int spring_error_cause_count(const struct SpringError *err);
+
+/**
+ * Get the message of the `i`-th cause of an error.
+ *
+ * Causes are ordered from the direct cause (`i = 0`) to the root cause (`i = spring_error_cause_count() - 1`).
+ *
+ * # Returns
+ *
+ * - `> 0`: the length of the message.
+ * - `CNull`: `err` or `buf` is a NULL pointer.
+ * - `CInvalidHandle`: `err` is already freed.
+ * - `CInsufficient`: `buf_len` is too small to store the message (with a trailing NUL).
+ * - `Unavailable`: `i` is out of range.
+ */
+int spring_error_cause(const struct SpringError *err,
+                       int i,
+                       char *buf,
+                       int buf_len);
+
+/**
+ * Get the backtrace of a panic inside this library.
+ *
+ * # Returns
+ *
+ * - `> 0`: the length of the backtrace.
+ * - `CNull`: `err` or `buf` is a NULL pointer.
+ * - `CInvalidHandle`: `err` is already freed.
+ * - `CInsufficient`: `buf_len` is too small to store the backtrace (with a trailing NUL).
+ * - `Unavailable`: `err` is not a panic, or its backtrace is not recorded.
+ */
+int spring_error_backtrace(const struct SpringError *err, char *buf, int buf_len);
+
+/**
+ * Get the location of a syntax error in the SQL statement passed to `spring_command()`.
+ *
+ * # Parameters
+ *
+ * - `offset`: A pointer to store the 0-origin byte offset in the statement. `-1` if unknown. Can be NULL if not needed.
+ * - `line`: A pointer to store the 1-origin line number. Can be NULL if not needed.
+ * - `column`: A pointer to store the 1-origin column number in characters. Can be NULL if not needed.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `CNull`: `err` is a NULL pointer.
+ * - `CInvalidHandle`: `err` is already freed.
+ * - `Unavailable`: `err` is not a syntax error in SQL.
+ */
+enum SpringErrno spring_error_sql_position(const struct SpringError *err,
+                                           int *offset,
+                                           int *line,
+                                           int *column);
+
 /**
  * Write the most recent error number into `errno_` and message into a caller-provided buffer as a UTF-8
  * string, returning the number of bytes written.
@@ -427,11 +1441,16 @@ enum SpringErrno spring_column_float(const struct SpringSinkRow *row, uint16_t i
  * bytes). `-1` is returned if there are any errors, for example when passed a
  * null pointer or a buffer of insufficient size.
  *
+ * If the error is a panic inside this library, the message includes where the panic occurred.
+ * Its backtrace is got by `spring_error_backtrace()`.
+ *
+ * The error is cleared only if the message is stored in `errmsg`.
+ *
  * # Returns
  *
  * - `0`: if there are no recent errors.
  * - `> 0`: the length of the recent error message.
- * - `< 0`: SpringErrno
+ * - `< 0`: SpringErrno (`CInsufficient` if `errmsg_len` is too small to store the message with a trailing NUL)
  */
 int spring_last_err(enum SpringErrno *errno_,
                     char *errmsg,
@@ -448,4 +1467,382 @@ int spring_last_err(enum SpringErrno *errno_,
  */
 int spring_last_errmsg_len(void);
 
+/**
+ * Get the ingest time of a row as nanoseconds since the Unix epoch (UTC).
+ *
+ * The ingest time must be selected into a `TIMESTAMP` column from the processing time of a source stream, e.g.:
+ *
+ * ```sql
+ * CREATE SINK STREAM sink_1 (n INTEGER NOT NULL, ingest_time TIMESTAMP NOT NULL);
+ * CREATE PUMP pump_1 AS
+ *     INSERT INTO sink_1 (n, ingest_time)
+ *     SELECT STREAM source_1.n, source_1.ptime FROM source_1;
+ * ```
+ *
+ * SpringQL-core stamps the processing time when a source reader reads the row, shortly after `spring_push()` for in memory queues.
+ * Streams with a `ROWTIME` column have no processing time.
+ *
+ * # Parameters
+ *
+ * - `row`: A `SpringRow` pointer to get the ingest time from.
+ * - `i_col`: The column index of the ingest time.
+ * - `out`: A pointer to a buffer to store the ingest time.
+ *
+ * # Returns
+ *
+ * - `Ok`: On success.
+ * - `Sql`: `i_col` is out of range, or column value is not a timestamp.
+ * - `Null`: Column value is NULL.
+ * - `CNull`: `row` or `out` is a NULL pointer.
+ * - `CInvalidHandle`: `row` is already freed.
+ */
+enum SpringErrno spring_sink_row_ingest_time(const struct SpringSinkRow *row,
+                                             uint16_t i_col,
+                                             int64_t *out);
+
+/**
+ * Start recording latency from ingestion to pop of rows in an in memory sink queue.
+ *
+ * Latency is recorded when a row is popped by any pop function or delivered to a subscriber,
+ * from the ingest time in column `ingest_time_col` (see `spring_sink_row_ingest_time()`).
+ * Calling again resets the histogram.
+ *
+ * This library does not stamp rows by itself: latency is only recorded if the SQL selects `<source stream>.ptime`
+ * into a `TIMESTAMP` column `ingest_time_col` of the sink stream. Other rows are counted in `rows_untraced`.
+ * Latency starts when a source reader of SpringQL-core reads the row, so the time a row waits on the client side
+ * (staged by an overflow policy, see `spring_queue_set_overflow_policy()`) is not included.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`: queue named `queue` is not a sink queue created by `spring_command()` of this pipeline.
+ * - `CNull`: `pipeline` or `queue` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_latency_trace(const struct SpringPipeline *pipeline,
+                                      const char *queue,
+                                      uint16_t ingest_time_col);
+
+/**
+ * Get the latency histogram of an in memory sink queue.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`: latency of `queue` is not recorded. Call `spring_latency_trace()` first.
+ * - `CNull`: `pipeline`, `queue` or `histogram` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_latency_histogram(const struct SpringPipeline *pipeline,
+                                          const char *queue,
+                                          struct SpringLatencyHistogram *histogram);
+
+/**
+ * Route log records from this library and SpringQL-core to a callback.
+ *
+ * Without a callback, records are written to stderr by `env_logger` configured by `RUST_LOG` environment variable.
+ *
+ * Call this function before `spring_open()` not to miss records on startup.
+ * Callbacks already running on other threads may still be running when this function returns.
+ * Records emitted while the callback runs on the same thread are dropped.
+ *
+ * # Parameters
+ *
+ * - `cb`: Callback to receive records. NULL to restore the default logger.
+ * - `user_data`: Passed to `cb` as-is.
+ * - `max_level`: Most verbose `SpringLogLevel` to pass to `cb`.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `InvalidOption`: `max_level` is not a `SpringLogLevel`.
+ * - `Unavailable`: Another logger is installed in this process (by an other Rust library).
+ */
+enum SpringErrno spring_set_log_callback(SpringLogCallback cb,
+                                         void *user_data,
+                                         int max_level);
+
+/**
+ * Install a built-in logger for log records from this library and SpringQL-core.
+ *
+ * Each line has a timestamp, level, thread name and target (module path) as:
+ *
+ * ```text
+ * [2022-07-13T01:23:45.678Z INFO  springql-autonomous-executor springql_core::stream_engine] message
+ * ```
+ *
+ * Replaces a callback set by `spring_set_log_callback()`, and vice versa.
+ *
+ * # Parameters
+ *
+ * - `target`: `SpringLogTarget` to write to.
+ * - `level`: Comma-separated level filters (e.g. `springql_core=debug,springql_client=info`, or just `warn`).
+ *   NULL for `info`. `SPRINGQL_LOG` environment variable, if set, is used instead.
+ * - `path`: Log file path when `target` is `SPRING_LOG_TARGET_FILE`. Rotated to `path.1` ... `path.5` by 10 MiB.
+ *   Ignored (can be NULL) for other targets.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `InvalidOption`: `target` is not a `SpringLogTarget`, or `path` cannot be opened.
+ * - `Unavailable`: Another logger is installed in this process (by an other Rust library).
+ * - `CNull`: `path` is a NULL pointer while `target` is `SPRING_LOG_TARGET_FILE`.
+ */
+enum SpringErrno spring_log_init(int target,
+                                 const char *level,
+                                 const char *path);
+
+/**
+ * Get the memory usage and memory state of a pipeline.
+ *
+ * SpringQL-core reports memory usage every `web_console.report_interval_msec` in the config
+ * if `client.enable_metrics_receiver` is true (see `spring_open()`), so the values may lag behind by the interval. Before the first report, `used` is 0 and `state` is `Moderate`.
+ *
+ * # Parameters
+ *
+ * - `used`: A pointer to store memory usage in bytes. Can be NULL if not needed.
+ * - `limit`: A pointer to store `memory.upper_limit_bytes` in the config. Can be NULL if not needed.
+ * - `state`: A pointer to store the memory state. Can be NULL if not needed.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`: memory usage is not reported (see `spring_open()`).
+ * - `CNull`: `pipeline` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_memory_usage(const struct SpringPipeline *pipeline,
+                                     uint64_t *used,
+                                     uint64_t *limit,
+                                     enum SpringMemoryState *state);
+
+/**
+ * Set a callback called whenever the memory state of a pipeline changes.
+ *
+ * Replaces the previous callback. A callback already running may still be running when this function returns.
+ *
+ * # Parameters
+ *
+ * - `cb`: Callback to receive memory state changes. NULL to unset.
+ * - `user_data`: Passed to `cb` as-is.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`: memory usage is not reported (see `spring_open()`).
+ * - `CNull`: `pipeline` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_set_memory_state_callback(const struct SpringPipeline *pipeline,
+                                                  SpringMemoryStateCallback cb,
+                                                  void *user_data);
+
+/**
+ * Set the overflow policy of an in-memory source queue.
+ *
+ * Rows pushed to the queue are staged on the C client side (up to `capacity` rows) and forwarded to SpringQL-core
+ * while the memory state of the pipeline is `Moderate` (see `spring_memory_usage()`).
+ * While the pipeline cannot keep up, the memory state gets `Severe` and the staging fills up.
+ * Pushing to the full staging applies `policy`. Rows dropped by the policy are counted in `SpringQueueStats.client_rows_dropped`.
+ * Rows still staged at `spring_close()` are discarded.
+ *
+ * Same as `OPTIONS (NAME '...', OVERFLOW_POLICY 'BLOCK', CAPACITY '1000', BLOCK_TIMEOUT_MSEC '100')` in
+ * `CREATE SOURCE READER ... TYPE IN_MEMORY_QUEUE`. Calling again replaces the policy.
+ *
+ * The memory state follows metrics reports, so it lags behind the pipeline by `web_console.report_interval_msec` in the config.
+ * Metrics reports must be received (`client.enable_metrics_receiver` in the config).
+ *
+ * # Parameters
+ *
+ * - `policy`: One of `SpringOverflowPolicy`.
+ * - `capacity`: Number of rows staged at most. Must be positive.
+ * - `block_timeout_msec`: How long a push waits for room with `Block` policy. Ignored for other policies.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`:
+ *   - queue named `queue` is not a source queue created by `spring_command()` of this pipeline.
+ *   - memory usage is not reported (see `spring_memory_usage()`).
+ * - `InvalidOption`: `policy` is not a `SpringOverflowPolicy`, or `capacity` is 0.
+ * - `CNull`: `pipeline` or `queue` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_queue_set_overflow_policy(const struct SpringPipeline *pipeline,
+                                                  const char *queue,
+                                                  int policy,
+                                                  uint32_t capacity,
+                                                  uint32_t block_timeout_msec);
+
+/**
+ * Get a summary of pipeline health.
+ *
+ * Components are worker threads of SpringQL-core and tasks (source readers, pumps and sink writers) of the pipeline.
+ *
+ * - A worker thread is `Failed` if it died by a panic, or if it got an error (e.g. a source reader failed to read)
+ *   and no task on it has made progress since then. Errors are captured from log records of SpringQL-core, so they are not captured
+ *   if another logger than this library's is installed. SpringQL-core does not tell which pipeline a worker thread belongs to,
+ *   so all pipelines in the process report the same worker failures (see `n_pipelines` in `spring_pipeline_health_json()`).
+ * - `PerformanceMonitorWorker` is `Stalled` if metrics are not reported for `stall_threshold_msec`.
+ * - A task is `Failed` if the worker thread running it (`SourceWorker` for source readers, `GenericWorker` for pumps and sink writers)
+ *   is `Failed` and the task has not made progress since the error. SpringQL-core does not tell which task an error comes from,
+ *   so an error fails all tasks on the worker thread until each of them makes progress again.
+ * - A task is `Stalled` if it has not made progress for `stall_threshold_msec`. A task makes progress while rows flow through it,
+ *   i.e. its average gain or rows and bytes in its input and output queues change between metrics reports,
+ *   or while its input queues are empty.
+ * - A source reader is `Idle` instead of `Stalled`, as it has no input queue to tell whether rows are coming.
+ *   It cannot be told apart from a source reader blocked without an error (e.g. on a silent remote host).
+ *
+ * Tasks are known from metrics reports (see `spring_pipeline_metrics()`), so health is not reported without metrics reports
+ * (see `spring_open()`), nor until the first report arrives (SpringQL-core starts reporting after the first `spring_command()`).
+ *
+ * `stall_threshold_msec` should be several times `web_console.report_interval_msec` in the config.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `Unavailable`: metrics reports are not received, or the first report has not arrived yet.
+ * - `CNull`: `pipeline` or `report` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_pipeline_health(const struct SpringPipeline *pipeline,
+                                        uint32_t stall_threshold_msec,
+                                        struct SpringHealthReport *report);
+
+/**
+ * Get pipeline health as a JSON string, with the status and last error of each component.
+ *
+ * ```json
+ * {
+ *   "status": "failed",
+ *   "components": [
+ *     {
+ *       "name": "SourceWorker", "kind": "worker", "status": "failed", "progress_age_msec": null,
+ *       "last_error": {
+ *         "errno": -2, "errno_name": "ForeignIo", "panic": false, "age_msec": 120, "n_pipelines": 1,
+ *         "message": "ForeignIo { foreign_info: GenericTcp(127.0.0.1:17890), source: failed to connect to remote host ... }"
+ *       }
+ *     },
+ *     {"name": "GenericWorker", "kind": "worker", "status": "alive", "progress_age_msec": null, "last_error": null},
+ *     {"name": "PerformanceMonitorWorker", "kind": "worker", "status": "alive", "progress_age_msec": 1210, "last_error": null},
+ *     {"name": "MemoryStateMachineWorker", "kind": "worker", "status": "alive", "progress_age_msec": null, "last_error": null},
+ *     {"name": "PurgerWorker", "kind": "worker", "status": "alive", "progress_age_msec": null, "last_error": null},
+ *     {
+ *       "name": "tcp_src", "kind": "source-task", "status": "failed", "progress_age_msec": 1830,
+ *       "last_error": {
+ *         "errno": -2, "errno_name": "ForeignIo", "panic": false, "age_msec": 120, "n_pipelines": 1,
+ *         "message": "ForeignIo { foreign_info: GenericTcp(127.0.0.1:17890), source: failed to connect to remote host ... }"
+ *       }
+ *     },
+ *     {"name": "pump_1", "kind": "pump-task", "status": "alive", "progress_age_msec": 30, "last_error": null},
+ *     {"name": "queue_sink", "kind": "sink-task", "status": "alive", "progress_age_msec": 30, "last_error": null}
+ *   ]
+ * }
+ * ```
+ *
+ * `progress_age_msec` is milliseconds since the last progress, and null if not measurable.
+ * `last_error` is the latest error (or the panic) of a worker thread, or of the worker thread running a task,
+ * and is kept after the component recovers. Its `n_pipelines` is the number of pipelines open in the process when it occurred,
+ * and the error may be of another pipeline than `pipeline` unless it is `1` (see `spring_pipeline_health()`).
+ * See `spring_pipeline_health()` for each status.
+ *
+ * # Returns
+ *
+ * - `> 0`: the length of the JSON string.
+ * - `Unavailable`: metrics reports are not received, or the first report has not arrived yet.
+ * - `CNull`: `pipeline` or `buf` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ * - `CInsufficient`: `buf_len` is too small to store the JSON string (with a trailing NUL).
+ */
+int spring_pipeline_health_json(const struct SpringPipeline *pipeline,
+                                uint32_t stall_threshold_msec,
+                                char *buf,
+                                int buf_len);
+
+/**
+ * Get a summary of pipeline metrics.
+ *
+ * Metrics from SpringQL-core are received every `web_console.report_interval_msec`
+ * if `client.enable_metrics_receiver` is true in the config (see `spring_open()`).
+ * Otherwise, only the counters of in memory queues are available.
+ * See `SpringPipelineMetrics` for metrics not provided by SpringQL-core (e.g. row counts per pump).
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `CNull`: `pipeline` or `metrics` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_pipeline_metrics(const struct SpringPipeline *pipeline,
+                                         struct SpringPipelineMetrics *metrics);
+
+/**
+ * Get pipeline metrics as a JSON string.
+ *
+ * ```json
+ * {
+ *   "report_age_msec": 8,
+ *   "tasks": [
+ *     {"id": "queue_src", "type": "source-task", "avg_gain_bytes_per_sec": 748229.625},
+ *     {"id": "pump_1", "type": "pump-task", "avg_gain_bytes_per_sec": 0.0},
+ *     {"id": "queue_sink", "type": "sink-task", "avg_gain_bytes_per_sec": -1980383.0}
+ *   ],
+ *   "queues": [
+ *     {
+ *       "id": "pump_1-source_1", "type": "row-queue", "upstream_task_id": "queue_src", "downstream_task_id": "pump_1",
+ *       "rows": 0, "bytes": 0
+ *     },
+ *     {
+ *       "id": "queue_sink", "type": "row-queue", "upstream_task_id": "pump_1", "downstream_task_id": "queue_sink",
+ *       "rows": 0, "bytes": 0
+ *     }
+ *   ],
+ *   "in_memory_queues": [
+ *     {"name": "q_sink", "client_depth": 0, "rows_pushed": 0, "rows_popped": 3, "client_rows_dropped": 0, "latency": null},
+ *     {"name": "q_src", "client_depth": 0, "rows_pushed": 3, "rows_popped": 0, "client_rows_dropped": 0, "latency": null}
+ *   ],
+ *   "memory": {"used_bytes": 0, "upper_limit_bytes": 10000000}
+ * }
+ * ```
+ *
+ * Queues of type `window-queue` hold rows waiting for their windows to close.
+ * `latency` of an in memory queue is null unless traced by `spring_latency_trace()`. Otherwise, it has the fields of `SpringLatencyHistogram`
+ * and `buckets` as `[{"le_nsec": 50000, "count": 1}, ..., {"le_nsec": null, "count": 0}]`.
+ * `report_age_msec` and `memory.used_bytes` are null, and `tasks` and `queues` are empty, until SpringQL-core reports metrics.
+ * See `spring_pipeline_metrics()` for when metrics are reported and `SpringPipelineMetrics` for each value.
+ *
+ * # Returns
+ *
+ * - `> 0`: the length of the JSON string.
+ * - `CNull`: `pipeline` or `buf` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ * - `CInsufficient`: `buf_len` is too small to store the JSON string (with a trailing NUL).
+ */
+int spring_pipeline_metrics_json(const struct SpringPipeline *pipeline,
+                                 char *buf,
+                                 int buf_len);
+
+/**
+ * Serve pipeline metrics in [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format)
+ * at `GET /metrics` on a library-owned thread.
+ *
+ * Exposes the same metrics as `spring_pipeline_metrics_json()` (`springql_task_*`, `springql_queue_*`, `springql_memory_*`
+ * and `springql_in_memory_queue_*`). Metrics from SpringQL-core are omitted until they are reported.
+ * Latency traced by `spring_latency_trace()` is a histogram `springql_in_memory_queue_latency_seconds`.
+ *
+ * # Parameters
+ *
+ * - `addr`: Address to listen on (e.g. `127.0.0.1:9187`). NULL to stop serving.
+ *
+ * # Returns
+ *
+ * - `Ok`: on success.
+ * - `InvalidOption`: failed to listen on `addr`.
+ * - `Unavailable`: metrics of `pipeline` are already served. Stop serving first to change `addr`.
+ * - `CNull`: `pipeline` is a NULL pointer.
+ * - `CInvalidHandle`: `pipeline` is already freed.
+ */
+enum SpringErrno spring_metrics_serve(const struct SpringPipeline *pipeline,
+                                      const char *addr);
+
 #endif /* _SPRINGQL_H_ */
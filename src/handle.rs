@@ -0,0 +1,141 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Table of live handles (heap objects passed to C as opaque pointers).
+//!
+//! A handle passed to C is not the address of the object but an index in this table with a generation.
+//! The generation of a slot is bumped when its handle is freed, so that already-freed (or never created) handles
+//! are rejected instead of being dereferenced, even after the slot or the heap address is reused by another object.
+//! A slot whose generation is exhausted is retired instead of being reused, so that a handle value is never issued twice.
+//!
+//! Resolving a handle does not hold the object: it must not be freed by another thread while it is in use.
+
+use std::{
+    any::type_name,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use ::anyhow::anyhow;
+use ::springql::{error::SpringError, Result};
+
+/// Lower half of a handle is the generation, and upper half is `index + 1` (so that a handle is never NULL).
+const GENERATION_BITS: u32 = usize::BITS / 2;
+const GENERATION_MASK: usize = (1 << GENERATION_BITS) - 1;
+/// Number of slots representable in the upper half (65,535 on 32-bit targets).
+const MAX_SLOTS: usize = usize::MAX >> GENERATION_BITS;
+
+/// Write-locked only to create or free a handle, so that resolving handles does not serialize calls from C.
+static TABLE: RwLock<Table> = RwLock::new(Table {
+    slots: Vec::new(),
+    free: Vec::new(),
+});
+
+#[derive(Debug)]
+struct Table {
+    slots: Vec<Slot>,
+    /// Indices of slots without a live handle.
+    free: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct Slot {
+    generation: usize,
+    /// Type name and address of the live object.
+    live: Option<(&'static str, *mut ())>,
+}
+
+// the objects are only dereferenced through handles, by the thread calling the C API
+unsafe impl Send for Slot {}
+unsafe impl Sync for Slot {}
+
+// the table is always consistent even if a thread panicked while holding the lock
+fn table() -> RwLockReadGuard<'static, Table> {
+    TABLE
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn table_mut() -> RwLockWriteGuard<'static, Table> {
+    TABLE
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Table {
+    /// Index and address of a live handle of `T`.
+    fn lookup<T>(&self, handle: *const T) -> Option<(usize, *mut T)> {
+        let handle = handle as usize;
+        let index = (handle >> GENERATION_BITS).checked_sub(1)?;
+        let slot = self.slots.get(index)?;
+        match slot.live {
+            Some((name, ptr))
+                if slot.generation == handle & GENERATION_MASK && name == type_name::<T>() =>
+            {
+                Some((index, ptr as *mut T))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Moves `v` to heap and registers it as a live handle.
+///
+/// # Failure
+///
+/// - `SpringError::Unavailable`: all the slots are live or retired. `v` is dropped.
+pub(crate) fn into_handle<T>(v: T) -> Result<*mut T> {
+    let mut table = table_mut();
+    let index = match table.free.pop() {
+        Some(index) => index,
+        None if table.slots.len() < MAX_SLOTS => {
+            table.slots.push(Slot {
+                generation: 0,
+                live: None,
+            });
+            table.slots.len() - 1
+        }
+        None => {
+            return Err(SpringError::Unavailable {
+                resource: "handle".to_string(),
+                source: anyhow!("too many handles ({} slots in use)", MAX_SLOTS),
+            })
+        }
+    };
+    let ptr = Box::into_raw(Box::new(v));
+    let slot = &mut table.slots[index];
+    slot.live = Some((type_name::<T>(), ptr as *mut ()));
+    Ok((((index + 1) << GENERATION_BITS) | slot.generation) as *mut T)
+}
+
+/// Borrows the object of a live handle of `T`.
+///
+/// # Returns
+///
+/// - `None`: `handle` is not a live handle of `T`.
+///
+/// # Safety
+///
+/// The handle must not be freed while the returned reference is used.
+/// The table lock is released on return, so this is up to the caller of the C API (documented on the functions freeing handles).
+pub(crate) unsafe fn get<'a, T>(handle: *const T) -> Option<&'a T> {
+    table().lookup(handle).map(|(_, ptr)| &*ptr)
+}
+
+/// Unregisters a live handle and takes its ownership back.
+///
+/// # Returns
+///
+/// - `None`: `handle` is not a live handle of `T`.
+pub(crate) unsafe fn from_handle<T>(handle: *mut T) -> Option<Box<T>> {
+    let mut table = table_mut();
+    let (index, ptr) = table.lookup(handle)?;
+
+    let slot = &mut table.slots[index];
+    slot.live = None;
+    // retire the slot instead of wrapping its generation around to a value already issued
+    if slot.generation < GENERATION_MASK {
+        slot.generation += 1;
+        table.free.push(index);
+    }
+
+    Some(Box::from_raw(ptr))
+}
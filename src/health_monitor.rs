@@ -0,0 +1,131 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Progress of a pipeline, tracked from metrics reports.
+//!
+//! SpringQL-core does not count rows processed by each task, but updates the average gain of a task
+//! and rows and bytes in its queues on every execution. A task is regarded as making progress
+//! while any of them changes between reports (or while its input queues are empty, as it has nothing to do).
+//! A constant non-zero depth of a queue is not a stall as long as rows flow through it.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::metrics_receiver::{PipelineReport, QueueReport};
+
+/// Progress of a task.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) struct TaskProgress {
+    avg_gain_bytes_per_sec: f64,
+    /// When the task appeared in a report.
+    pub(crate) seen_at: Instant,
+    /// When the task made progress last time. `None` if not since it appeared.
+    pub(crate) progressed_at: Option<Instant>,
+}
+
+impl TaskProgress {
+    /// Time since the last progress (or since the task appeared).
+    pub(crate) fn age(&self) -> Duration {
+        self.progressed_at.unwrap_or(self.seen_at).elapsed()
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    last_report_at: Instant,
+    tasks: HashMap<String, TaskProgress>,
+    /// Queues in the last report.
+    queues: HashMap<String, QueueReport>,
+}
+
+#[derive(Debug)]
+pub(crate) struct HealthMonitor {
+    state: Mutex<State>,
+}
+
+impl HealthMonitor {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                last_report_at: Instant::now(),
+                tasks: HashMap::new(),
+                queues: HashMap::new(),
+            }),
+        }
+    }
+
+    pub(crate) fn update(&self, report: &PipelineReport) {
+        let mut state = self.lock();
+        state.last_report_at = report.received_at;
+
+        let queue_changed = |queue: &QueueReport| {
+            state
+                .queues
+                .get(&queue.id)
+                .is_none_or(|prev| (prev.rows, prev.bytes) != (queue.rows, queue.bytes))
+        };
+
+        let mut tasks = HashMap::with_capacity(report.tasks.len());
+        for task in &report.tasks {
+            let mut inputs = report
+                .queues
+                .iter()
+                .filter(|queue| queue.downstream_task_id == task.id)
+                .peekable();
+            let idle = inputs.peek().is_some() && inputs.all(|queue| queue.rows == 0);
+            let flowed = report
+                .queues
+                .iter()
+                .filter(|queue| {
+                    queue.downstream_task_id == task.id || queue.upstream_task_id == task.id
+                })
+                .any(queue_changed);
+
+            let progress = match state.tasks.get(&task.id) {
+                Some(prev) => TaskProgress {
+                    avg_gain_bytes_per_sec: task.avg_gain_bytes_per_sec,
+                    seen_at: prev.seen_at,
+                    progressed_at: if idle
+                        || flowed
+                        || prev.avg_gain_bytes_per_sec != task.avg_gain_bytes_per_sec
+                    {
+                        Some(report.received_at)
+                    } else {
+                        prev.progressed_at
+                    },
+                },
+                None => TaskProgress {
+                    avg_gain_bytes_per_sec: task.avg_gain_bytes_per_sec,
+                    seen_at: report.received_at,
+                    progressed_at: None,
+                },
+            };
+            tasks.insert(task.id.clone(), progress);
+        }
+
+        state.tasks = tasks;
+        state.queues = report
+            .queues
+            .iter()
+            .map(|queue| (queue.id.clone(), queue.clone()))
+            .collect();
+    }
+
+    /// When the last report is received (or this monitor is created).
+    pub(crate) fn last_report_at(&self) -> Instant {
+        self.lock().last_report_at
+    }
+
+    /// Progress of `task`. `None` for an unknown task.
+    pub(crate) fn task_progress(&self, task: &str) -> Option<TaskProgress> {
+        self.lock().tasks.get(task).copied()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,310 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Minimal HTTP/1.1 server on a library-owned thread.
+//!
+//! Requests are handled one by one and every connection is closed after a response.
+//! A whole request must arrive within a deadline, so that the server thread is never blocked by idle or slow connections.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    panic::{catch_unwind, AssertUnwindSafe, RefUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Deadline to read a whole request from a client (and to write its response).
+/// Requests not completed in time are rejected with `408 Request Timeout`.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Interval to check whether the server is stopped while no connection arrives.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Requests with a larger request line and headers are rejected with `431 Request Header Fields Too Large`.
+const MAX_HEADER_BYTES: u64 = 8 * 1024;
+
+/// Requests with a larger body are rejected with `413 Payload Too Large`.
+/// Metrics reports from SpringQL-core are a few hundred bytes per task and queue.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) body: Vec<u8>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Response {
+    /// e.g. `200 OK`
+    pub(crate) status: &'static str,
+    pub(crate) content_type: &'static str,
+    pub(crate) body: String,
+}
+
+impl Response {
+    pub(crate) fn ok(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    pub(crate) fn bad_request(reason: &str) -> Self {
+        Self {
+            status: "400 Bad Request",
+            content_type: "text/plain",
+            body: reason.to_string(),
+        }
+    }
+
+    pub(crate) fn not_found() -> Self {
+        Self {
+            status: "404 Not Found",
+            content_type: "text/plain",
+            body: String::new(),
+        }
+    }
+
+    fn request_timeout() -> Self {
+        Self {
+            status: "408 Request Timeout",
+            content_type: "text/plain",
+            body: format!(
+                "request must be sent within {} ms",
+                REQUEST_TIMEOUT.as_millis()
+            ),
+        }
+    }
+
+    fn payload_too_large() -> Self {
+        Self {
+            status: "413 Payload Too Large",
+            content_type: "text/plain",
+            body: format!("request body must be at most {} bytes", MAX_BODY_BYTES),
+        }
+    }
+
+    fn header_fields_too_large() -> Self {
+        Self {
+            status: "431 Request Header Fields Too Large",
+            content_type: "text/plain",
+            body: format!(
+                "request line and headers must be at most {} bytes",
+                MAX_HEADER_BYTES
+            ),
+        }
+    }
+}
+
+/// Why a request is not passed to the handler.
+#[derive(Debug)]
+enum RequestError {
+    Io(io::Error),
+    /// Responded without calling the handler.
+    Rejected(Response),
+}
+
+impl From<io::Error> for RequestError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            // read timeouts are `WouldBlock` on Unix
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+                Self::Rejected(Response::request_timeout())
+            }
+            _ => Self::Io(e),
+        }
+    }
+}
+
+/// Reads a stream until a deadline, across any number of reads.
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct HttpServer {
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl HttpServer {
+    /// Starts serving on `addr` (port 0 for an ephemeral port).
+    pub(crate) fn start<H>(addr: &str, thread_name: &str, handler: H) -> io::Result<Self>
+    where
+        H: Fn(Request) -> Response + Send + 'static,
+    {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        // polled so that stopping the server does not depend on waking up accept()
+        listener.set_nonblocking(true)?;
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let worker = {
+            let stopped = stopped.clone();
+            thread::Builder::new()
+                .name(thread_name.to_string())
+                .spawn(move || {
+                    while !stopped.load(Ordering::Acquire) {
+                        let result = match listener.accept() {
+                            // a panic in the handler must not stop the server thread (nor unwind into C)
+                            Ok((stream, _)) => {
+                                catch_unwind(AssertUnwindSafe(|| Self::serve(stream, &handler)))
+                            }
+                            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                                thread::sleep(ACCEPT_POLL_INTERVAL);
+                                continue;
+                            }
+                            Err(e) => {
+                                thread::sleep(ACCEPT_POLL_INTERVAL);
+                                Ok(Err(e))
+                            }
+                        };
+                        match result {
+                            Ok(Ok(())) => {}
+                            Ok(Err(e)) => {
+                                log::warn!("failed to serve an HTTP request on {}: {:?}", addr, e)
+                            }
+                            Err(_) => {
+                                log::error!("panicked while serving an HTTP request on {}", addr)
+                            }
+                        }
+                    }
+                })?
+        };
+
+        Ok(Self {
+            addr,
+            stopped,
+            worker: Some(worker),
+        })
+    }
+
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    fn serve<H>(stream: TcpStream, handler: &H) -> io::Result<()>
+    where
+        H: Fn(Request) -> Response,
+    {
+        // accepted sockets inherit non-blocking mode from the listener on some platforms
+        stream.set_nonblocking(false)?;
+        stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+        let response = match Self::read_request(&stream) {
+            Ok(Some(request)) => handler(request),
+            // connection closed without a request
+            Ok(None) => return Ok(()),
+            Err(RequestError::Io(e)) => return Err(e),
+            Err(RequestError::Rejected(response)) => response,
+        };
+
+        let header = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            response.status,
+            response.content_type,
+            response.body.len()
+        );
+        let mut stream = &stream;
+        stream.write_all(header.as_bytes())?;
+        stream.write_all(response.body.as_bytes())
+    }
+
+    fn read_request(stream: &TcpStream) -> Result<Option<Request>, RequestError> {
+        let mut reader = BufReader::new(DeadlineReader {
+            stream,
+            deadline: Instant::now() + REQUEST_TIMEOUT,
+        });
+        let mut head = (&mut reader).take(MAX_HEADER_BYTES);
+
+        let mut request_line = String::new();
+        if Self::read_head_line(&mut head, &mut request_line)? == 0 {
+            return Ok(None);
+        }
+        let (method, path) = match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+            [method, path, _version] => (method.to_string(), path.to_string()),
+            _ => {
+                return Err(RequestError::Rejected(Response::bad_request(
+                    "invalid request line",
+                )))
+            }
+        };
+
+        let mut content_length = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if Self::read_head_line(&mut head, &mut line)? == 0 {
+                break;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = match value.trim().parse::<usize>() {
+                        Ok(len) if len > MAX_BODY_BYTES => {
+                            return Err(RequestError::Rejected(Response::payload_too_large()))
+                        }
+                        Ok(len) => len,
+                        Err(_) => {
+                            return Err(RequestError::Rejected(Response::bad_request(
+                                "invalid Content-Length",
+                            )))
+                        }
+                    };
+                }
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        Ok(Some(Request { method, path, body }))
+    }
+
+    /// Reads a line of the request line and headers, limited to `MAX_HEADER_BYTES` in total.
+    fn read_head_line<R: BufRead>(
+        head: &mut io::Take<R>,
+        line: &mut String,
+    ) -> Result<usize, RequestError> {
+        let len = head.read_line(line)?;
+        if head.limit() == 0 && !line.ends_with('\n') {
+            return Err(RequestError::Rejected(Response::header_fields_too_large()));
+        }
+        Ok(len)
+    }
+}
+
+// `worker` is only touched on drop.
+impl RefUnwindSafe for HttpServer {}
+
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        // the server thread notices it within `ACCEPT_POLL_INTERVAL` (or `REQUEST_TIMEOUT` while serving)
+        self.stopped.store(true, Ordering::Release);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
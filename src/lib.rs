@@ -4,15 +4,72 @@
 
 #![allow(clippy::missing_safety_doc)] // C header file does not need `Safety` section
 
+// Macros are defined before the modules so that all of them can use them.
+
+/// Returns `$ret` from the function if any of the pointers is NULL, recording `CNull` as the last error.
+macro_rules! ensure_not_null {
+    ($ret:expr; $($ptr:ident),+ $(,)?) => {
+        $(
+            if $ptr.is_null() {
+                $crate::spring_last_err::null_pointer_error(stringify!($ptr));
+                return $ret;
+            }
+        )+
+    };
+}
+
+/// Returns `$ret` from the function if any of the handles is already freed or not created by this library,
+/// recording `CInvalidHandle` as the last error.
+///
+/// Use after `ensure_not_null!()` so that NULL pointers are reported as `CNull`.
+macro_rules! ensure_live {
+    ($ret:expr; $($ptr:ident),+ $(,)?) => {
+        $(
+            let $ptr = match $crate::handle::get($ptr) {
+                Some($ptr) => $ptr,
+                None => {
+                    $crate::spring_last_err::invalid_handle_error(stringify!($ptr));
+                    return $ret;
+                }
+            };
+        )+
+    };
+}
+
 pub(crate) mod c_mem;
+mod handle;
+mod health_monitor;
+mod http_server;
+mod log_file;
+mod logger;
+mod memory_monitor;
+mod metrics_receiver;
+mod panic_hook;
+mod queue_catalog;
+mod sink_queue_dispatcher;
+mod sink_queue_subscription;
+mod source_queue_feeder;
+mod worker_monitor;
 
+pub mod spring_background_error;
 pub mod spring_config;
 pub mod spring_errno;
+pub mod spring_error;
 pub mod spring_last_err;
+pub mod spring_latency;
+pub mod spring_log;
+pub mod spring_memory;
+pub mod spring_overflow_policy;
 mod spring_pipeline;
+pub mod spring_pipeline_health;
+pub mod spring_pipeline_metrics;
+pub mod spring_pop_status;
+mod spring_queue;
+pub mod spring_queue_stats;
 mod spring_sink_row;
 mod spring_source_row;
 mod spring_source_row_builder;
+pub mod spring_struct_layout;
 
 #[cfg(test)]
 mod tests;
@@ -20,22 +77,29 @@ mod tests;
 use std::{
     ffi::{c_void, CStr},
     os::raw::{c_char, c_float, c_int, c_long, c_short, c_uint},
-    panic::{catch_unwind, UnwindSafe},
+    panic::{catch_unwind, AssertUnwindSafe, UnwindSafe},
     ptr, slice,
+    time::Duration,
 };
 
 use crate::{
     c_mem::{memcpy, strcpy},
+    sink_queue_subscription::SpringSinkRowCallback,
     spring_config::SpringConfig,
     spring_errno::SpringErrno,
-    spring_last_err::{update_last_error, LastError},
+    spring_last_err::{invalid_handle_error, null_pointer_error, update_last_error, LastError},
     spring_pipeline::SpringPipeline,
+    spring_pop_status::SpringPopStatus,
+    spring_queue::SpringQueue,
+    spring_queue_stats::SpringQueueStats,
     spring_sink_row::SpringSinkRow,
     spring_source_row::SpringSourceRow,
     spring_source_row_builder::SpringSourceRowBuilder,
+    spring_struct_layout::SpringStructLayout,
 };
+use ::anyhow::anyhow;
 use ::springql::{
-    error::SpringError, SpringPipeline as Pipeline, SpringSourceRow as RuSpringSourceRow,
+    error::SpringError as RuSpringError, SpringSourceRow as RuSpringSourceRow,
     SpringSourceRowBuilder as RuSpringSourceRowBuilder,
 };
 
@@ -45,8 +109,11 @@ use ::springql::{
 /// If you would like to change the default configuration, use `spring_config_toml()` instead.
 #[no_mangle]
 pub extern "C" fn spring_config_default() -> *mut SpringConfig {
-    let config = SpringConfig::default();
-    config.into_ptr()
+    let result = with_catch(|| SpringConfig::default().into_ptr());
+    match result {
+        Ok(config) => config,
+        Err(_) => ptr::null_mut(),
+    }
 }
 
 /// Configuration by TOML format string.
@@ -57,42 +124,53 @@ pub extern "C" fn spring_config_default() -> *mut SpringConfig {
 ///
 /// - `overwrite_config_toml`: TOML format configuration to overwrite default.
 ///   See <https://springql.github.io/deployment/configuration> for TOML format and configuration values.
+///   `[client]` section is read by this library: `enable_metrics_receiver` (boolean, false by default, see `spring_open()`).
+///
+/// # Returns
 ///
-/// # Panics
+/// - non-NULL: on success.
+/// - NULL: on failure. Check spring_last_err() for details.
 ///
-/// Currently, the process aborts when:
+/// # Errors
 ///
-/// - `overwrite_config_toml` includes invalid key and/or value.
-/// - `overwrite_config_toml` is not valid as TOML.
+/// - `InvalidFormat`: `overwrite_config_toml` is not valid as TOML.
+/// - `InvalidConfig`: `overwrite_config_toml` includes invalid value.
+/// - `CNull`: `overwrite_config_toml` is a NULL pointer.
 #[no_mangle]
 pub unsafe extern "C" fn spring_config_toml(
     overwrite_config_toml: *const c_char,
 ) -> *mut SpringConfig {
-    let s = CStr::from_ptr(overwrite_config_toml);
-    let s = s.to_str().expect("failed to parse TOML string into UTF-8");
+    ensure_not_null!(ptr::null_mut(); overwrite_config_toml);
 
-    let config = SpringConfig::from_toml(s).expect("failed to parse TOML config");
-    config.into_ptr()
+    let s = CStr::from_ptr(overwrite_config_toml).to_string_lossy();
+    let result = with_catch(|| SpringConfig::from_toml(&s)?.into_ptr());
+    match result {
+        Ok(config) => config,
+        Err(_) => ptr::null_mut(),
+    }
 }
 
 /// Frees heap occupied by a `SpringConfig`.
 ///
+/// Must not be called while another thread is still using `config`: it is not reference-counted.
+///
 /// # Returns
 ///
 /// - `Ok`: on success.
 /// - `CNull`: `config` is a NULL pointer.
+/// - `CInvalidHandle`: `config` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_config_close(config: *mut SpringConfig) -> SpringErrno {
-    if config.is_null() {
-        SpringErrno::CNull
-    } else {
-        let _ = Box::from_raw(config);
-        SpringErrno::Ok
-    }
+    close_handle(config, "config")
 }
 
 /// Creates and open an in-process stream pipeline.
 ///
+/// If `client.enable_metrics_receiver` is true in the config (`[client]` section in `spring_config_toml()`),
+/// metrics reports from SpringQL-core (every `web_console.report_interval_msec`) are received by an HTTP endpoint
+/// on `127.0.0.1` bound by this function, for `spring_pipeline_metrics()`, `spring_memory_usage()` and `spring_pipeline_health()`.
+/// The endpoint is not authenticated. It is not bound if `web_console.enable_report_post` is true.
+///
 /// # Returns
 ///
 /// - non-NULL: on success
@@ -100,31 +178,41 @@ pub unsafe extern "C" fn spring_config_close(config: *mut SpringConfig) -> Sprin
 ///
 /// # Errors
 ///
-/// No errors are expected currently.
+/// - `CNull`: `config` is a NULL pointer.
+/// - `CInvalidHandle`: `config` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_open(config: *const SpringConfig) -> *mut SpringPipeline {
-    let config = &*config;
-    let res_ru_pipeline = with_catch(|| Pipeline::new(config.as_ref()));
-    match res_ru_pipeline {
-        Ok(ru_pipeline) => SpringPipeline::from(ru_pipeline).into_ptr(),
+    ensure_not_null!(ptr::null_mut(); config);
+    ensure_live!(ptr::null_mut(); config);
+
+    let res_pipeline = with_catch(|| {
+        // before SpringQL-core installs its own logger
+        logger::install();
+        let pipeline = SpringPipeline::open(config)?;
+        // SpringQL-core replaces the panic hook on the first pipeline creation
+        panic_hook::install_after_core();
+        pipeline.into_ptr()
+    });
+    match res_pipeline {
+        Ok(pipeline) => pipeline,
         Err(_err) => ptr::null_mut(),
     }
 }
 
 /// Frees heap occupied by a `SpringPipeline`.
 ///
+/// Waits for the background error callback (see `spring_set_background_error_callback()`) running on other threads to return.
+///
+/// Must not be called while another thread is still using `pipeline`: it is not reference-counted.
+///
 /// # Returns
 ///
 /// - `Ok`: on success.
 /// - `CNull`: `pipeline` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_close(pipeline: *mut SpringPipeline) -> SpringErrno {
-    if pipeline.is_null() {
-        SpringErrno::CNull
-    } else {
-        let _ = Box::from_raw(pipeline);
-        SpringErrno::Ok
-    }
+    close_handle(pipeline, "pipeline")
 }
 
 /// Execute commands (DDL) to modify the pipeline.
@@ -138,14 +226,23 @@ pub unsafe extern "C" fn spring_close(pipeline: *mut SpringPipeline) -> SpringEr
 ///   - Other semantic errors.
 /// - `InvalidOption`:
 ///   - `OPTIONS` in `CREATE` statement includes invalid key or value.
+/// - `CNull`: `pipeline` or `sql` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_command(
     pipeline: *const SpringPipeline,
     sql: *const c_char,
 ) -> SpringErrno {
-    let ru_pipeline = (*pipeline).as_ref();
+    ensure_not_null!(SpringErrno::CNull; pipeline, sql);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
     let sql = CStr::from_ptr(sql).to_string_lossy().into_owned();
-    let result = with_catch(|| ru_pipeline.command(sql));
+    let result = with_catch(|| {
+        pipeline.command(&sql).map_err(|err| LastError::CommandErr {
+            err,
+            statement: sql.clone(),
+        })
+    });
 
     match result {
         Ok(_) => SpringErrno::Ok,
@@ -155,9 +252,8 @@ pub unsafe extern "C" fn spring_command(
 
 /// Pop a row from an in memory queue. This is a blocking function.
 ///
-/// Do not call this function from threads.
-/// If you need to pop from multiple in-memory queues using threads, use `spring_pop_non_blocking()`.
-/// See: <https://github.com/SpringQL/SpringQL/issues/125>
+/// This function can be called from multiple threads, for the same queue or different queues.
+/// While waiting for a row, other functions (including `spring_push()`) are not blocked.
 ///
 /// # Returns
 ///
@@ -167,19 +263,24 @@ pub unsafe extern "C" fn spring_command(
 /// # Errors
 ///
 /// - `Unavailable`: queue named `queue` does not exist.
+/// - `Interrupted`: interrupted by `spring_pipeline_interrupt()` or `spring_queue_interrupt()`.
+/// - `CNull`: `pipeline` or `queue` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_pop(
     pipeline: *const SpringPipeline,
     queue: *const c_char,
 ) -> *mut SpringSinkRow {
-    let ru_pipeline = (*pipeline).as_ref();
+    ensure_not_null!(ptr::null_mut(); pipeline, queue);
+    ensure_live!(ptr::null_mut(); pipeline);
+
     let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
-    let result = with_catch(|| ru_pipeline.pop(&queue));
+    let result = with_catch(|| {
+        let ru_row = pipeline.pop(&queue)?;
+        Ok::<_, LastError>(SpringSinkRow::from(ru_row).into_ptr()?)
+    });
     match result {
-        Ok(ru_row) => {
-            let row = SpringSinkRow::from(ru_row);
-            row.into_ptr()
-        }
+        Ok(row) => row,
         Err(_) => ptr::null_mut(),
     }
 }
@@ -194,19 +295,29 @@ pub unsafe extern "C" fn spring_pop(
 /// # Errors
 ///
 /// - `Unavailable`: queue named `queue` does not exist.
+/// - `CNull`: `pipeline`, `queue` or `is_err` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_pop_non_blocking(
     pipeline: *const SpringPipeline,
     queue: *const c_char,
     is_err: *mut bool,
 ) -> *mut SpringSinkRow {
-    let ru_pipeline = (*pipeline).as_ref();
+    ensure_not_null!(ptr::null_mut(); is_err);
+    ensure_not_null!({ *is_err = true; ptr::null_mut() }; pipeline, queue);
+    ensure_live!({ *is_err = true; ptr::null_mut() }; pipeline);
+
     let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
-    let result = with_catch(|| ru_pipeline.pop_non_blocking(&queue));
+    let result = with_catch(|| {
+        pipeline
+            .pop_non_blocking(&queue)?
+            .map(|row| SpringSinkRow::from(row).into_ptr())
+            .transpose()
+    });
     match result {
         Ok(Some(row)) => {
             *is_err = false;
-            SpringSinkRow::from(row).into_ptr()
+            row
         }
         Ok(None) => {
             *is_err = false;
@@ -219,32 +330,677 @@ pub unsafe extern "C" fn spring_pop_non_blocking(
     }
 }
 
+/// Pop a row from an in memory queue, waiting until a row arrives or `timeout_ms` elapses.
+///
+/// # Parameters
+///
+/// - `timeout_ms`: How long to wait for a row in milliseconds. Negative value waits forever.
+/// - `status`: A pointer to store whether a row is popped, the timeout elapsed, or an error occurred.
+///
+/// # Returns
+///
+/// - non-NULL: Successfully get a row (`status` is `SPRING_POP_STATUS_ROW`).
+/// - NULL: No row arrived until `timeout_ms` elapsed (`status` is `SPRING_POP_STATUS_TIMEOUT`),
+///   or error occurred (`status` is `SPRING_POP_STATUS_ERROR`; check spring_last_err() for details).
+///
+/// # Errors
+///
+/// - `Unavailable`: queue named `queue` does not exist.
+/// - `Interrupted`: interrupted by `spring_pipeline_interrupt()` or `spring_queue_interrupt()`.
+/// - `CNull`: `pipeline`, `queue` or `status` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_pop_timeout(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    timeout_ms: c_int,
+    status: *mut SpringPopStatus,
+) -> *mut SpringSinkRow {
+    ensure_not_null!(ptr::null_mut(); status);
+    ensure_not_null!({ *status = SpringPopStatus::Error; ptr::null_mut() }; pipeline, queue);
+    ensure_live!({ *status = SpringPopStatus::Error; ptr::null_mut() }; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    let timeout = timeout_from_msec(timeout_ms);
+
+    let result = with_catch(|| {
+        let ru_row = pipeline.pop_timeout(&queue, timeout)?;
+        Ok::<_, LastError>(
+            ru_row
+                .map(|ru_row| SpringSinkRow::from(ru_row).into_ptr())
+                .transpose()?,
+        )
+    });
+    match result {
+        Ok(Some(row)) => {
+            *status = SpringPopStatus::Row;
+            row
+        }
+        Ok(None) => {
+            *status = SpringPopStatus::Timeout;
+            ptr::null_mut()
+        }
+        Err(_) => {
+            *status = SpringPopStatus::Error;
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Pop rows from an in memory queue at once.
+///
+/// Blocks until at least a row arrives or `timeout_ms` elapses, then returns the rows already in the queue (up to `max`).
+///
+/// # Parameters
+///
+/// - `out_rows`: Array of `max` elements to store popped rows. Each row must be freed by `spring_sink_row_close()`.
+/// - `max`: Maximum number of rows to pop. Must be positive.
+/// - `timeout_ms`: How long to wait for the first row in milliseconds. Negative value waits forever.
+///
+/// # Returns
+///
+/// - `> 0`: Number of rows stored in `out_rows`.
+/// - `0`: No row arrived until `timeout_ms` elapsed.
+/// - `< 0`: SpringErrno. Check spring_last_err() for details.
+///
+/// # Errors
+///
+/// - `InvalidOption`: `max` is not positive.
+/// - `Unavailable`: queue named `queue` does not exist, or too many handles are live (the popped rows are dropped).
+/// - `Interrupted`: interrupted by `spring_pipeline_interrupt()` or `spring_queue_interrupt()`.
+/// - `CNull`: `pipeline`, `queue` or `out_rows` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_pop_batch(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    out_rows: *mut *mut SpringSinkRow,
+    max: c_int,
+    timeout_ms: c_int,
+) -> c_int {
+    ensure_not_null!(SpringErrno::CNull as c_int; pipeline, queue, out_rows);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    let timeout = timeout_from_msec(timeout_ms);
+
+    let result = with_catch(|| {
+        if max <= 0 {
+            return Err(LastError::from(RuSpringError::InvalidOption {
+                key: "max".to_string(),
+                value: max.to_string(),
+                source: anyhow!("max must be positive"),
+            }));
+        }
+        let ru_rows = pipeline.pop_batch(&queue, max as usize, timeout)?;
+        let n_rows = ru_rows.len();
+        for (i, ru_row) in ru_rows.into_iter().enumerate() {
+            match SpringSinkRow::from(ru_row).into_ptr() {
+                Ok(row) => *out_rows.add(i) = row,
+                Err(e) => {
+                    for j in 0..i {
+                        handle::from_handle(*out_rows.add(j));
+                    }
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(n_rows as c_int)
+    });
+    match result {
+        Ok(n_rows) => n_rows,
+        Err(e) => e as c_int,
+    }
+}
+
+/// Get a file descriptor which becomes readable whenever an in memory sink queue has rows.
+///
+/// Pass the file descriptor to `poll()`, `epoll` or any event loop and call `spring_pop_non_blocking()` (or other pop functions) when it is readable.
+///
+/// The file descriptor is owned by the pipeline. Do not read from, write to, or close it.
+/// It is valid until `spring_close()`.
+///
+/// # Returns
+///
+/// - `>= 0`: The file descriptor.
+/// - `< 0`: SpringErrno. Check spring_last_err() for details.
+///
+/// # Errors
+///
+/// - `Unavailable`: queue named `queue` does not exist.
+/// - `CNull`: `pipeline` or `queue` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_queue_fd(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+) -> c_int {
+    ensure_not_null!(SpringErrno::CNull as c_int; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    let result = with_catch(|| pipeline.queue_fd(&queue));
+    match result {
+        Ok(fd) => fd,
+        Err(e) => e as c_int,
+    }
+}
+
+/// Subscribe an in memory sink queue.
+///
+/// Rows in `queue` are delivered to `callback` one by one on a thread owned by the library,
+/// so you do not need to call pop functions (and should not call them for `queue`) while subscribing.
+///
+/// Do not call `spring_close()` from `callback`.
+///
+/// # Parameters
+///
+/// - `callback`: Called with each row and `user_data`. The row must be freed by `spring_sink_row_close()`.
+/// - `user_data`: Passed to `callback` as-is.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`:
+///   - queue named `queue` does not exist.
+///   - `queue` is already subscribed.
+/// - `CNull`: `pipeline`, `queue` or `callback` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_subscribe(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    callback: SpringSinkRowCallback,
+    user_data: *mut c_void,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+    let callback = match callback {
+        Some(callback) => callback,
+        None => return null_pointer_error("callback"),
+    };
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    let result = with_catch(|| pipeline.subscribe(&queue, callback, user_data));
+    match result {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
+
+/// Unsubscribe an in memory sink queue subscribed by `spring_subscribe()`.
+///
+/// Returns after the last call to the callback finishes (except when called from the callback itself).
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`: `queue` is not subscribed.
+/// - `CNull`: `pipeline` or `queue` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_unsubscribe(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    let result = with_catch(|| pipeline.unsubscribe(&queue));
+    match result {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
+
+/// Interrupt blocking pops (`spring_pop()`, `spring_pop_timeout()` and `spring_pop_batch()`) on all queues in the pipeline.
+///
+/// Pops blocked at the time and ones called afterwards return with `Interrupted` error,
+/// so that threads blocked by them can be joined before `spring_close()`.
+/// Subscriptions by `spring_subscribe()` also stop delivering rows.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `CNull`: `pipeline` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_pipeline_interrupt(pipeline: *const SpringPipeline) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let result = with_catch(|| {
+        pipeline.interrupt();
+        Ok::<_, LastError>(())
+    });
+    match result {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
+
+/// Interrupt blocking pops (`spring_pop()`, `spring_pop_timeout()` and `spring_pop_batch()`) waiting for `queue`.
+///
+/// Pops blocked at the time return with `Interrupted` error. Pops called afterwards are not affected.
+/// Does nothing if no blocking pop has ever been called for `queue`.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`: queue named `queue` is not created by `spring_command()` of this pipeline.
+/// - `CNull`: `pipeline` or `queue` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_queue_interrupt(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    let result = with_catch(|| pipeline.interrupt_queue(&queue));
+    match result {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
+
 /// Push a row into an in memory queue. This is a non-blocking function.
 ///
-/// `row` is freed internally.
+/// `row` is freed internally, even if this function fails (e.g. `pipeline` is a NULL pointer).
+///
+/// By default, in-memory source queues have no capacity limit and this function never blocks nor drops rows.
+/// With an overflow policy (see `spring_queue_set_overflow_policy()`), a full queue blocks,
+/// drops a row or fails with `WouldBlock`.
 ///
 /// # Returns
 ///
 /// - `Ok`: on success.
 /// - `Unavailable`: queue named `queue` does not exist.
+/// - `WouldBlock`: the queue is full and its overflow policy rejects `row`.
+/// - `CNull`: `pipeline`, `queue` or `row` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` or `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_push(
     pipeline: *const SpringPipeline,
     queue: *const c_char,
     row: *mut SpringSourceRow,
 ) -> SpringErrno {
-    let ru_pipeline = (*pipeline).as_ref();
+    ensure_not_null!(SpringErrno::CNull; row);
+    let source_row = match handle::from_handle(row) {
+        Some(source_row) => RuSpringSourceRow::from(*source_row),
+        None => return invalid_handle_error("row"),
+    };
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+
+    let result = with_catch(|| pipeline.push(&queue, source_row));
+    match result {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
+
+/// Push rows into an in memory queue at once. This is a non-blocking function.
+///
+/// All of `rows` are freed internally, even if this function fails (e.g. `pipeline` is a NULL pointer)
+/// or some of them fail to be pushed. Only if `rows` itself is a NULL pointer, no rows are freed.
+///
+/// # Parameters
+///
+/// - `rows`: Array of rows to push.
+/// - `n`: Number of rows in `rows`.
+/// - `statuses`: Array of `n` elements to store the result of each row. Can be NULL if not needed.
+///
+/// # Returns
+///
+/// - `Ok`: all rows are successfully pushed.
+/// - `Unavailable`: queue named `queue` does not exist.
+/// - `WouldBlock`: the queue is full and its overflow policy rejects a row.
+/// - Other errno: the result of the first row failed to be pushed. Check `statuses` for each row's result.
+/// - `CNull`: `pipeline`, `queue` or `rows` is a NULL pointer, or an element of `rows` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` or an element of `rows` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_push_batch(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    rows: *const *mut SpringSourceRow,
+    n: c_int,
+    statuses: *mut SpringErrno,
+) -> SpringErrno {
+    if n <= 0 {
+        return SpringErrno::Ok;
+    }
+    ensure_not_null!(SpringErrno::CNull; rows);
+
+    // takes all rows first, so that they are freed even if the other arguments are invalid
+    let source_rows: Vec<_> = slice::from_raw_parts(rows, n as usize)
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            if row.is_null() {
+                Err(null_pointer_error(format!("rows[{}]", i)))
+            } else {
+                handle::from_handle(*row)
+                    .map(|source_row| RuSpringSourceRow::from(*source_row))
+                    .ok_or_else(|| invalid_handle_error(format!("rows[{}]", i)))
+            }
+        })
+        .collect();
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+
+    let mut first_err = SpringErrno::Ok;
+    for (i, source_row) in source_rows.into_iter().enumerate() {
+        let errno = match source_row {
+            Ok(source_row) => match with_catch(|| pipeline.push(&queue, source_row)) {
+                Ok(()) => SpringErrno::Ok,
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+
+        if !statuses.is_null() {
+            *statuses.add(i) = errno;
+        }
+        if first_err == SpringErrno::Ok {
+            first_err = errno;
+        }
+    }
+    first_err
+}
+
+/// Push a row written in JSON into an in memory queue. This is a non-blocking function.
+///
+/// Same as `spring_source_row_from_json()` followed by `spring_push()`.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `InvalidFormat`: JSON string is invalid.
+/// - `Unavailable`: queue named `queue` does not exist.
+/// - `WouldBlock`: the queue is full and its overflow policy rejects the row.
+/// - `CNull`: `pipeline`, `queue` or `json` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_push_json(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    json: *const c_char,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue, json);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
     let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    let json = CStr::from_ptr(json).to_string_lossy().into_owned();
 
-    let source_row = Box::from_raw(row);
-    let source_row = RuSpringSourceRow::from(*source_row);
-    let result = with_catch(|| ru_pipeline.push(&queue, source_row));
+    let result = with_catch(|| {
+        let source_row = RuSpringSourceRow::from_json(&json)?;
+        pipeline.push(&queue, source_row)
+    });
     match result {
         Ok(()) => SpringErrno::Ok,
         Err(e) => e,
     }
 }
 
+/// Push rows written in newline-delimited JSON into an in memory queue. This is a non-blocking function.
+///
+/// Each line is pushed as a row in order. Empty lines are skipped.
+/// Stops at the first line failed to be pushed. Rows in the previous lines are already pushed at that time.
+///
+/// # Parameters
+///
+/// - `buf`: Newline-delimited JSON. Does not need to be NUL-terminated.
+/// - `len`: Length of `buf` in bytes.
+/// - `failed_line`: A pointer to store the 1-origin line number failed to be pushed (0 on success). Can be NULL if not needed.
+///
+/// # Returns
+///
+/// - `Ok`: all lines are successfully pushed.
+/// - `InvalidFormat`: a line is invalid JSON.
+/// - `Unavailable`: queue named `queue` does not exist.
+/// - `WouldBlock`: the queue is full and its overflow policy rejects a row.
+/// - `CNull`: `pipeline` or `queue` is a NULL pointer, or `buf` is a NULL pointer while `len > 0`.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_push_json_lines(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    buf: *const c_char,
+    len: c_int,
+    failed_line: *mut c_int,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+
+    let buf = if len <= 0 {
+        &[]
+    } else if buf.is_null() {
+        return null_pointer_error("buf");
+    } else {
+        slice::from_raw_parts(buf as *const u8, len as usize)
+    };
+    for (i, line) in buf.split(|b| *b == b'\n').enumerate() {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let result = with_catch(|| {
+            let source_row = RuSpringSourceRow::from_json(line)?;
+            pipeline.push(&queue, source_row)
+        });
+        if let Err(e) = result {
+            if !failed_line.is_null() {
+                *failed_line = (i + 1) as c_int;
+            }
+            return e;
+        }
+    }
+
+    if !failed_line.is_null() {
+        *failed_line = 0;
+    }
+    SpringErrno::Ok
+}
+
+/// Get statistics of an in memory queue (either source or sink).
+///
+/// Counters only include rows which went through this client. See `SpringQueueStats` for each field
+/// and what SpringQL-core cannot provide.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`: queue named `queue` is not created by `spring_command()` of this pipeline.
+/// - `CNull`: `pipeline`, `queue` or `stats` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_queue_stats(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    stats: *mut SpringQueueStats,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue, stats);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+
+    match with_catch(|| pipeline.queue_stats(&queue)) {
+        Ok(v) => {
+            *stats = v;
+            SpringErrno::Ok
+        }
+        Err(e) => e,
+    }
+}
+
+/// Validate the name of an in memory queue (either source or sink) and get a handle for it.
+///
+/// The queue must be created by `spring_command()` of this pipeline (`CREATE SOURCE READER` or `CREATE SINK WRITER`
+/// with `TYPE IN_MEMORY_QUEUE`), so that a typo fails here instead of mid-stream.
+/// Checking the name does not touch rows in the queue.
+///
+/// The handle only holds the queue name. SpringQL-core has no API to resolve a queue in advance,
+/// so handle-based functions (`spring_queue_push()`, `spring_queue_pop()` and `spring_queue_pop_non_blocking()`)
+/// still look up the queue by name (and allocate it) in SpringQL-core on every row. They save converting the C string.
+///
+/// # Returns
+///
+/// - non-NULL: on success. Must be freed by `spring_queue_close()` before `spring_close()`.
+/// - NULL: on failure. Check spring_last_err() for details.
+///
+/// # Errors
+///
+/// - `Unavailable`: queue named `name` is not created by `spring_command()` of this pipeline.
+/// - `CNull`: `pipeline` or `name` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_queue_open(
+    pipeline: *const SpringPipeline,
+    name: *const c_char,
+) -> *mut SpringQueue {
+    ensure_not_null!(ptr::null_mut(); pipeline, name);
+    ensure_live!(ptr::null_mut(); pipeline);
+
+    let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+    let result = with_catch(|| pipeline.open_queue(&name)?.into_ptr());
+    match result {
+        Ok(queue) => queue,
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Frees heap occupied by a `SpringQueue`.
+///
+/// Must not be called while another thread is still using `queue`: it is not reference-counted.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `CNull`: `queue` is a NULL pointer.
+/// - `CInvalidHandle`: `queue` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_queue_close(queue: *mut SpringQueue) -> SpringErrno {
+    close_handle(queue, "queue")
+}
+
+/// Push a row into an in memory queue resolved by `spring_queue_open()`. This is a non-blocking function.
+///
+/// `row` is freed internally.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `WouldBlock`: the queue is full and its overflow policy rejects `row`.
+/// - `CNull`: `pipeline`, `queue` or `row` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline`, `queue` or `row` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_queue_push(
+    pipeline: *const SpringPipeline,
+    queue: *const SpringQueue,
+    row: *mut SpringSourceRow,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; row);
+    let source_row = match handle::from_handle(row) {
+        Some(source_row) => RuSpringSourceRow::from(*source_row),
+        None => return invalid_handle_error("row"),
+    };
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline, queue);
+
+    let result = with_catch(|| pipeline.push(queue.name(), source_row));
+    match result {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
+
+/// Pop a row from an in memory queue resolved by `spring_queue_open()`. This is a blocking function.
+///
+/// See `spring_pop()` for details.
+///
+/// # Returns
+///
+/// - non-NULL: on success
+/// - NULL: on failure. Check spring_last_err() for details.
+///
+/// # Errors
+///
+/// - `Interrupted`: interrupted by `spring_pipeline_interrupt()` or `spring_queue_interrupt()`.
+/// - `CNull`: `pipeline` or `queue` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` or `queue` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_queue_pop(
+    pipeline: *const SpringPipeline,
+    queue: *const SpringQueue,
+) -> *mut SpringSinkRow {
+    ensure_not_null!(ptr::null_mut(); pipeline, queue);
+    ensure_live!(ptr::null_mut(); pipeline, queue);
+
+    let result = with_catch(|| {
+        let ru_row = pipeline.pop(queue.name())?;
+        Ok::<_, LastError>(SpringSinkRow::from(ru_row).into_ptr()?)
+    });
+    match result {
+        Ok(row) => row,
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Pop a row from an in memory queue resolved by `spring_queue_open()`. This is a non-blocking function.
+///
+/// # Returns
+///
+/// - non-NULL: Successfully get a row.
+/// - NULL: Error occurred if `is_err` is true (check spring_last_err() for details). Otherwise, any row is not in the queue.
+///
+/// # Errors
+///
+/// - `CNull`: `pipeline`, `queue` or `is_err` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` or `queue` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_queue_pop_non_blocking(
+    pipeline: *const SpringPipeline,
+    queue: *const SpringQueue,
+    is_err: *mut bool,
+) -> *mut SpringSinkRow {
+    ensure_not_null!(ptr::null_mut(); is_err);
+    ensure_not_null!({ *is_err = true; ptr::null_mut() }; pipeline, queue);
+    ensure_live!({ *is_err = true; ptr::null_mut() }; pipeline, queue);
+
+    let result = with_catch(|| {
+        pipeline
+            .pop_non_blocking(queue.name())?
+            .map(|row| SpringSinkRow::from(row).into_ptr())
+            .transpose()
+    });
+    match result {
+        Ok(Some(row)) => {
+            *is_err = false;
+            row
+        }
+        Ok(None) => {
+            *is_err = false;
+            ptr::null_mut()
+        }
+        Err(_) => {
+            *is_err = true;
+            ptr::null_mut()
+        }
+    }
+}
+
 /// Create a source row from JSON string
 ///
 /// # Returns
@@ -255,12 +1011,53 @@ pub unsafe extern "C" fn spring_push(
 /// # Errors
 ///
 /// - `InvalidFormat`: JSON string is invalid.
+/// - `CNull`: `json` is a NULL pointer.
 #[no_mangle]
 pub unsafe extern "C" fn spring_source_row_from_json(json: *const c_char) -> *mut SpringSourceRow {
+    ensure_not_null!(ptr::null_mut(); json);
+
     let json = CStr::from_ptr(json).to_string_lossy().into_owned();
-    let res_ru_source_row = with_catch(|| ::springql::SpringSourceRow::from_json(&json));
-    match res_ru_source_row {
-        Ok(ru_source_row) => SpringSourceRow::from(ru_source_row).into_ptr(),
+    let res_source_row = with_catch(|| {
+        SpringSourceRow::from(::springql::SpringSourceRow::from_json(&json)?).into_ptr()
+    });
+    match res_source_row {
+        Ok(source_row) => source_row,
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Create a source row from a C struct.
+///
+/// # Parameters
+///
+/// - `layout`: Layout descriptor of the struct pointed by `s`. Each member in `layout` is encoded into a column.
+/// - `s`: Pointer to the struct to encode. The struct may be packed.
+///
+/// # Returns
+///
+/// - non-NULL: Successfully created a row.
+/// - NULL: Error occurred.
+///
+/// # Errors
+///
+/// - `Sql`: the same column name appears twice in `layout`.
+/// - `InvalidOption`: `column_type` of a column in `layout` is not a `SpringColumnType`.
+/// - `CNull`: `layout` or `s` is a NULL pointer, or `layout` has a NULL pointer in `columns` or a column name.
+#[no_mangle]
+pub unsafe extern "C" fn spring_source_row_from_struct(
+    layout: *const SpringStructLayout,
+    s: *const c_void,
+) -> *mut SpringSourceRow {
+    ensure_not_null!(ptr::null_mut(); layout, s);
+
+    let layout = &*layout;
+    if let Err(arg) = layout.validate() {
+        null_pointer_error(arg);
+        return ptr::null_mut();
+    }
+    let res_source_row = with_catch(|| SpringSourceRow::from(layout.to_source_row(s)?).into_ptr());
+    match res_source_row {
+        Ok(source_row) => source_row,
         Err(_) => ptr::null_mut(),
     }
 }
@@ -272,7 +1069,11 @@ pub unsafe extern "C" fn spring_source_row_from_json(json: *const c_char) -> *mu
 /// Pointer to the builder
 #[no_mangle]
 pub unsafe extern "C" fn spring_source_row_builder() -> *mut SpringSourceRowBuilder {
-    SpringSourceRowBuilder::default().into_ptr()
+    let result = with_catch(|| SpringSourceRowBuilder::default().into_ptr());
+    match result {
+        Ok(builder) => builder,
+        Err(_) => ptr::null_mut(),
+    }
 }
 /// Add a BLOB column to the builder and return the new one.
 ///
@@ -293,6 +1094,8 @@ pub unsafe extern "C" fn spring_source_row_builder() -> *mut SpringSourceRowBuil
 /// # Errors
 ///
 /// - `Sql`: `column_name` is already added to the builder.
+/// - `CNull`: `builder` or `column_name` is a NULL pointer, or `v` is a NULL pointer while `v_len > 0`.
+/// - `CInvalidHandle`: `builder` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_source_row_add_column_blob(
     builder: *mut SpringSourceRowBuilder,
@@ -300,17 +1103,33 @@ pub unsafe extern "C" fn spring_source_row_add_column_blob(
     v: *const c_void,
     v_len: c_int,
 ) -> *mut SpringSourceRowBuilder {
+    ensure_not_null!(ptr::null_mut(); builder);
+    let builder = match handle::from_handle(builder) {
+        Some(builder) => builder,
+        None => {
+            invalid_handle_error("builder");
+            return ptr::null_mut();
+        }
+    };
+    ensure_not_null!(ptr::null_mut(); column_name);
+
     let column_name = CStr::from_ptr(column_name).to_string_lossy().into_owned();
 
-    let v = v as *const u8;
-    let v = slice::from_raw_parts(v, v_len as usize);
-    let v = v.to_vec();
+    let v = if v_len <= 0 {
+        Vec::new()
+    } else if v.is_null() {
+        null_pointer_error("v");
+        return ptr::null_mut();
+    } else {
+        slice::from_raw_parts(v as *const u8, v_len as usize).to_vec()
+    };
 
-    let builder = Box::from_raw(builder);
     let ru_builder = RuSpringSourceRowBuilder::from(*builder);
-    let res_ru_builder = with_catch(|| ru_builder.add_column(column_name, v));
-    match res_ru_builder {
-        Ok(ru_builder) => SpringSourceRowBuilder::from(ru_builder).into_ptr(),
+    let res_builder = with_catch(|| {
+        SpringSourceRowBuilder::from(ru_builder.add_column(column_name, v)?).into_ptr()
+    });
+    match res_builder {
+        Ok(builder) => builder,
         Err(_) => ptr::null_mut(),
     }
 }
@@ -320,30 +1139,41 @@ pub unsafe extern "C" fn spring_source_row_add_column_blob(
 ///
 /// # Returns
 ///
-/// SpringSourceRow
+/// - non-NULL: SpringSourceRow
+/// - NULL: `builder` is a NULL pointer (`CNull`) or already freed (`CInvalidHandle`).
 #[no_mangle]
 pub unsafe extern "C" fn spring_source_row_build(
     builder: *mut SpringSourceRowBuilder,
 ) -> *mut SpringSourceRow {
-    let builder = Box::from_raw(builder);
+    ensure_not_null!(ptr::null_mut(); builder);
+    let builder = match handle::from_handle(builder) {
+        Some(builder) => builder,
+        None => {
+            invalid_handle_error("builder");
+            return ptr::null_mut();
+        }
+    };
+
     let ru_builder = RuSpringSourceRowBuilder::from(*builder);
-    SpringSourceRow::from(ru_builder.build()).into_ptr()
+    let result = with_catch(|| SpringSourceRow::from(ru_builder.build()).into_ptr());
+    match result {
+        Ok(source_row) => source_row,
+        Err(_) => ptr::null_mut(),
+    }
 }
 
 /// Frees heap occupied by a `SpringSinkRow`.
 ///
+/// Must not be called while another thread is still using `row`: it is not reference-counted.
+///
 /// # Returns
 ///
 /// - `Ok`: on success.
-/// - `CNull`: `pipeline` is a NULL pointer.
+/// - `CNull`: `row` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_sink_row_close(row: *mut SpringSinkRow) -> SpringErrno {
-    if row.is_null() {
-        SpringErrno::CNull
-    } else {
-        let _ = Box::from_raw(row);
-        SpringErrno::Ok
-    }
+    close_handle(row, "row")
 }
 
 /// Get a 2-byte integer column.
@@ -360,14 +1190,18 @@ pub unsafe extern "C" fn spring_sink_row_close(row: *mut SpringSinkRow) -> Sprin
 /// - `Unavailable`:
 ///   - Column pointed by `i_col` is already fetched.
 ///   - `i_col` is out of range.
-/// - `CNull`: Column value is NULL.
+/// - `Null`: Column value is NULL.
+/// - `CNull`: `row` or `out` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_column_short(
     row: *const SpringSinkRow,
     i_col: u16,
     out: *mut c_short,
 ) -> SpringErrno {
-    let row = &*row;
+    ensure_not_null!(SpringErrno::CNull; row, out);
+    ensure_live!(SpringErrno::CInvalidHandle; row);
+
     let i_col = i_col as usize;
     let result = with_catch(|| row.get_not_null_by_index(i_col));
     match result {
@@ -393,14 +1227,18 @@ pub unsafe extern "C" fn spring_column_short(
 /// - `Unavailable`:
 ///   - Column pointed by `i_col` is already fetched.
 ///   - `i_col` is out of range.
-/// - `CNull`: Column value is NULL.
+/// - `Null`: Column value is NULL.
+/// - `CNull`: `row` or `out` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_column_int(
     row: *const SpringSinkRow,
     i_col: u16,
     out: *mut c_int,
 ) -> SpringErrno {
-    let row = &*row;
+    ensure_not_null!(SpringErrno::CNull; row, out);
+    ensure_live!(SpringErrno::CInvalidHandle; row);
+
     let i_col = i_col as usize;
     let result = with_catch(|| row.get_not_null_by_index(i_col));
     match result {
@@ -426,14 +1264,18 @@ pub unsafe extern "C" fn spring_column_int(
 /// - `Unavailable`:
 ///   - Column pointed by `i_col` is already fetched.
 ///   - `i_col` is out of range.
-/// - `CNull`: Column value is NULL.
+/// - `Null`: Column value is NULL.
+/// - `CNull`: `row` or `out` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_column_long(
     row: *const SpringSinkRow,
     i_col: u16,
     out: *mut c_long,
 ) -> SpringErrno {
-    let row = &*row;
+    ensure_not_null!(SpringErrno::CNull; row, out);
+    ensure_live!(SpringErrno::CInvalidHandle; row);
+
     let i_col = i_col as usize;
     let result = with_catch(|| row.get_not_null_by_index(i_col));
     match result {
@@ -459,14 +1301,18 @@ pub unsafe extern "C" fn spring_column_long(
 /// - `Unavailable`:
 ///   - Column pointed by `i_col` is already fetched.
 ///   - `i_col` is out of range.
-/// - `CNull`: Column value is NULL.
+/// - `Null`: Column value is NULL.
+/// - `CNull`: `row` or `out` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_column_unsigned_int(
     row: *const SpringSinkRow,
     i_col: u16,
     out: *mut c_uint,
 ) -> SpringErrno {
-    let row = &*row;
+    ensure_not_null!(SpringErrno::CNull; row, out);
+    ensure_live!(SpringErrno::CInvalidHandle; row);
+
     let i_col = i_col as usize;
     let result = with_catch(|| row.get_not_null_by_index(i_col));
     match result {
@@ -493,7 +1339,9 @@ pub unsafe extern "C" fn spring_column_unsigned_int(
 /// - `Unavailable`:
 ///   - Column pointed by `i_col` is already fetched.
 ///   - `i_col` is out of range.
-/// - `CNull`: Column value is NULL.
+/// - `Null`: Column value is NULL.
+/// - `CNull`: `row` or `out` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_column_text(
     row: *const SpringSinkRow,
@@ -501,7 +1349,9 @@ pub unsafe extern "C" fn spring_column_text(
     out: *mut c_char,
     out_len: c_int,
 ) -> c_int {
-    let row = &*row;
+    ensure_not_null!(SpringErrno::CNull as c_int; row, out);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; row);
+
     let i_col = i_col as usize;
     let result: Result<String, SpringErrno> = with_catch(|| row.get_not_null_by_index(i_col));
     match result {
@@ -528,7 +1378,9 @@ pub unsafe extern "C" fn spring_column_text(
 /// - `Unavailable`:
 ///   - Column pointed by `i_col` is already fetched.
 ///   - `i_col` is out of range.
-/// - `CNull`: Column value is NULL.
+/// - `Null`: Column value is NULL.
+/// - `CNull`: `row` or `out` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_column_blob(
     row: *const SpringSinkRow,
@@ -536,7 +1388,9 @@ pub unsafe extern "C" fn spring_column_blob(
     out: *mut c_void,
     out_len: c_int,
 ) -> c_int {
-    let row = &*row;
+    ensure_not_null!(SpringErrno::CNull as c_int; row, out);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; row);
+
     let i_col = i_col as usize;
     let result: Result<Vec<u8>, SpringErrno> = with_catch(|| row.get_not_null_by_index(i_col));
     match result {
@@ -562,14 +1416,18 @@ pub unsafe extern "C" fn spring_column_blob(
 /// - `Unavailable`:
 ///   - Column pointed by `i_col` is already fetched.
 ///   - `i_col` is out of range.
-/// - `CNull`: Column value is NULL.
+/// - `Null`: Column value is NULL.
+/// - `CNull`: `row` or `out` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_column_bool(
     row: *const SpringSinkRow,
     i_col: u16,
     out: *mut bool,
 ) -> SpringErrno {
-    let row = &*row;
+    ensure_not_null!(SpringErrno::CNull; row, out);
+    ensure_live!(SpringErrno::CInvalidHandle; row);
+
     let i_col = i_col as usize;
     let result = with_catch(|| row.get_not_null_by_index(i_col));
     match result {
@@ -595,14 +1453,18 @@ pub unsafe extern "C" fn spring_column_bool(
 /// - `Unavailable`:
 ///   - Column pointed by `i_col` is already fetched.
 ///   - `i_col` is out of range.
-/// - `CNull`: Column value is NULL.
+/// - `Null`: Column value is NULL.
+/// - `CNull`: `row` or `out` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
 #[no_mangle]
 pub unsafe extern "C" fn spring_column_float(
     row: *const SpringSinkRow,
     i_col: u16,
     out: *mut c_float,
 ) -> SpringErrno {
-    let row = &*row;
+    ensure_not_null!(SpringErrno::CNull; row, out);
+    ensure_live!(SpringErrno::CInvalidHandle; row);
+
     let i_col = i_col as usize;
     let result = with_catch(|| row.get_not_null_by_index(i_col));
     match result {
@@ -614,17 +1476,47 @@ pub unsafe extern "C" fn spring_column_float(
     }
 }
 
-fn with_catch<F, R>(f: F) -> Result<R, SpringErrno>
+/// Negative `timeout_ms` means no timeout.
+fn timeout_from_msec(timeout_ms: c_int) -> Option<Duration> {
+    u64::try_from(timeout_ms).ok().map(Duration::from_millis)
+}
+
+/// Frees a handle created by `handle::into_handle()`.
+unsafe fn close_handle<T>(ptr: *mut T, arg: &'static str) -> SpringErrno {
+    if ptr.is_null() {
+        return null_pointer_error(arg);
+    }
+    match handle::from_handle(ptr) {
+        Some(v) => match with_catch(AssertUnwindSafe(move || {
+            drop(v);
+            Ok::<_, LastError>(())
+        })) {
+            Ok(()) => SpringErrno::Ok,
+            Err(e) => e,
+        },
+        None => invalid_handle_error(arg),
+    }
+}
+
+/// Calls `f` and sets the last error if it returns an error or panics.
+///
+/// The panic message, location and backtrace are available from `spring_last_err()`.
+fn with_catch<F, R, E>(f: F) -> Result<R, SpringErrno>
 where
-    F: FnOnce() -> Result<R, SpringError> + UnwindSafe,
+    F: FnOnce() -> Result<R, E> + UnwindSafe,
+    E: Into<LastError>,
 {
-    catch_unwind(|| f().map_err(LastError::SpringErr))
-        .unwrap_or_else(|panic_err| Err(LastError::UnwindErr(panic_err)))
+    panic_hook::install();
+
+    catch_unwind(|| f().map_err(Into::into))
+        .unwrap_or_else(|panic_err| {
+            Err(LastError::UnwindErr(
+                panic_err,
+                panic_hook::take_last_panic(),
+            ))
+        })
         .map_err(|last_err| {
-            let errno = match &last_err {
-                LastError::SpringErr(e) => SpringErrno::from(e),
-                LastError::UnwindErr(_) => SpringErrno::Unknown,
-            };
+            let errno = SpringErrno::from(&last_err);
             update_last_error(last_err);
             errno
         })
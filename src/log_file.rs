@@ -0,0 +1,82 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Log file rotated by size.
+
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Appends to `path`. When `path` gets larger than `max_len`, renames it to `path.1` (`path.1` to `path.2`, and so on)
+/// and starts a new `path`. At most `n_rotated` old files are kept.
+#[derive(Debug)]
+pub(crate) struct RotatingFile {
+    path: PathBuf,
+    max_len: u64,
+    n_rotated: usize,
+
+    file: File,
+    len: u64,
+}
+
+impl RotatingFile {
+    pub(crate) fn open(path: PathBuf, max_len: u64, n_rotated: usize) -> io::Result<Self> {
+        let file = Self::open_append(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_len,
+            n_rotated,
+            file,
+            len,
+        })
+    }
+
+    fn open_append(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// `path.i`
+    fn rotated_path(&self, i: usize) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{}", i));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.n_rotated == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for i in (1..self.n_rotated).rev() {
+                match fs::rename(self.rotated_path(i), self.rotated_path(i + 1)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+
+        self.file = Self::open_append(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.len > 0 && self.len + buf.len() as u64 > self.max_len {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.len += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -0,0 +1,108 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Logger of this process, which forwards records to a backend replaceable from C.
+//!
+//! SpringQL-core installs `env_logger` on the first pipeline creation unless another logger is installed.
+//! This logger is installed before that, with `env_logger` as the default backend.
+//! Warnings and errors are also passed to `worker_monitor` regardless of the backend.
+
+use std::sync::{Arc, OnceLock, RwLock};
+
+use ::log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::worker_monitor;
+
+/// Where records go.
+#[derive(Clone)]
+pub(crate) struct Backend {
+    logger: Arc<dyn Log>,
+    max_level: LevelFilter,
+}
+
+impl Backend {
+    pub(crate) fn new(logger: impl Log + 'static, max_level: LevelFilter) -> Self {
+        Self {
+            logger: Arc::new(logger),
+            max_level,
+        }
+    }
+
+    /// `env_logger` configured by `RUST_LOG` environment variable (as SpringQL-core does).
+    fn env_logger() -> Self {
+        let logger = env_logger::Builder::from_default_env().build();
+        let max_level = logger.filter();
+        Self::new(logger, max_level)
+    }
+}
+
+static BACKEND: RwLock<Option<Backend>> = RwLock::new(None);
+
+struct ProxyLogger;
+
+static LOGGER: ProxyLogger = ProxyLogger;
+
+impl ProxyLogger {
+    fn backend(&self) -> Option<Backend> {
+        BACKEND
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+impl Log for ProxyLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= Level::Warn
+            || self
+                .backend()
+                .is_some_and(|backend| backend.logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        worker_monitor::on_log(record);
+
+        // the lock is not held while the backend runs, so that the backend can replace itself
+        if let Some(backend) = self.backend() {
+            if record.level() <= backend.max_level {
+                backend.logger.log(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(backend) = self.backend() {
+            backend.logger.flush();
+        }
+    }
+}
+
+/// Installs the logger with the default backend if not yet.
+///
+/// # Returns
+///
+/// `false` if another logger had been installed in this process.
+pub(crate) fn install() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+
+    *INSTALLED.get_or_init(|| {
+        let installed = ::log::set_logger(&LOGGER).is_ok();
+        if installed {
+            set_backend(Backend::env_logger());
+        }
+        installed
+    })
+}
+
+/// Replaces the backend. The logger must be installed in advance.
+pub(crate) fn set_backend(backend: Backend) {
+    // warnings and errors are always needed by `worker_monitor`
+    ::log::set_max_level(backend.max_level.max(LevelFilter::Warn));
+    *BACKEND
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(backend);
+}
+
+/// Replaces the backend with the default one.
+pub(crate) fn reset_backend() {
+    set_backend(Backend::env_logger());
+}
@@ -0,0 +1,151 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Memory state of a pipeline, replicated on the client side.
+//!
+//! SpringQL-core does not expose its memory state machine. This one follows the same transitions
+//! from memory usage in metrics reports, so it may lag behind SpringQL-core by the report interval.
+
+use std::{ffi::c_void, sync::Mutex};
+
+use ::springql::SpringMemoryConfig;
+
+use crate::spring_memory::{MemoryStateCallback, SpringMemoryState, SpringMemoryStateCallback};
+
+/// Opaque pointer passed to a callback as-is.
+#[derive(Copy, Clone)]
+struct UserData(*mut c_void);
+
+// The library never dereferences `user_data`. Thread safety of the pointee is up to the caller.
+unsafe impl Send for UserData {}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Threshold {
+    moderate_to_severe_bytes: u64,
+    severe_to_critical_bytes: u64,
+    critical_to_severe_bytes: u64,
+    severe_to_moderate_bytes: u64,
+}
+
+impl From<&SpringMemoryConfig> for Threshold {
+    fn from(c: &SpringMemoryConfig) -> Self {
+        // same as SpringQL-core
+        let bytes_from_percent =
+            |percent: u8| (c.upper_limit_bytes as f32 * percent as f32 * 0.01) as u64;
+        Self {
+            moderate_to_severe_bytes: bytes_from_percent(c.moderate_to_severe_percent),
+            severe_to_critical_bytes: bytes_from_percent(c.severe_to_critical_percent),
+            critical_to_severe_bytes: bytes_from_percent(c.critical_to_severe_percent),
+            severe_to_moderate_bytes: bytes_from_percent(c.severe_to_moderate_percent),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Usage {
+    used_bytes: u64,
+    state: SpringMemoryState,
+}
+
+pub(crate) struct MemoryMonitor {
+    upper_limit_bytes: u64,
+    threshold: Threshold,
+    usage: Mutex<Usage>,
+    callback: Mutex<Option<(MemoryStateCallback, UserData)>>,
+}
+
+impl std::fmt::Debug for MemoryMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryMonitor")
+            .field("upper_limit_bytes", &self.upper_limit_bytes)
+            .field("threshold", &self.threshold)
+            .field("usage", &self.usage)
+            .finish()
+    }
+}
+
+impl MemoryMonitor {
+    pub(crate) fn new(config: &SpringMemoryConfig) -> Self {
+        Self {
+            upper_limit_bytes: config.upper_limit_bytes,
+            threshold: Threshold::from(config),
+            usage: Mutex::new(Usage {
+                used_bytes: 0,
+                state: SpringMemoryState::Moderate,
+            }),
+            callback: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn upper_limit_bytes(&self) -> u64 {
+        self.upper_limit_bytes
+    }
+
+    /// Memory usage in bytes and the memory state.
+    pub(crate) fn usage(&self) -> (u64, SpringMemoryState) {
+        let usage = self
+            .usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        (usage.used_bytes, usage.state)
+    }
+
+    pub(crate) fn set_callback(&self, callback: SpringMemoryStateCallback, user_data: *mut c_void) {
+        *self
+            .callback
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            callback.map(|callback| (callback, UserData(user_data)));
+    }
+
+    /// Updates the memory usage and calls the callback if the state changes.
+    pub(crate) fn update(&self, used_bytes: u64) {
+        let transition = {
+            let mut usage = self
+                .usage
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            usage.used_bytes = used_bytes;
+            let next_state = self.next_state(usage.state, used_bytes);
+            (next_state != usage.state).then(|| {
+                usage.state = next_state;
+                next_state
+            })
+        };
+
+        if let Some(state) = transition {
+            log::info!(
+                "memory state transition to {:?} ({} / {} bytes)",
+                state,
+                used_bytes,
+                self.upper_limit_bytes
+            );
+
+            let callback = *self
+                .callback
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some((callback, user_data)) = callback {
+                unsafe { callback(state, used_bytes, self.upper_limit_bytes, user_data.0) };
+            }
+        }
+    }
+
+    fn next_state(&self, state: SpringMemoryState, used_bytes: u64) -> SpringMemoryState {
+        let t = &self.threshold;
+        match state {
+            SpringMemoryState::Moderate if used_bytes > t.moderate_to_severe_bytes => {
+                SpringMemoryState::Severe
+            }
+            SpringMemoryState::Severe if used_bytes > t.severe_to_critical_bytes => {
+                SpringMemoryState::Critical
+            }
+            SpringMemoryState::Severe if used_bytes < t.severe_to_moderate_bytes => {
+                SpringMemoryState::Moderate
+            }
+            SpringMemoryState::Critical if used_bytes < t.critical_to_severe_bytes => {
+                SpringMemoryState::Severe
+            }
+            _ => state,
+        }
+    }
+}
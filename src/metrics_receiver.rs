@@ -0,0 +1,151 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Embedded HTTP endpoint receiving performance metrics reports from SpringQL-core.
+//!
+//! SpringQL-core exposes its metrics only by POSTing them to the [web console](https://github.com/SpringQL/web-console/blob/main/doc/api.md)
+//! every `web_console.report_interval_msec`. If `client.enable_metrics_receiver` is true and the web console is not enabled
+//! in the config, `spring_open()` points the reports to this endpoint on `127.0.0.1`.
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use ::serde_json::Value;
+
+use crate::http_server::{HttpServer, Response};
+
+/// Metrics of a task (source, pump or sink).
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct TaskReport {
+    pub(crate) id: String,
+    /// `source-task`, `pump-task` or `sink-task`.
+    pub(crate) type_: String,
+    pub(crate) avg_gain_bytes_per_sec: f64,
+}
+
+/// Metrics of a queue between tasks.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct QueueReport {
+    pub(crate) id: String,
+    pub(crate) upstream_task_id: String,
+    pub(crate) downstream_task_id: String,
+    /// Window queues hold rows waiting for their windows to close.
+    pub(crate) is_window: bool,
+    pub(crate) rows: u64,
+    pub(crate) bytes: u64,
+}
+
+/// A metrics report from SpringQL-core.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct PipelineReport {
+    pub(crate) received_at: Instant,
+    pub(crate) tasks: Vec<TaskReport>,
+    pub(crate) queues: Vec<QueueReport>,
+}
+
+impl PipelineReport {
+    /// Parses a request body of `POST /task-graph`.
+    fn parse(body: &[u8]) -> Option<Self> {
+        let body: Value = ::serde_json::from_slice(body).ok()?;
+
+        let tasks = body["tasks"]
+            .as_array()?
+            .iter()
+            .map(|task| {
+                Some(TaskReport {
+                    id: task["id"].as_str()?.to_string(),
+                    type_: task["type"].as_str()?.to_string(),
+                    avg_gain_bytes_per_sec: task["avg-gain-bytes-per-sec"].as_f64()?,
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        let queues = body["queues"]
+            .as_array()?
+            .iter()
+            .map(|queue| {
+                let (is_window, inner, rows_key) = if queue["row-queue"].is_object() {
+                    (false, &queue["row-queue"], "num-rows")
+                } else {
+                    (true, &queue["window-queue"], "num-rows-waiting")
+                };
+                Some(QueueReport {
+                    id: queue["id"].as_str()?.to_string(),
+                    upstream_task_id: queue["upstream-task-id"].as_str()?.to_string(),
+                    downstream_task_id: queue["downstream-task-id"].as_str()?.to_string(),
+                    is_window,
+                    rows: inner[rows_key].as_u64()?,
+                    bytes: inner["total-bytes"].as_u64()?,
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            received_at: Instant::now(),
+            tasks,
+            queues,
+        })
+    }
+
+    /// Total bytes of rows in queues, which SpringQL-core compares with `memory.upper_limit_bytes`.
+    pub(crate) fn queue_total_bytes(&self) -> u64 {
+        self.queues.iter().map(|queue| queue.bytes).sum()
+    }
+}
+
+/// Called on every report, on the receiver thread.
+pub(crate) type ReportObserver = Box<dyn Fn(&PipelineReport) + Send>;
+
+/// Receives reports on a library-owned thread and keeps the latest one.
+#[derive(Debug)]
+pub(crate) struct MetricsReceiver {
+    latest: Arc<Mutex<Option<PipelineReport>>>,
+    server: HttpServer,
+}
+
+impl MetricsReceiver {
+    /// Starts listening on an ephemeral port of `127.0.0.1`.
+    pub(crate) fn start(observer: ReportObserver) -> io::Result<Self> {
+        let latest = Arc::new(Mutex::new(None));
+
+        let server = {
+            let latest = latest.clone();
+            HttpServer::start(
+                "127.0.0.1:0",
+                "springql-client-metrics-receiver",
+                move |request| match (request.method.as_str(), request.path.as_str()) {
+                    ("POST", "/task-graph") => match PipelineReport::parse(&request.body) {
+                        Some(report) => {
+                            observer(&report);
+                            *latest
+                                .lock()
+                                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(report);
+                            Response::ok("text/plain", String::new())
+                        }
+                        None => {
+                            log::warn!("ignored an invalid metrics report");
+                            Response::bad_request("invalid metrics report")
+                        }
+                    },
+                    _ => Response::not_found(),
+                },
+            )?
+        };
+
+        Ok(Self { latest, server })
+    }
+
+    pub(crate) fn port(&self) -> u16 {
+        self.server.local_addr().port()
+    }
+
+    /// The latest report. `None` until the first report arrives.
+    pub(crate) fn latest(&self) -> Option<PipelineReport> {
+        self.latest
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
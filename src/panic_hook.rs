@@ -0,0 +1,88 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Panic hook to record where a panic occurred, so that `with_catch()` can tell it to C.
+
+use std::{
+    backtrace::Backtrace,
+    cell::RefCell,
+    panic::{self, PanicHookInfo},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use crate::worker_monitor;
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<PanicDetail>> = const { RefCell::new(None) };
+}
+
+/// Where a panic occurred.
+#[derive(Debug)]
+pub(crate) struct PanicDetail {
+    /// `file:line:column`
+    pub(crate) location: Option<String>,
+    pub(crate) backtrace: Backtrace,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum HookState {
+    NotInstalled,
+    /// SpringQL-core replaces the panic hook on the first `spring_open()`.
+    InstalledBeforeCore,
+    InstalledAfterCore,
+}
+
+static HOOK_STATE: Mutex<HookState> = Mutex::new(HookState::NotInstalled);
+
+/// Whether `HOOK_STATE` is not `NotInstalled`, so that `install()` does not lock it on every call.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the panic hook if not yet.
+pub(crate) fn install() {
+    if INSTALLED.load(Ordering::Acquire) {
+        return;
+    }
+
+    let mut state = HOOK_STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if *state == HookState::NotInstalled {
+        set_hook();
+        *state = HookState::InstalledBeforeCore;
+    }
+    INSTALLED.store(true, Ordering::Release);
+}
+
+/// Installs the panic hook again after SpringQL-core set its own one.
+pub(crate) fn install_after_core() {
+    let mut state = HOOK_STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if *state != HookState::InstalledAfterCore {
+        set_hook();
+        *state = HookState::InstalledAfterCore;
+    }
+    INSTALLED.store(true, Ordering::Release);
+}
+
+/// Takes where the last panic in the current thread occurred.
+pub(crate) fn take_last_panic() -> Option<PanicDetail> {
+    LAST_PANIC.with(|last_panic| last_panic.borrow_mut().take())
+}
+
+/// Records the panic (also as a worker failure) and then calls the previous hook (which logs the panic).
+fn set_hook() {
+    let prev_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info: &PanicHookInfo<'_>| {
+        let detail = PanicDetail {
+            location: info.location().map(|location| location.to_string()),
+            backtrace: Backtrace::force_capture(),
+        };
+        LAST_PANIC.with(|last_panic| *last_panic.borrow_mut() = Some(detail));
+        worker_monitor::on_panic(info);
+
+        prev_hook(info);
+    }));
+}
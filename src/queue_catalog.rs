@@ -0,0 +1,129 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! In-memory queues declared in a pipeline, tracked from `CREATE SOURCE READER` / `CREATE SINK WRITER` statements.
+//!
+//! SpringQL-core does not tell if a queue exists without popping from it, so queues are recorded
+//! when a statement creating an `IN_MEMORY_QUEUE` reader or writer succeeds.
+
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum QueueKind {
+    Source,
+    Sink,
+}
+
+/// An in-memory queue created by a statement.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct QueueDecl {
+    pub(crate) name: String,
+    pub(crate) kind: QueueKind,
+    /// All `OPTIONS` in the statement, including `NAME`.
+    pub(crate) options: HashMap<String, String>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum Token<'a> {
+    /// Identifier or keyword.
+    Word(&'a str),
+    /// Content of a string constant.
+    Str(&'a str),
+    Punct(char),
+}
+
+impl Token<'_> {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == '\'' {
+            let start = i + 1;
+            let mut end = sql.len();
+            for (j, c) in chars.by_ref() {
+                if c == '\'' {
+                    end = j;
+                    break;
+                }
+            }
+            tokens.push(Token::Str(&sql[start..end]));
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut end = i + c.len_utf8();
+            while let Some(&(j, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = j + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Word(&sql[i..end]));
+        } else {
+            tokens.push(Token::Punct(c));
+        }
+    }
+    tokens
+}
+
+/// Parses a statement creating an `IN_MEMORY_QUEUE` reader or writer.
+///
+/// # Returns
+///
+/// `None` for other statements. The statement is assumed to be accepted by SpringQL-core.
+pub(crate) fn parse_queue_decl(sql: &str) -> Option<QueueDecl> {
+    let tokens = tokenize(sql);
+
+    let kind = match tokens.get(..3)? {
+        [create, source, reader]
+            if create.is_keyword("CREATE")
+                && source.is_keyword("SOURCE")
+                && reader.is_keyword("READER") =>
+        {
+            QueueKind::Source
+        }
+        [create, sink, writer]
+            if create.is_keyword("CREATE")
+                && sink.is_keyword("SINK")
+                && writer.is_keyword("WRITER") =>
+        {
+            QueueKind::Sink
+        }
+        _ => return None,
+    };
+
+    // `<reader or writer> FOR <stream> TYPE <type> OPTIONS (<name> '<value>', ...)`
+    let type_at = tokens.iter().position(|t| t.is_keyword("TYPE"))?;
+    // types are case sensitive in SpringQL-core
+    if tokens.get(type_at + 1)? != &Token::Word("IN_MEMORY_QUEUE") {
+        return None;
+    }
+
+    let mut options = HashMap::new();
+    if tokens
+        .get(type_at + 2)
+        .is_some_and(|t| t.is_keyword("OPTIONS"))
+    {
+        for option in tokens[type_at + 3..].split(|t| *t == Token::Punct(',')) {
+            let option: Vec<_> = option
+                .iter()
+                .filter(|t| !matches!(t, Token::Punct('(' | ')' | ';')))
+                .collect();
+            if let [Token::Word(key), Token::Str(value)] = option.as_slice() {
+                options.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+
+    Some(QueueDecl {
+        // options are case sensitive in SpringQL-core
+        name: options.get("NAME")?.clone(),
+        kind,
+        options,
+    })
+}
@@ -0,0 +1,303 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    os::unix::{io::AsRawFd, net::UnixStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use ::log::warn;
+use ::springql::{
+    error::SpringError, Result, SpringPipeline as RuSpringPipeline,
+    SpringSinkRow as RuSpringSinkRow,
+};
+
+use crate::{spring_last_err::LastError, spring_queue_stats::QueueCounters};
+
+/// Sleep between non-blocking pops from SpringQL-core while its queue is empty.
+/// Doubled on each empty pop up to `MAX_POP_POLL_INTERVAL`, and reset when a row arrives.
+const MIN_POP_POLL_INTERVAL: Duration = Duration::from_millis(1);
+const MAX_POP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Rows buffered on the C client side at most.
+/// Further rows are left in SpringQL-core, where they count toward its memory usage.
+const MAX_BUFFERED_ROWS: usize = 1024;
+
+/// A failure to pop from SpringQL-core is logged at most once in this interval.
+const POP_ERROR_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Moves rows from an in-memory sink queue in SpringQL-core to a bounded buffer on the C client side.
+///
+/// Rows in the buffer can be waited without polling, and a file descriptor is readable while the buffer has rows.
+#[derive(Debug)]
+pub(crate) struct SinkQueueDispatcher {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    queue: String,
+    state: Mutex<State>,
+    state_changed: Condvar,
+    /// Notified when the buffer gets room or the dispatcher stops.
+    space_available: Condvar,
+    stopped: AtomicBool,
+    counters: Arc<QueueCounters>,
+
+    /// Readable while `rows` is not empty. Holds 1 byte in that case.
+    notify_rx: UnixStream,
+    notify_tx: UnixStream,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    rows: VecDeque<RuSpringSinkRow>,
+
+    /// Incremented to interrupt blocking pops waiting at that time.
+    interrupt_generation: u64,
+    /// Interrupts all blocking pops, including future ones.
+    interrupted: bool,
+}
+
+impl SinkQueueDispatcher {
+    /// Starts a dispatcher thread for `queue`.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - queue named `queue` does not exist.
+    pub(crate) fn start(
+        pipeline: Arc<RuSpringPipeline>,
+        queue: &str,
+        counters: Arc<QueueCounters>,
+    ) -> Result<Self> {
+        // also validates the queue exists
+        let first_row = pipeline.pop_non_blocking(queue)?;
+
+        let (notify_rx, notify_tx) =
+            UnixStream::pair().expect("failed to create a socket pair for notification");
+        notify_rx
+            .set_nonblocking(true)
+            .expect("failed to set a socket non-blocking");
+        notify_tx
+            .set_nonblocking(true)
+            .expect("failed to set a socket non-blocking");
+
+        let shared = Arc::new(Shared {
+            queue: queue.to_string(),
+            state: Mutex::default(),
+            state_changed: Condvar::new(),
+            space_available: Condvar::new(),
+            stopped: AtomicBool::new(false),
+            counters,
+            notify_rx,
+            notify_tx,
+        });
+        if let Some(row) = first_row {
+            shared.push(row);
+        }
+
+        let worker = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name(format!("springql-client-dispatcher-{}", queue))
+                .spawn(move || shared.run(&pipeline))
+                .expect("failed to spawn a dispatcher thread")
+        };
+
+        Ok(Self {
+            shared,
+            worker: Some(worker),
+        })
+    }
+
+    /// File descriptor readable while the buffer has rows.
+    pub(crate) fn fd(&self) -> i32 {
+        self.shared.notify_rx.as_raw_fd()
+    }
+
+    /// Number of rows in the buffer.
+    pub(crate) fn depth(&self) -> usize {
+        self.shared.lock().rows.len()
+    }
+
+    pub(crate) fn pop_non_blocking(&self) -> Option<RuSpringSinkRow> {
+        let mut state = self.shared.lock();
+        self.shared.take(&mut state)
+    }
+
+    /// # Returns
+    ///
+    /// - `Ok(Some)` when a row is popped.
+    /// - `Ok(None)` when `timeout` elapsed. `timeout == None` waits forever.
+    ///
+    /// # Failure
+    ///
+    /// - `LastError::Interrupted` when:
+    ///   - `interrupt()` or `interrupt_all()` is called while waiting.
+    ///   - `interrupt_all()` is already called.
+    pub(crate) fn pop_timeout(
+        &self,
+        timeout: Option<Duration>,
+    ) -> std::result::Result<Option<RuSpringSinkRow>, LastError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        let mut state = self.shared.lock();
+        let generation = state.interrupt_generation;
+        loop {
+            if state.interrupted || state.interrupt_generation != generation {
+                return Err(LastError::Interrupted(self.shared.queue.clone()));
+            }
+            if let Some(row) = self.shared.take(&mut state) {
+                return Ok(Some(row));
+            }
+
+            state = match deadline {
+                None => self
+                    .shared
+                    .state_changed
+                    .wait(state)
+                    .expect("another thread sharing the same dispatcher got panic"),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(None);
+                    }
+                    self.shared
+                        .state_changed
+                        .wait_timeout(state, deadline - now)
+                        .expect("another thread sharing the same dispatcher got panic")
+                        .0
+                }
+            };
+        }
+    }
+
+    /// Interrupts blocking pops waiting now.
+    pub(crate) fn interrupt(&self) {
+        let mut state = self.shared.lock();
+        state.interrupt_generation += 1;
+        self.shared.state_changed.notify_all();
+    }
+
+    /// Interrupts blocking pops waiting now and all blocking pops afterwards.
+    pub(crate) fn interrupt_all(&self) {
+        let mut state = self.shared.lock();
+        state.interrupted = true;
+        self.shared.state_changed.notify_all();
+    }
+}
+
+impl Drop for SinkQueueDispatcher {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        {
+            let _state = self.shared.lock();
+            self.shared.space_available.notify_all();
+        }
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Shared {
+    fn run(&self, pipeline: &RuSpringPipeline) {
+        let mut poll_interval = MIN_POP_POLL_INTERVAL;
+        let mut pop_errors = PopErrorLog::default();
+
+        while self.wait_for_space() {
+            match pipeline.pop_non_blocking(&self.queue) {
+                Ok(Some(row)) => {
+                    self.push(row);
+                    poll_interval = MIN_POP_POLL_INTERVAL;
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => pop_errors.log(&self.queue, &e),
+            }
+            thread::sleep(poll_interval);
+            poll_interval = (poll_interval * 2).min(MAX_POP_POLL_INTERVAL);
+        }
+    }
+
+    /// Waits until the buffer has room.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the dispatcher is stopped.
+    fn wait_for_space(&self) -> bool {
+        let mut state = self.lock();
+        loop {
+            if self.stopped.load(Ordering::Acquire) {
+                return false;
+            }
+            if state.rows.len() < MAX_BUFFERED_ROWS {
+                return true;
+            }
+            state = self
+                .space_available
+                .wait(state)
+                .expect("another thread sharing the same dispatcher got panic");
+        }
+    }
+
+    fn push(&self, row: RuSpringSinkRow) {
+        let mut state = self.lock();
+        if state.rows.is_empty() {
+            let _ = (&self.notify_tx).write(&[1]);
+        }
+        state.rows.push_back(row);
+        self.state_changed.notify_one();
+    }
+
+    fn take(&self, state: &mut State) -> Option<RuSpringSinkRow> {
+        let row = state.rows.pop_front()?;
+        if state.rows.is_empty() {
+            let _ = (&self.notify_rx).read(&mut [0]);
+        }
+        if state.rows.len() + 1 == MAX_BUFFERED_ROWS {
+            self.space_available.notify_one();
+        }
+        self.counters.popped(&row);
+        Some(row)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("another thread sharing the same dispatcher got panic")
+    }
+}
+
+/// Rate-limited log of failures to pop from SpringQL-core.
+#[derive(Debug, Default)]
+struct PopErrorLog {
+    last_logged_at: Option<Instant>,
+    suppressed: u64,
+}
+
+impl PopErrorLog {
+    fn log(&mut self, queue: &str, e: &SpringError) {
+        if self
+            .last_logged_at
+            .is_some_and(|at| at.elapsed() < POP_ERROR_LOG_INTERVAL)
+        {
+            self.suppressed += 1;
+            return;
+        }
+        warn!(
+            "[SinkQueueDispatcher] failed to pop from {}: {:?} ({} more failures since the last log)",
+            queue, e, self.suppressed
+        );
+        self.last_logged_at = Some(Instant::now());
+        self.suppressed = 0;
+    }
+}
@@ -0,0 +1,91 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::c_void,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{sink_queue_dispatcher::SinkQueueDispatcher, spring_sink_row::SpringSinkRow};
+
+/// How long a subscriber thread waits for a row before checking if it is unsubscribed.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Callback to receive rows from an in memory sink queue.
+///
+/// `row` must be freed by `spring_sink_row_close()`.
+pub type SpringSinkRowCallback =
+    Option<unsafe extern "C" fn(row: *mut SpringSinkRow, user_data: *mut c_void)>;
+
+/// Non-NULL `SpringSinkRowCallback`.
+pub(crate) type SinkRowCallback =
+    unsafe extern "C" fn(row: *mut SpringSinkRow, user_data: *mut c_void);
+
+/// Opaque pointer passed to a callback as-is.
+struct UserData(*mut c_void);
+
+// The library never dereferences `user_data`. Thread safety of the pointee is up to the caller.
+unsafe impl Send for UserData {}
+
+/// Delivers rows from a dispatcher to a C callback on a library-owned thread.
+#[derive(Debug)]
+pub(crate) struct SinkQueueSubscription {
+    stopped: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl SinkQueueSubscription {
+    pub(crate) fn start(
+        dispatcher: Arc<SinkQueueDispatcher>,
+        queue: &str,
+        callback: SinkRowCallback,
+        user_data: *mut c_void,
+    ) -> Self {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let user_data = UserData(user_data);
+
+        let worker = {
+            let stopped = stopped.clone();
+            thread::Builder::new()
+                .name(format!("springql-client-subscriber-{}", queue))
+                .spawn(move || {
+                    let user_data = user_data;
+                    while !stopped.load(Ordering::Acquire) {
+                        match dispatcher.pop_timeout(Some(STOP_CHECK_INTERVAL)) {
+                            Ok(Some(row)) => match SpringSinkRow::from(row).into_ptr() {
+                                Ok(row) => unsafe { callback(row, user_data.0) },
+                                Err(e) => {
+                                    log::warn!("dropped a row from a subscribed queue: {:?}", e)
+                                }
+                            },
+                            Ok(None) => {}
+                            // the pipeline is interrupted to be closed
+                            Err(_) => break,
+                        }
+                    }
+                })
+                .expect("failed to spawn a subscriber thread")
+        };
+
+        Self {
+            stopped,
+            worker: Some(worker),
+        }
+    }
+}
+
+impl Drop for SinkQueueSubscription {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+        if let Some(worker) = self.worker.take() {
+            // Unsubscribed from the callback. The thread finishes after the callback returns.
+            if worker.thread().id() != thread::current().id() {
+                let _ = worker.join();
+            }
+        }
+    }
+}
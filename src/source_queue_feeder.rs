@@ -0,0 +1,216 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Bounded staging of rows pushed to an in-memory source queue, with an overflow policy.
+//!
+//! SpringQL-core accepts any number of rows into an in-memory source queue and does not tell how many are left.
+//! Rows are staged here and forwarded to SpringQL-core while the memory state of the pipeline is `Moderate`,
+//! so that the staging fills up (and the overflow policy applies) when the pipeline cannot keep up.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use ::log::warn;
+use ::springql::{SpringPipeline as RuSpringPipeline, SpringSourceRow as RuSpringSourceRow};
+
+use crate::{
+    memory_monitor::MemoryMonitor,
+    spring_last_err::LastError,
+    spring_memory::SpringMemoryState,
+    spring_overflow_policy::{OverflowConfig, SpringOverflowPolicy},
+    spring_queue_stats::QueueCounters,
+};
+
+/// How often to check the memory state while rows are held.
+const MEMORY_STATE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug)]
+pub(crate) struct SourceQueueFeeder {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    queue: String,
+    state: Mutex<State>,
+    /// Notified when a row is staged or the feeder stops.
+    not_empty: Condvar,
+    /// Notified when a row leaves the staging or the feeder stops.
+    not_full: Condvar,
+    stopped: AtomicBool,
+    counters: Arc<QueueCounters>,
+    memory_monitor: Arc<MemoryMonitor>,
+}
+
+#[derive(Debug)]
+struct State {
+    config: OverflowConfig,
+    rows: VecDeque<RuSpringSourceRow>,
+}
+
+impl SourceQueueFeeder {
+    /// Starts a feeder thread for `queue`.
+    pub(crate) fn start(
+        pipeline: Arc<RuSpringPipeline>,
+        queue: &str,
+        config: OverflowConfig,
+        counters: Arc<QueueCounters>,
+        memory_monitor: Arc<MemoryMonitor>,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            queue: queue.to_string(),
+            state: Mutex::new(State {
+                config,
+                rows: VecDeque::new(),
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            stopped: AtomicBool::new(false),
+            counters,
+            memory_monitor,
+        });
+
+        let worker = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name(format!("springql-client-feeder-{}", queue))
+                .spawn(move || shared.run(&pipeline))
+                .expect("failed to spawn a feeder thread")
+        };
+
+        Self {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Replaces the overflow policy. Rows already staged over the new capacity are kept.
+    pub(crate) fn set_config(&self, config: OverflowConfig) {
+        self.shared.lock().config = config;
+        self.shared.not_full.notify_all();
+    }
+
+    /// Stages a row, applying the overflow policy if the staging is full.
+    ///
+    /// # Failure
+    ///
+    /// - `LastError::WouldBlock` when:
+    ///   - the staging is full with `Fail` policy.
+    ///   - the staging is still full after the timeout with `Block` policy.
+    pub(crate) fn push(&self, row: RuSpringSourceRow) -> Result<(), LastError> {
+        let shared = &self.shared;
+        let mut state = shared.lock();
+
+        if state.rows.len() >= state.config.capacity {
+            match state.config.policy {
+                SpringOverflowPolicy::Block => {
+                    let deadline = Instant::now() + state.config.block_timeout;
+                    while state.rows.len() >= state.config.capacity {
+                        let now = Instant::now();
+                        if now >= deadline || shared.stopped.load(Ordering::Acquire) {
+                            return Err(LastError::WouldBlock(shared.queue.clone()));
+                        }
+                        state = shared
+                            .not_full
+                            .wait_timeout(state, deadline - now)
+                            .expect("another thread sharing the same feeder got panic")
+                            .0;
+                    }
+                }
+                SpringOverflowPolicy::DropOldest => {
+                    state.rows.pop_front();
+                    shared.counters.add_dropped(1);
+                }
+                SpringOverflowPolicy::DropNewest => {
+                    shared.counters.add_dropped(1);
+                    return Ok(());
+                }
+                SpringOverflowPolicy::Fail => {
+                    return Err(LastError::WouldBlock(shared.queue.clone()));
+                }
+            }
+        }
+
+        state.rows.push_back(row);
+        shared.counters.add_pushed(1);
+        shared.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Number of rows staged.
+    pub(crate) fn depth(&self) -> usize {
+        self.shared.lock().rows.len()
+    }
+}
+
+impl Drop for SourceQueueFeeder {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        {
+            let _state = self.shared.lock();
+            self.shared.not_empty.notify_all();
+            self.shared.not_full.notify_all();
+        }
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Shared {
+    fn run(&self, pipeline: &RuSpringPipeline) {
+        while let Some(row) = self.next_row() {
+            // SpringQL-core only fails if the queue does not exist, which is checked before the feeder starts
+            if let Err(e) = pipeline.push(&self.queue, row) {
+                warn!(
+                    "[SourceQueueFeeder] failed to push to {}: {:?}",
+                    self.queue, e
+                );
+                self.counters.add_dropped(1);
+            }
+        }
+    }
+
+    /// Waits until a row is staged and the memory state is `Moderate`, and takes the row.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the feeder is stopped.
+    fn next_row(&self) -> Option<RuSpringSourceRow> {
+        let mut state = self.lock();
+        loop {
+            if self.stopped.load(Ordering::Acquire) {
+                return None;
+            }
+            if state.rows.is_empty() {
+                state = self
+                    .not_empty
+                    .wait(state)
+                    .expect("another thread sharing the same feeder got panic");
+            } else if self.memory_monitor.usage().1 != SpringMemoryState::Moderate {
+                state = self
+                    .not_empty
+                    .wait_timeout(state, MEMORY_STATE_POLL_INTERVAL)
+                    .expect("another thread sharing the same feeder got panic")
+                    .0;
+            } else {
+                let row = state.rows.pop_front();
+                self.not_full.notify_one();
+                return row;
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("another thread sharing the same feeder got panic")
+    }
+}
@@ -0,0 +1,145 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::c_void,
+    os::raw::{c_char, c_int},
+};
+
+use crate::{
+    c_mem::strcpy, spring_errno::SpringErrno, spring_last_err::LastError,
+    spring_pipeline::SpringPipeline, with_catch,
+};
+
+/// Callback to receive errors in background tasks.
+///
+/// Called from the worker thread which got the error, while the worker waits for the callback to return.
+///
+/// # Parameters
+///
+/// - `errno`: Errno of the error. `Unknown` for a panic.
+/// - `component`: Name of the worker thread which got the error (e.g. `SourceWorker`).
+/// - `message`: Message of the error, including the foreign host if any.
+/// - `n_pipelines`: Number of pipelines open in the process when the error occurred. The error belongs to one of them
+///   (see `spring_poll_background_error()`). `1` means that the error is of the pipeline receiving it.
+/// - `user_data`: The pointer passed to `spring_set_background_error_callback()` as-is.
+///
+/// All strings are valid only during the call.
+pub type SpringBackgroundErrorCallback = Option<
+    unsafe extern "C" fn(
+        errno: SpringErrno,
+        component: *const c_char,
+        message: *const c_char,
+        n_pipelines: u32,
+        user_data: *mut c_void,
+    ),
+>;
+
+/// Non-NULL `SpringBackgroundErrorCallback`.
+pub(crate) type BackgroundErrorCallback = unsafe extern "C" fn(
+    errno: SpringErrno,
+    component: *const c_char,
+    message: *const c_char,
+    n_pipelines: u32,
+    user_data: *mut c_void,
+);
+
+/// Take the oldest error in background tasks, which `spring_last_err()` cannot tell.
+///
+/// Background tasks (source readers, pumps and sink writers) run on worker threads of SpringQL-core.
+/// When a task fails (e.g. a `NET_SERVER` source reader receives a malformed JSON, or a sink writer loses its connection),
+/// the worker thread reports the error and continues, and a panic kills the worker thread.
+/// Up to 256 errors are kept from `spring_open()` until polled (the oldest one is dropped on overflow).
+///
+/// Errors are captured from log records of SpringQL-core, so they are not captured
+/// if another logger than this library's is installed. SpringQL-core does not tell which pipeline a worker thread belongs to,
+/// so all pipelines in the process receive the same errors, with the number of pipelines open when the error occurred.
+///
+/// # Parameters
+///
+/// - `errno`: A pointer to store the errno of the error (`Ok` if there is none). `Unknown` for a panic. Can be NULL if not needed.
+/// - `n_pipelines`: A pointer to store the number of pipelines open in the process when the error occurred (`0` if there is no error).
+///   The error belongs to one of them, and `1` means that the error is of `pipeline`. Can be NULL if not needed.
+/// - `buf`: A buffer to store `<component>: <message>` with a trailing NUL,
+///   where `<component>` is the name of the worker thread which got the error (e.g. `SourceWorker: ForeignIo { .. }`).
+///
+/// # Returns
+///
+/// - `> 0`: the length of the string stored in `buf`. The error is removed.
+/// - `0`: there is no error to take.
+/// - `CNull`: `pipeline` or `buf` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+/// - `CInsufficient`: `buf_len` is too small to store the string (with a trailing NUL). The error is kept.
+#[no_mangle]
+pub unsafe extern "C" fn spring_poll_background_error(
+    pipeline: *const SpringPipeline,
+    errno: *mut SpringErrno,
+    n_pipelines: *mut u32,
+    buf: *mut c_char,
+    buf_len: c_int,
+) -> c_int {
+    ensure_not_null!(SpringErrno::CNull as c_int; pipeline, buf);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; pipeline);
+
+    match with_catch(|| {
+        let mut len = 0;
+        let failure = pipeline.worker_monitor().poll(|failure| {
+            len = strcpy(
+                &format!("{}: {}", failure.worker, failure.message),
+                buf,
+                buf_len,
+            );
+            len > 0
+        });
+        Ok::<_, LastError>((failure, len))
+    }) {
+        Ok((failure, len)) => {
+            if len >= 0 {
+                if !errno.is_null() {
+                    *errno = failure
+                        .as_ref()
+                        .map_or(SpringErrno::Ok, |failure| failure.errno);
+                }
+                if !n_pipelines.is_null() {
+                    *n_pipelines = failure.map_or(0, |failure| failure.n_pipelines as u32);
+                }
+            }
+            len
+        }
+        Err(e) => e as c_int,
+    }
+}
+
+/// Set a callback called whenever a background task gets an error, instead of keeping it for `spring_poll_background_error()`.
+///
+/// See `spring_poll_background_error()` for errors to be received.
+/// Replaces the previous callback. Waits for the previous callback running on other threads to return,
+/// so that its `user_data` can be freed after this function returns (a callback replacing itself is not waited for).
+/// `spring_close()` also waits for the callback.
+///
+/// # Parameters
+///
+/// - `cb`: Callback to receive errors. NULL to unset (errors are kept for `spring_poll_background_error()` again).
+/// - `user_data`: Passed to `cb` as-is.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `CNull`: `pipeline` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_set_background_error_callback(
+    pipeline: *const SpringPipeline,
+    cb: SpringBackgroundErrorCallback,
+    user_data: *mut c_void,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    match with_catch(|| {
+        pipeline.worker_monitor().set_callback(cb, user_data);
+        Ok::<_, LastError>(())
+    }) {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
@@ -1,24 +1,61 @@
 // This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
-use ::springql::{Result, SpringConfig as RuSpringConfig};
+use ::anyhow::anyhow;
+use ::springql::{error::SpringError, Result, SpringConfig as RuSpringConfig};
+
+use crate::handle;
 
 /// Configuration.
 #[non_exhaustive]
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
-pub struct SpringConfig(RuSpringConfig);
+pub struct SpringConfig {
+    core: RuSpringConfig,
+
+    /// `client.enable_metrics_receiver` in TOML, read by this library (SpringQL-core ignores `[client]` section).
+    ///
+    /// Whether `spring_open()` receives metrics reports from SpringQL-core on an embedded endpoint on `127.0.0.1`.
+    enable_metrics_receiver: bool,
+}
 
 impl AsRef<RuSpringConfig> for SpringConfig {
     fn as_ref(&self) -> &RuSpringConfig {
-        &self.0
+        &self.core
     }
 }
 
 impl SpringConfig {
     pub(crate) fn from_toml(toml: &str) -> Result<Self> {
-        let config = RuSpringConfig::from_toml(toml)?;
-        Ok(Self(config))
+        let core = RuSpringConfig::from_toml(toml)?;
+
+        // already validated as TOML by SpringQL-core
+        let value: ::toml::Value =
+            ::toml::from_str(toml).map_err(|e| SpringError::InvalidFormat {
+                s: toml.to_string(),
+                source: anyhow::Error::new(e),
+            })?;
+        let enable_metrics_receiver = match value
+            .get("client")
+            .and_then(|client| client.get("enable_metrics_receiver"))
+        {
+            None => false,
+            Some(::toml::Value::Boolean(enabled)) => *enabled,
+            Some(v) => {
+                return Err(SpringError::InvalidConfig {
+                    source: anyhow!("client.enable_metrics_receiver must be a boolean: {}", v),
+                })
+            }
+        };
+
+        Ok(Self {
+            core,
+            enable_metrics_receiver,
+        })
+    }
+
+    pub(crate) fn enable_metrics_receiver(&self) -> bool {
+        self.enable_metrics_receiver
     }
 
-    pub(crate) fn into_ptr(self) -> *mut SpringConfig {
-        Box::into_raw(Box::new(self))
+    pub(crate) fn into_ptr(self) -> Result<*mut SpringConfig> {
+        handle::into_handle(self)
     }
 }
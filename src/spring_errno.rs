@@ -1,5 +1,10 @@
 // This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
 
+use std::{
+    ffi::CStr,
+    os::raw::{c_char, c_int},
+};
+
 use ::springql::error::SpringError;
 
 use crate::spring_last_err::LastError;
@@ -27,6 +32,14 @@ pub enum SpringErrno {
     Null = -12,
     Time = -13,
 
+    /// Blocking pop is interrupted
+    Interrupted = -14,
+
+    /// Source queue is full (see `SpringOverflowPolicy`)
+    WouldBlock = -15,
+
+    /// Invalid handle (already freed or never created)
+    CInvalidHandle = -125,
     /// Insufficient buffer size
     CInsufficient = -126,
     /// Invalid null pointer
@@ -55,8 +68,129 @@ impl From<&SpringError> for SpringErrno {
 impl From<&LastError> for SpringErrno {
     fn from(e: &LastError) -> Self {
         match e {
-            LastError::SpringErr(e) => e.into(),
-            LastError::UnwindErr(_) => SpringErrno::Unknown,
+            LastError::SpringErr(e) | LastError::CommandErr { err: e, .. } => e.into(),
+            LastError::UnwindErr(..) => SpringErrno::Unknown,
+            LastError::Interrupted(_) => SpringErrno::Interrupted,
+            LastError::WouldBlock(_) => SpringErrno::WouldBlock,
+            LastError::NullPointer(_) => SpringErrno::CNull,
+            LastError::InvalidHandle(_) => SpringErrno::CInvalidHandle,
         }
     }
 }
+
+impl SpringErrno {
+    /// All variants.
+    const ALL: [SpringErrno; 19] = [
+        SpringErrno::Ok,
+        SpringErrno::Unknown,
+        SpringErrno::ForeignIo,
+        SpringErrno::ForeignSourceTimeout,
+        SpringErrno::InputTimeout,
+        SpringErrno::SpringQlCoreIo,
+        SpringErrno::ThreadPoisoned,
+        SpringErrno::InvalidOption,
+        SpringErrno::InvalidFormat,
+        SpringErrno::Unavailable,
+        SpringErrno::Sql,
+        SpringErrno::InvalidConfig,
+        SpringErrno::Null,
+        SpringErrno::Time,
+        SpringErrno::Interrupted,
+        SpringErrno::WouldBlock,
+        SpringErrno::CInvalidHandle,
+        SpringErrno::CInsufficient,
+        SpringErrno::CNull,
+    ];
+
+    fn from_c_int(errno_: c_int) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| *e as c_int == errno_)
+    }
+
+    /// Inverse of `name()`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|e| e.name().to_bytes() == name.as_bytes())
+    }
+
+    /// Variant name, same as in C.
+    pub(crate) fn name(self) -> &'static CStr {
+        static_cstr(match self {
+            SpringErrno::Ok => b"Ok\0",
+            SpringErrno::Unknown => b"Unknown\0",
+            SpringErrno::ForeignIo => b"ForeignIo\0",
+            SpringErrno::ForeignSourceTimeout => b"ForeignSourceTimeout\0",
+            SpringErrno::InputTimeout => b"InputTimeout\0",
+            SpringErrno::SpringQlCoreIo => b"SpringQlCoreIo\0",
+            SpringErrno::ThreadPoisoned => b"ThreadPoisoned\0",
+            SpringErrno::InvalidOption => b"InvalidOption\0",
+            SpringErrno::InvalidFormat => b"InvalidFormat\0",
+            SpringErrno::Unavailable => b"Unavailable\0",
+            SpringErrno::Sql => b"Sql\0",
+            SpringErrno::InvalidConfig => b"InvalidConfig\0",
+            SpringErrno::Null => b"Null\0",
+            SpringErrno::Time => b"Time\0",
+            SpringErrno::Interrupted => b"Interrupted\0",
+            SpringErrno::WouldBlock => b"WouldBlock\0",
+            SpringErrno::CInvalidHandle => b"CInvalidHandle\0",
+            SpringErrno::CInsufficient => b"CInsufficient\0",
+            SpringErrno::CNull => b"CNull\0",
+        })
+    }
+
+    fn description(self) -> &'static CStr {
+        static_cstr(match self {
+            SpringErrno::Ok => b"success\0",
+            SpringErrno::Unknown => b"unexpected error (panic) inside the library\0",
+            SpringErrno::ForeignIo => b"I/O error related to a foreign system\0",
+            SpringErrno::ForeignSourceTimeout => {
+                b"timeout when getting an input from a foreign source\0"
+            }
+            SpringErrno::InputTimeout => b"timeout when getting an input from a stream\0",
+            SpringErrno::SpringQlCoreIo => b"I/O error inside SpringQL-core\0",
+            SpringErrno::ThreadPoisoned => b"another thread sharing the same resource got panic\0",
+            SpringErrno::InvalidOption => b"invalid key or value in OPTIONS\0",
+            SpringErrno::InvalidFormat => b"invalid format\0",
+            SpringErrno::Unavailable => b"requested resource is not available\0",
+            SpringErrno::Sql => b"SQL error\0",
+            SpringErrno::InvalidConfig => b"invalid config\0",
+            SpringErrno::Null => b"unexpectedly got NULL from a column\0",
+            SpringErrno::Time => b"time conversion error\0",
+            SpringErrno::Interrupted => b"blocking pop is interrupted\0",
+            SpringErrno::WouldBlock => b"source queue is full\0",
+            SpringErrno::CInvalidHandle => b"invalid handle (already freed or never created)\0",
+            SpringErrno::CInsufficient => b"insufficient buffer size\0",
+            SpringErrno::CNull => b"invalid NULL pointer\0",
+        })
+    }
+}
+
+/// Get the name of an errno (e.g. `"Sql"` for `Sql`).
+///
+/// # Returns
+///
+/// Static NUL-terminated string. Must not be freed.
+/// `"(unknown errno)"` if `errno_` is not a `SpringErrno`.
+#[no_mangle]
+pub extern "C" fn spring_errno_name(errno_: c_int) -> *const c_char {
+    SpringErrno::from_c_int(errno_)
+        .map_or(static_cstr(b"(unknown errno)\0"), SpringErrno::name)
+        .as_ptr()
+}
+
+/// Get a human-readable description of an errno.
+///
+/// # Returns
+///
+/// Static NUL-terminated string. Must not be freed.
+/// `"(unknown errno)"` if `errno_` is not a `SpringErrno`.
+#[no_mangle]
+pub extern "C" fn spring_strerror(errno_: c_int) -> *const c_char {
+    SpringErrno::from_c_int(errno_)
+        .map_or(static_cstr(b"(unknown errno)\0"), SpringErrno::description)
+        .as_ptr()
+}
+
+fn static_cstr(bytes: &'static [u8]) -> &'static CStr {
+    CStr::from_bytes_with_nul(bytes).expect("static string must end with a NUL")
+}
@@ -0,0 +1,283 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::os::raw::{c_char, c_int};
+
+use ::springql::{error::SpringError as RuSpringError, Result};
+
+use crate::{
+    c_mem::strcpy,
+    close_handle, handle,
+    spring_errno::SpringErrno,
+    spring_last_err::{with_last_error, LastError},
+    with_catch,
+};
+
+/// Detail of an error, taken by `spring_last_err_detail()`.
+#[non_exhaustive]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SpringError {
+    errno: SpringErrno,
+    message: String,
+    causes: Vec<String>,
+    sql_position: Option<SqlPosition>,
+    /// Backtrace of a panic inside this library.
+    backtrace: Option<String>,
+}
+
+/// Location of a syntax error in a SQL statement.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct SqlPosition {
+    /// 0-origin byte offset.
+    offset: Option<usize>,
+    /// 1-origin line number.
+    line: usize,
+    /// 1-origin column number in characters.
+    column: usize,
+}
+
+impl From<&LastError> for SpringError {
+    fn from(last_err: &LastError) -> Self {
+        let message = match last_err {
+            LastError::SpringErr(e) | LastError::CommandErr { err: e, .. } => e.to_string(),
+            _ => last_err.to_string(),
+        };
+        let causes = last_err.causes();
+
+        let sql_position = match last_err {
+            LastError::SpringErr(RuSpringError::Sql(_)) => causes
+                .iter()
+                .find_map(|cause| SqlPosition::parse(cause, None)),
+            LastError::CommandErr {
+                err: RuSpringError::Sql(_),
+                statement,
+            } => causes
+                .iter()
+                .find_map(|cause| SqlPosition::parse(cause, Some(statement))),
+            _ => None,
+        };
+
+        let backtrace = match last_err {
+            LastError::UnwindErr(_, Some(detail)) => Some(detail.backtrace.to_string()),
+            _ => None,
+        };
+
+        Self {
+            errno: SpringErrno::from(last_err),
+            message,
+            causes,
+            sql_position,
+            backtrace,
+        }
+    }
+}
+
+impl SpringError {
+    pub(crate) fn into_ptr(self) -> Result<*mut SpringError> {
+        handle::into_handle(self)
+    }
+}
+
+impl SqlPosition {
+    /// Parses ` --> line:column` in a syntax error message from the SQL parser.
+    fn parse(message: &str, statement: Option<&str>) -> Option<Self> {
+        let (_, rest) = message.split_once("--> ")?;
+        let (line, rest) = rest.split_once(':')?;
+        let column: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let line = line.trim().parse().ok()?;
+        let column = column.parse().ok()?;
+
+        let offset = statement.and_then(|statement| Self::offset(statement, line, column));
+        Some(Self {
+            offset,
+            line,
+            column,
+        })
+    }
+
+    fn offset(statement: &str, line: usize, column: usize) -> Option<usize> {
+        let line_head: usize = statement
+            .split_inclusive('\n')
+            .take(line.checked_sub(1)?)
+            .map(str::len)
+            .sum();
+        let column_offset: usize = statement[line_head..]
+            .chars()
+            .take(column.checked_sub(1)?)
+            .map(char::len_utf8)
+            .sum();
+        Some(line_head + column_offset)
+    }
+}
+
+/// Get the detail of the most recent error.
+///
+/// Unlike `spring_last_err()`, this function does not clear the most recent error.
+///
+/// # Returns
+///
+/// - non-NULL: the detail of the most recent error. Must be freed by `spring_error_free()`.
+/// - NULL: if there are no recent errors.
+#[no_mangle]
+pub extern "C" fn spring_last_err_detail() -> *mut SpringError {
+    let result = with_catch(|| {
+        with_last_error(|last_err| last_err.map(SpringError::from))
+            .map(SpringError::into_ptr)
+            .transpose()
+    });
+    match result {
+        Ok(Some(err)) => err,
+        Ok(None) | Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Frees heap occupied by a `SpringError`.
+///
+/// Must not be called while another thread is still using `err`: it is not reference-counted.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `CNull`: `err` is a NULL pointer.
+/// - `CInvalidHandle`: `err` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_error_free(err: *mut SpringError) -> SpringErrno {
+    close_handle(err, "err")
+}
+
+/// Get the errno of an error.
+///
+/// # Returns
+///
+/// - `CNull`: `err` is a NULL pointer.
+/// - `CInvalidHandle`: `err` is already freed.
+/// - Other errno: the errno of `err`.
+#[no_mangle]
+pub unsafe extern "C" fn spring_error_errno(err: *const SpringError) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; err);
+    ensure_live!(SpringErrno::CInvalidHandle; err);
+
+    err.errno
+}
+
+/// Get the top-level message of an error.
+///
+/// # Returns
+///
+/// - `> 0`: the length of the message.
+/// - `CNull`: `err` or `buf` is a NULL pointer.
+/// - `CInvalidHandle`: `err` is already freed.
+/// - `CInsufficient`: `buf_len` is too small to store the message (with a trailing NUL).
+#[no_mangle]
+pub unsafe extern "C" fn spring_error_message(
+    err: *const SpringError,
+    buf: *mut c_char,
+    buf_len: c_int,
+) -> c_int {
+    ensure_not_null!(SpringErrno::CNull as c_int; err, buf);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; err);
+    strcpy(&err.message, buf, buf_len)
+}
+
+/// Get the number of causes of an error.
+///
+/// # Returns
+///
+/// - `>= 0`: the number of causes.
+/// - `CNull`: `err` is a NULL pointer.
+/// - `CInvalidHandle`: `err` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_error_cause_count(err: *const SpringError) -> c_int {
+    ensure_not_null!(SpringErrno::CNull as c_int; err);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; err);
+    err.causes.len() as c_int
+}
+
+/// Get the message of the `i`-th cause of an error.
+///
+/// Causes are ordered from the direct cause (`i = 0`) to the root cause (`i = spring_error_cause_count() - 1`).
+///
+/// # Returns
+///
+/// - `> 0`: the length of the message.
+/// - `CNull`: `err` or `buf` is a NULL pointer.
+/// - `CInvalidHandle`: `err` is already freed.
+/// - `CInsufficient`: `buf_len` is too small to store the message (with a trailing NUL).
+/// - `Unavailable`: `i` is out of range.
+#[no_mangle]
+pub unsafe extern "C" fn spring_error_cause(
+    err: *const SpringError,
+    i: c_int,
+    buf: *mut c_char,
+    buf_len: c_int,
+) -> c_int {
+    ensure_not_null!(SpringErrno::CNull as c_int; err, buf);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; err);
+    let cause = usize::try_from(i).ok().and_then(|i| err.causes.get(i));
+    match cause {
+        Some(cause) => strcpy(cause, buf, buf_len),
+        None => SpringErrno::Unavailable as c_int,
+    }
+}
+
+/// Get the backtrace of a panic inside this library.
+///
+/// # Returns
+///
+/// - `> 0`: the length of the backtrace.
+/// - `CNull`: `err` or `buf` is a NULL pointer.
+/// - `CInvalidHandle`: `err` is already freed.
+/// - `CInsufficient`: `buf_len` is too small to store the backtrace (with a trailing NUL).
+/// - `Unavailable`: `err` is not a panic, or its backtrace is not recorded.
+#[no_mangle]
+pub unsafe extern "C" fn spring_error_backtrace(
+    err: *const SpringError,
+    buf: *mut c_char,
+    buf_len: c_int,
+) -> c_int {
+    ensure_not_null!(SpringErrno::CNull as c_int; err, buf);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; err);
+    match &err.backtrace {
+        Some(backtrace) => strcpy(backtrace, buf, buf_len),
+        None => SpringErrno::Unavailable as c_int,
+    }
+}
+
+/// Get the location of a syntax error in the SQL statement passed to `spring_command()`.
+///
+/// # Parameters
+///
+/// - `offset`: A pointer to store the 0-origin byte offset in the statement. `-1` if unknown. Can be NULL if not needed.
+/// - `line`: A pointer to store the 1-origin line number. Can be NULL if not needed.
+/// - `column`: A pointer to store the 1-origin column number in characters. Can be NULL if not needed.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `CNull`: `err` is a NULL pointer.
+/// - `CInvalidHandle`: `err` is already freed.
+/// - `Unavailable`: `err` is not a syntax error in SQL.
+#[no_mangle]
+pub unsafe extern "C" fn spring_error_sql_position(
+    err: *const SpringError,
+    offset: *mut c_int,
+    line: *mut c_int,
+    column: *mut c_int,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; err);
+    ensure_live!(SpringErrno::CInvalidHandle; err);
+    let pos = match err.sql_position {
+        Some(pos) => pos,
+        None => return SpringErrno::Unavailable,
+    };
+
+    if !offset.is_null() {
+        *offset = pos.offset.map_or(-1, |offset| offset as c_int);
+    }
+    if !line.is_null() {
+        *line = pos.line as c_int;
+    }
+    if !column.is_null() {
+        *column = pos.column as c_int;
+    }
+    SpringErrno::Ok
+}
@@ -2,6 +2,7 @@
 
 use std::{
     any::Any,
+    borrow::Cow,
     cell::RefCell,
     error::Error,
     fmt::Display,
@@ -11,45 +12,134 @@ use std::{
 use ::log::{info, warn};
 use ::springql::error::SpringError;
 
-use crate::{c_mem::strcpy, spring_errno::SpringErrno};
+use crate::{c_mem::strcpy, panic_hook::PanicDetail, spring_errno::SpringErrno};
 
 thread_local! {
-    static LAST_ERROR: RefCell<Option<LastError>> = RefCell::new(None);
+    static LAST_ERROR: RefCell<Option<LastError>> = const { RefCell::new(None) };
 }
 
 fn take_last_error() -> Option<LastError> {
     LAST_ERROR.with(|prev| prev.borrow_mut().take())
 }
 
+/// Apply `f` to the most recent error without clearing it.
+pub(super) fn with_last_error<R>(f: impl FnOnce(Option<&LastError>) -> R) -> R {
+    LAST_ERROR.with(|prev| f(prev.borrow().as_ref()))
+}
+
 #[derive(Debug)]
 pub(super) enum LastError {
     SpringErr(SpringError),
-    UnwindErr(Box<dyn Any + Send + 'static>),
+
+    /// Error from `spring_command()`, with the statement for locating SQL errors.
+    CommandErr {
+        err: SpringError,
+        statement: String,
+    },
+
+    /// Panic payload, and where the panic occurred if the panic hook recorded it.
+    UnwindErr(Box<dyn Any + Send + 'static>, Option<PanicDetail>),
+
+    /// A blocking pop on the queue is interrupted.
+    Interrupted(String),
+
+    /// The source queue is full and its overflow policy rejects the row.
+    WouldBlock(String),
+
+    /// The argument (or its element, e.g. `rows[3]`) is a NULL pointer.
+    NullPointer(Cow<'static, str>),
+
+    /// The argument (or its element) is not a live handle.
+    InvalidHandle(Cow<'static, str>),
+}
+
+impl From<SpringError> for LastError {
+    fn from(e: SpringError) -> Self {
+        LastError::SpringErr(e)
+    }
 }
 
 impl Error for LastError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            LastError::SpringErr(e) => e.source(),
-            LastError::UnwindErr(_) => None,
+            LastError::SpringErr(e) | LastError::CommandErr { err: e, .. } => match e {
+                // not marked as `#[source]` in SpringQL-core
+                SpringError::SpringQlCoreIo(e)
+                | SpringError::ThreadPoisoned(e)
+                | SpringError::Sql(e) => Some(e.as_ref()),
+                _ => e.source(),
+            },
+            LastError::UnwindErr(..)
+            | LastError::Interrupted(_)
+            | LastError::WouldBlock(_)
+            | LastError::NullPointer(_)
+            | LastError::InvalidHandle(_) => None,
         }
     }
 }
+
+/// `"<errno name>: <message>: <cause>: ...: <root cause>"`
 impl Display for LastError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
-            LastError::SpringErr(e) => format!("{:?}", e),
-            LastError::UnwindErr(any) => {
+        let errno = SpringErrno::from(self);
+        write!(f, "{}", errno.name().to_string_lossy())?;
+
+        match self {
+            LastError::SpringErr(e) | LastError::CommandErr { err: e, .. } => {
+                // "SQL error" only repeats the errno name
+                if !matches!(e, SpringError::Sql(_)) {
+                    write!(f, ": {}", e)?;
+                }
+            }
+            LastError::UnwindErr(any, detail) => {
                 if let Some(s) = any.downcast_ref::<String>() {
-                    s.clone()
+                    write!(f, ": {}", s)?;
                 } else if let Some(s) = any.downcast_ref::<&str>() {
-                    s.to_string()
+                    write!(f, ": {}", s)?;
                 } else {
-                    "a panic occurred".to_string()
+                    write!(f, ": a panic occurred")?;
+                }
+
+                if let Some(location) = detail.as_ref().and_then(|detail| detail.location.as_ref())
+                {
+                    write!(f, " (at {})", location)?;
                 }
             }
-        };
-        write!(f, "{}", s)
+            LastError::Interrupted(queue) => {
+                write!(f, ": blocking pop from queue ({}) is interrupted", queue)?;
+            }
+            LastError::WouldBlock(queue) => {
+                write!(f, ": source queue ({}) is full", queue)?;
+            }
+            LastError::NullPointer(arg) => {
+                write!(f, ": `{}` is a NULL pointer", arg)?;
+            }
+            LastError::InvalidHandle(arg) => {
+                write!(
+                    f,
+                    ": `{}` is already freed or not created by this library",
+                    arg
+                )?;
+            }
+        }
+
+        for cause in self.causes() {
+            write!(f, ": {}", cause)?;
+        }
+        Ok(())
+    }
+}
+
+impl LastError {
+    /// Messages of errors causing this error, from the direct cause to the root cause.
+    pub(super) fn causes(&self) -> Vec<String> {
+        let mut causes = Vec::new();
+        let mut source = self.source();
+        while let Some(parent_err) = source {
+            causes.push(parent_err.to_string());
+            source = parent_err.source();
+        }
+        causes
     }
 }
 
@@ -72,6 +162,18 @@ pub(super) fn update_last_error(err: LastError) {
     });
 }
 
+/// Record that the argument `arg` is a NULL pointer as the most recent error.
+pub(super) fn null_pointer_error(arg: impl Into<Cow<'static, str>>) -> SpringErrno {
+    update_last_error(LastError::NullPointer(arg.into()));
+    SpringErrno::CNull
+}
+
+/// Record that the argument `arg` is not a live handle as the most recent error.
+pub(super) fn invalid_handle_error(arg: impl Into<Cow<'static, str>>) -> SpringErrno {
+    update_last_error(LastError::InvalidHandle(arg.into()));
+    SpringErrno::CInvalidHandle
+}
+
 /// Write the most recent error number into `errno_` and message into a caller-provided buffer as a UTF-8
 /// string, returning the number of bytes written.
 ///
@@ -84,23 +186,30 @@ pub(super) fn update_last_error(err: LastError) {
 /// bytes). `-1` is returned if there are any errors, for example when passed a
 /// null pointer or a buffer of insufficient size.
 ///
+/// If the error is a panic inside this library, the message includes where the panic occurred.
+/// Its backtrace is got by `spring_error_backtrace()`.
+///
+/// The error is cleared only if the message is stored in `errmsg`.
+///
 /// # Returns
 ///
 /// - `0`: if there are no recent errors.
 /// - `> 0`: the length of the recent error message.
-/// - `< 0`: SpringErrno
+/// - `< 0`: SpringErrno (`CInsufficient` if `errmsg_len` is too small to store the message with a trailing NUL)
 #[no_mangle]
 pub unsafe extern "C" fn spring_last_err(
     errno_: *mut SpringErrno,
     errmsg: *mut c_char,
     errmsg_len: c_int,
 ) -> c_int {
-    if errmsg.is_null() {
-        warn!("Null pointer passed into spring_last_err() as the buffer");
+    if errno_.is_null() || errmsg.is_null() {
+        warn!("Null pointer passed into spring_last_err()");
         return SpringErrno::CNull as c_int;
     }
 
-    let last_error = match take_last_error() {
+    let (errno, error_message) = match with_last_error(|last_error| {
+        last_error.map(|last_error| (SpringErrno::from(last_error), last_error.to_string()))
+    }) {
         Some(err) => err,
         None => {
             *errno_ = SpringErrno::Ok;
@@ -108,10 +217,12 @@ pub unsafe extern "C" fn spring_last_err(
         }
     };
 
-    *errno_ = SpringErrno::from(&last_error);
-    let error_message = last_error.to_string();
-
-    strcpy(&error_message, errmsg, errmsg_len)
+    *errno_ = errno;
+    let len = strcpy(&error_message, errmsg, errmsg_len);
+    if len >= 0 {
+        take_last_error();
+    }
+    len
 }
 
 /// Calculate the number of bytes in the last error's error message **not**
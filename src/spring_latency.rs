@@ -0,0 +1,233 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! End-to-end latency from ingestion to pop.
+//!
+//! SpringQL-core stamps a row with the processing time when its source reader reads the row (`<source stream>.ptime` in SQL).
+//! Selecting it into a `TIMESTAMP` column of a sink stream carries the ingest time to the sink row.
+
+use std::{
+    ffi::CStr,
+    os::raw::c_char,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ::springql::{SpringSinkRow as RuSpringSinkRow, SpringTimestamp};
+
+use crate::{
+    spring_errno::SpringErrno, spring_pipeline::SpringPipeline, spring_sink_row::SpringSinkRow,
+    with_catch,
+};
+
+/// Number of buckets in `SpringLatencyHistogram`.
+pub const SPRING_LATENCY_BUCKETS: usize = 16;
+
+/// Upper bounds (inclusive) of buckets but the last one, in nanoseconds.
+pub(crate) const LATENCY_BUCKET_BOUNDS_NSEC: [u64; SPRING_LATENCY_BUCKETS - 1] = [
+    50_000,
+    100_000,
+    250_000,
+    500_000,
+    1_000_000,
+    2_500_000,
+    5_000_000,
+    10_000_000,
+    25_000_000,
+    50_000_000,
+    100_000_000,
+    250_000_000,
+    500_000_000,
+    1_000_000_000,
+    2_500_000_000,
+];
+
+/// Histogram of latency from ingestion to pop of rows in an in memory sink queue, got by `spring_latency_histogram()`.
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SpringLatencyHistogram {
+    /// Number of rows whose latency is recorded.
+    pub count: u64,
+    /// Sum of latency in nanoseconds.
+    pub sum_nsec: u64,
+    /// Minimum latency in nanoseconds. 0 if `count` is 0.
+    pub min_nsec: u64,
+    /// Maximum latency in nanoseconds.
+    pub max_nsec: u64,
+    /// Number of rows popped without a valid ingest time (e.g. NULL or not a timestamp).
+    pub rows_untraced: u64,
+    /// Number of rows in each latency range (not cumulative). Upper bounds of the buckets are
+    /// 50us, 100us, 250us, 500us, 1ms, 2.5ms, 5ms, 10ms, 25ms, 50ms, 100ms, 250ms, 500ms, 1s, 2.5s and infinity.
+    pub buckets: [u64; SPRING_LATENCY_BUCKETS],
+}
+
+impl SpringLatencyHistogram {
+    fn record(&mut self, latency_nsec: u64) {
+        if self.count == 0 || latency_nsec < self.min_nsec {
+            self.min_nsec = latency_nsec;
+        }
+        self.max_nsec = self.max_nsec.max(latency_nsec);
+        self.count += 1;
+        self.sum_nsec = self.sum_nsec.saturating_add(latency_nsec);
+
+        let i_bucket = LATENCY_BUCKET_BOUNDS_NSEC
+            .iter()
+            .position(|bound| latency_nsec <= *bound)
+            .unwrap_or(SPRING_LATENCY_BUCKETS - 1);
+        self.buckets[i_bucket] += 1;
+    }
+}
+
+/// Records latency of rows popped from a sink queue.
+#[derive(Debug)]
+pub(crate) struct LatencyTrace {
+    ingest_time_col: usize,
+    histogram: SpringLatencyHistogram,
+}
+
+impl LatencyTrace {
+    pub(crate) fn new(ingest_time_col: usize) -> Self {
+        Self {
+            ingest_time_col,
+            histogram: SpringLatencyHistogram::default(),
+        }
+    }
+
+    pub(crate) fn histogram(&self) -> SpringLatencyHistogram {
+        self.histogram
+    }
+
+    /// Records latency of `row` popped now.
+    pub(crate) fn record(&mut self, row: &RuSpringSinkRow) {
+        match row.get_not_null_by_index::<SpringTimestamp>(self.ingest_time_col) {
+            Ok(ingest_time) => {
+                let latency_nsec = now_unix_nsec().saturating_sub(unix_nsec(ingest_time));
+                // clamps a negative latency by a clock adjustment
+                self.histogram.record(latency_nsec.max(0) as u64);
+            }
+            Err(_) => self.histogram.rows_untraced += 1,
+        }
+    }
+}
+
+fn unix_nsec(timestamp: SpringTimestamp) -> i64 {
+    static UNIX_EPOCH_TIMESTAMP: OnceLock<SpringTimestamp> = OnceLock::new();
+    let epoch = UNIX_EPOCH_TIMESTAMP.get_or_init(|| {
+        "1970-01-01 00:00:00.000000000"
+            .parse()
+            .expect("valid timestamp")
+    });
+    (timestamp - *epoch).num_nanoseconds() as i64
+}
+
+fn now_unix_nsec() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as i64)
+}
+
+/// Get the ingest time of a row as nanoseconds since the Unix epoch (UTC).
+///
+/// The ingest time must be selected into a `TIMESTAMP` column from the processing time of a source stream, e.g.:
+///
+/// ```sql
+/// CREATE SINK STREAM sink_1 (n INTEGER NOT NULL, ingest_time TIMESTAMP NOT NULL);
+/// CREATE PUMP pump_1 AS
+///     INSERT INTO sink_1 (n, ingest_time)
+///     SELECT STREAM source_1.n, source_1.ptime FROM source_1;
+/// ```
+///
+/// SpringQL-core stamps the processing time when a source reader reads the row, shortly after `spring_push()` for in memory queues.
+/// Streams with a `ROWTIME` column have no processing time.
+///
+/// # Parameters
+///
+/// - `row`: A `SpringRow` pointer to get the ingest time from.
+/// - `i_col`: The column index of the ingest time.
+/// - `out`: A pointer to a buffer to store the ingest time.
+///
+/// # Returns
+///
+/// - `Ok`: On success.
+/// - `Sql`: `i_col` is out of range, or column value is not a timestamp.
+/// - `Null`: Column value is NULL.
+/// - `CNull`: `row` or `out` is a NULL pointer.
+/// - `CInvalidHandle`: `row` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_sink_row_ingest_time(
+    row: *const SpringSinkRow,
+    i_col: u16,
+    out: *mut i64,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; row, out);
+    ensure_live!(SpringErrno::CInvalidHandle; row);
+
+    let i_col = i_col as usize;
+    match with_catch(|| row.get_not_null_by_index::<SpringTimestamp>(i_col)) {
+        Ok(ingest_time) => {
+            *out = unix_nsec(ingest_time);
+            SpringErrno::Ok
+        }
+        Err(e) => e,
+    }
+}
+
+/// Start recording latency from ingestion to pop of rows in an in memory sink queue.
+///
+/// Latency is recorded when a row is popped by any pop function or delivered to a subscriber,
+/// from the ingest time in column `ingest_time_col` (see `spring_sink_row_ingest_time()`).
+/// Calling again resets the histogram.
+///
+/// This library does not stamp rows by itself: latency is only recorded if the SQL selects `<source stream>.ptime`
+/// into a `TIMESTAMP` column `ingest_time_col` of the sink stream. Other rows are counted in `rows_untraced`.
+/// Latency starts when a source reader of SpringQL-core reads the row, so the time a row waits on the client side
+/// (staged by an overflow policy, see `spring_queue_set_overflow_policy()`) is not included.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`: queue named `queue` is not a sink queue created by `spring_command()` of this pipeline.
+/// - `CNull`: `pipeline` or `queue` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_latency_trace(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    ingest_time_col: u16,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    match with_catch(|| pipeline.trace_latency(&queue, ingest_time_col as usize)) {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
+
+/// Get the latency histogram of an in memory sink queue.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`: latency of `queue` is not recorded. Call `spring_latency_trace()` first.
+/// - `CNull`: `pipeline`, `queue` or `histogram` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_latency_histogram(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    histogram: *mut SpringLatencyHistogram,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue, histogram);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    match with_catch(|| pipeline.latency_histogram(&queue)) {
+        Ok(v) => {
+            *histogram = v;
+            SpringErrno::Ok
+        }
+        Err(e) => e,
+    }
+}
@@ -0,0 +1,354 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    cell::Cell,
+    env,
+    ffi::{c_void, CStr, CString},
+    io::Write,
+    os::raw::{c_char, c_int},
+    path::PathBuf,
+    ptr, thread,
+};
+
+use ::anyhow::anyhow;
+use ::log::{Level, LevelFilter, Log, Metadata, Record};
+use ::springql::error::SpringError;
+
+use crate::{
+    log_file::RotatingFile,
+    logger::{self, Backend},
+    spring_errno::SpringErrno,
+    spring_last_err::{null_pointer_error, LastError},
+    with_catch,
+};
+
+/// Environment variable to overwrite level filters passed to `spring_log_init()`.
+const LOG_ENV: &str = "SPRINGQL_LOG";
+
+/// Level filters used when `spring_log_init()` is given NULL.
+const DEFAULT_LOG_FILTERS: &str = "info";
+
+/// A log file is rotated when it gets larger than this size.
+const LOG_FILE_MAX_LEN: u64 = 10 * 1024 * 1024;
+/// Number of rotated log files to keep.
+const LOG_FILE_N_ROTATED: usize = 5;
+
+/// Where the built-in logger writes to.
+///
+/// cbindgen:prefix-with-name
+/// cbindgen:rename-all=ScreamingSnakeCase
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SpringLogTarget {
+    /// Standard error.
+    Stderr = 0,
+    /// A file rotated by size.
+    File = 1,
+}
+
+impl SpringLogTarget {
+    fn from_c_int(target: c_int) -> Option<Self> {
+        match target {
+            0 => Some(SpringLogTarget::Stderr),
+            1 => Some(SpringLogTarget::File),
+            _ => None,
+        }
+    }
+}
+
+/// Level of a log record.
+///
+/// cbindgen:prefix-with-name
+/// cbindgen:rename-all=ScreamingSnakeCase
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum SpringLogLevel {
+    /// No records (only used as a maximum level).
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl From<Level> for SpringLogLevel {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Error => SpringLogLevel::Error,
+            Level::Warn => SpringLogLevel::Warn,
+            Level::Info => SpringLogLevel::Info,
+            Level::Debug => SpringLogLevel::Debug,
+            Level::Trace => SpringLogLevel::Trace,
+        }
+    }
+}
+
+impl SpringLogLevel {
+    pub(crate) fn filter_from_c_int(level: c_int) -> Option<LevelFilter> {
+        match level {
+            0 => Some(LevelFilter::Off),
+            1 => Some(LevelFilter::Error),
+            2 => Some(LevelFilter::Warn),
+            3 => Some(LevelFilter::Info),
+            4 => Some(LevelFilter::Debug),
+            5 => Some(LevelFilter::Trace),
+            _ => None,
+        }
+    }
+}
+
+/// Callback to receive log records.
+///
+/// Called from any thread, including ones internally created by SpringQL.
+///
+/// # Parameters
+///
+/// - `level`: Level of the record.
+/// - `target`: Module path which emitted the record (e.g. `springql_core::stream_engine`).
+/// - `message`: Formatted message.
+/// - `file`: Source file which emitted the record. NULL if unknown.
+/// - `line`: Line number in `file`. 0 if unknown.
+/// - `user_data`: The pointer passed to `spring_set_log_callback()` as-is.
+///
+/// All strings are valid only during the call.
+pub type SpringLogCallback = Option<
+    unsafe extern "C" fn(
+        level: SpringLogLevel,
+        target: *const c_char,
+        message: *const c_char,
+        file: *const c_char,
+        line: c_int,
+        user_data: *mut c_void,
+    ),
+>;
+
+/// Non-NULL `SpringLogCallback`.
+type LogCallback = unsafe extern "C" fn(
+    level: SpringLogLevel,
+    target: *const c_char,
+    message: *const c_char,
+    file: *const c_char,
+    line: c_int,
+    user_data: *mut c_void,
+);
+
+/// Opaque pointer passed to a callback as-is.
+struct UserData(*mut c_void);
+
+// The library never dereferences `user_data`. Thread safety of the pointee is up to the caller.
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+thread_local! {
+    /// Whether the current thread is in the callback, to drop records emitted by the callback itself.
+    static IN_CALLBACK: Cell<bool> = const { Cell::new(false) };
+}
+
+struct CallbackLogger {
+    callback: LogCallback,
+    user_data: UserData,
+    max_level: LevelFilter,
+}
+
+impl Log for CallbackLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.max_level
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) || IN_CALLBACK.with(Cell::get) {
+            return;
+        }
+
+        let target = to_cstring(record.target().to_string());
+        let message = to_cstring(record.args().to_string());
+        let file = record.file().map(|file| to_cstring(file.to_string()));
+
+        IN_CALLBACK.with(|in_callback| in_callback.set(true));
+        unsafe {
+            (self.callback)(
+                record.level().into(),
+                target.as_ptr(),
+                message.as_ptr(),
+                file.as_ref().map_or(ptr::null(), |file| file.as_ptr()),
+                record.line().map_or(0, |line| line as c_int),
+                self.user_data.0,
+            )
+        };
+        IN_CALLBACK.with(|in_callback| in_callback.set(false));
+    }
+
+    fn flush(&self) {}
+}
+
+fn install_logger() -> Result<(), SpringError> {
+    if logger::install() {
+        Ok(())
+    } else {
+        Err(SpringError::Unavailable {
+            resource: "logger".to_string(),
+            source: anyhow!("another logger is installed in this process"),
+        })
+    }
+}
+
+fn open_log_file(path: PathBuf) -> Result<RotatingFile, SpringError> {
+    RotatingFile::open(path.clone(), LOG_FILE_MAX_LEN, LOG_FILE_N_ROTATED).map_err(|e| {
+        SpringError::InvalidOption {
+            key: "path".to_string(),
+            value: path.display().to_string(),
+            source: e.into(),
+        }
+    })
+}
+
+/// Drops NUL characters which cannot be in a C string.
+fn to_cstring(mut s: String) -> CString {
+    s.retain(|c| c != '\0');
+    CString::new(s).expect("NUL characters are removed")
+}
+
+/// Route log records from this library and SpringQL-core to a callback.
+///
+/// Without a callback, records are written to stderr by `env_logger` configured by `RUST_LOG` environment variable.
+///
+/// Call this function before `spring_open()` not to miss records on startup.
+/// Callbacks already running on other threads may still be running when this function returns.
+/// Records emitted while the callback runs on the same thread are dropped.
+///
+/// # Parameters
+///
+/// - `cb`: Callback to receive records. NULL to restore the default logger.
+/// - `user_data`: Passed to `cb` as-is.
+/// - `max_level`: Most verbose `SpringLogLevel` to pass to `cb`.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `InvalidOption`: `max_level` is not a `SpringLogLevel`.
+/// - `Unavailable`: Another logger is installed in this process (by an other Rust library).
+#[no_mangle]
+pub unsafe extern "C" fn spring_set_log_callback(
+    cb: SpringLogCallback,
+    user_data: *mut c_void,
+    max_level: c_int,
+) -> SpringErrno {
+    let result = with_catch(|| {
+        let max_level = SpringLogLevel::filter_from_c_int(max_level).ok_or_else(|| {
+            SpringError::InvalidOption {
+                key: "max_level".to_string(),
+                value: max_level.to_string(),
+                source: anyhow!("not a SpringLogLevel"),
+            }
+        })?;
+        install_logger()?;
+
+        match cb {
+            Some(callback) => logger::set_backend(Backend::new(
+                CallbackLogger {
+                    callback,
+                    user_data: UserData(user_data),
+                    max_level,
+                },
+                max_level,
+            )),
+            None => logger::reset_backend(),
+        }
+        Ok::<_, SpringError>(())
+    });
+    match result {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
+
+/// Install a built-in logger for log records from this library and SpringQL-core.
+///
+/// Each line has a timestamp, level, thread name and target (module path) as:
+///
+/// ```text
+/// [2022-07-13T01:23:45.678Z INFO  springql-autonomous-executor springql_core::stream_engine] message
+/// ```
+///
+/// Replaces a callback set by `spring_set_log_callback()`, and vice versa.
+///
+/// # Parameters
+///
+/// - `target`: `SpringLogTarget` to write to.
+/// - `level`: Comma-separated level filters (e.g. `springql_core=debug,springql_client=info`, or just `warn`).
+///   NULL for `info`. `SPRINGQL_LOG` environment variable, if set, is used instead.
+/// - `path`: Log file path when `target` is `SPRING_LOG_TARGET_FILE`. Rotated to `path.1` ... `path.5` by 10 MiB.
+///   Ignored (can be NULL) for other targets.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `InvalidOption`: `target` is not a `SpringLogTarget`, or `path` cannot be opened.
+/// - `Unavailable`: Another logger is installed in this process (by an other Rust library).
+/// - `CNull`: `path` is a NULL pointer while `target` is `SPRING_LOG_TARGET_FILE`.
+#[no_mangle]
+pub unsafe extern "C" fn spring_log_init(
+    target: c_int,
+    level: *const c_char,
+    path: *const c_char,
+) -> SpringErrno {
+    if target == SpringLogTarget::File as c_int && path.is_null() {
+        return null_pointer_error("path");
+    }
+    let filters = match env::var(LOG_ENV) {
+        Ok(filters) => filters,
+        Err(_) if level.is_null() => DEFAULT_LOG_FILTERS.to_string(),
+        Err(_) => CStr::from_ptr(level).to_string_lossy().into_owned(),
+    };
+    let path =
+        (!path.is_null()).then(|| PathBuf::from(CStr::from_ptr(path).to_string_lossy().as_ref()));
+
+    let result = with_catch(|| {
+        let log_target =
+            SpringLogTarget::from_c_int(target).ok_or_else(|| SpringError::InvalidOption {
+                key: "target".to_string(),
+                value: target.to_string(),
+                source: anyhow!("not a SpringLogTarget"),
+            })?;
+        install_logger()?;
+
+        let mut builder = env_logger::Builder::new();
+        builder.parse_filters(&filters).format(|buf, record| {
+            writeln!(
+                buf,
+                "[{} {:5} {} {}] {}",
+                buf.timestamp_millis(),
+                record.level(),
+                thread::current().name().unwrap_or("<unnamed>"),
+                record.target(),
+                record.args()
+            )
+        });
+
+        match (log_target, path) {
+            (SpringLogTarget::File, Some(path)) => {
+                let file = open_log_file(path)?;
+                // env_logger 0.9.0 writes to a pipe target only in test mode (otherwise to stderr)
+                builder
+                    .target(env_logger::Target::Pipe(Box::new(file)))
+                    .write_style(env_logger::WriteStyle::Never)
+                    .is_test(true);
+            }
+            _ => {
+                builder.target(env_logger::Target::Stderr);
+            }
+        }
+
+        let logger = builder.build();
+        let max_level = logger.filter();
+        logger::set_backend(Backend::new(logger, max_level));
+        Ok::<_, LastError>(())
+    });
+    match result {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
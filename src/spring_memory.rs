@@ -0,0 +1,124 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::ffi::c_void;
+
+use crate::{spring_errno::SpringErrno, spring_pipeline::SpringPipeline, with_catch};
+
+/// Memory state of a pipeline, decided by memory usage against `memory.upper_limit_bytes` in the config.
+///
+/// cbindgen:prefix-with-name
+/// cbindgen:rename-all=ScreamingSnakeCase
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SpringMemoryState {
+    /// Memory usage is low enough.
+    Moderate = 0,
+    /// Over `memory.moderate_to_severe_percent` (back under `memory.severe_to_moderate_percent`).
+    /// The internal scheduler is changed to exhibit memory-resilience.
+    Severe = 1,
+    /// Over `memory.severe_to_critical_percent` (back under `memory.critical_to_severe_percent`).
+    /// All intermediate rows are purged to release memory.
+    Critical = 2,
+}
+
+/// Callback to receive memory state changes.
+///
+/// Called from a library-owned thread.
+///
+/// # Parameters
+///
+/// - `state`: The new memory state.
+/// - `used_bytes`: Memory usage in bytes.
+/// - `upper_limit_bytes`: `memory.upper_limit_bytes` in the config.
+/// - `user_data`: The pointer passed to `spring_set_memory_state_callback()` as-is.
+pub type SpringMemoryStateCallback = Option<
+    unsafe extern "C" fn(
+        state: SpringMemoryState,
+        used_bytes: u64,
+        upper_limit_bytes: u64,
+        user_data: *mut c_void,
+    ),
+>;
+
+/// Non-NULL `SpringMemoryStateCallback`.
+pub(crate) type MemoryStateCallback = unsafe extern "C" fn(
+    state: SpringMemoryState,
+    used_bytes: u64,
+    upper_limit_bytes: u64,
+    user_data: *mut c_void,
+);
+
+/// Get the memory usage and memory state of a pipeline.
+///
+/// SpringQL-core reports memory usage every `web_console.report_interval_msec` in the config
+/// if `client.enable_metrics_receiver` is true (see `spring_open()`), so the values may lag behind by the interval. Before the first report, `used` is 0 and `state` is `Moderate`.
+///
+/// # Parameters
+///
+/// - `used`: A pointer to store memory usage in bytes. Can be NULL if not needed.
+/// - `limit`: A pointer to store `memory.upper_limit_bytes` in the config. Can be NULL if not needed.
+/// - `state`: A pointer to store the memory state. Can be NULL if not needed.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`: memory usage is not reported (see `spring_open()`).
+/// - `CNull`: `pipeline` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_memory_usage(
+    pipeline: *const SpringPipeline,
+    used: *mut u64,
+    limit: *mut u64,
+    state: *mut SpringMemoryState,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    match with_catch(|| pipeline.memory_usage()) {
+        Ok((used_bytes, upper_limit_bytes, memory_state)) => {
+            if !used.is_null() {
+                *used = used_bytes;
+            }
+            if !limit.is_null() {
+                *limit = upper_limit_bytes;
+            }
+            if !state.is_null() {
+                *state = memory_state;
+            }
+            SpringErrno::Ok
+        }
+        Err(e) => e,
+    }
+}
+
+/// Set a callback called whenever the memory state of a pipeline changes.
+///
+/// Replaces the previous callback. A callback already running may still be running when this function returns.
+///
+/// # Parameters
+///
+/// - `cb`: Callback to receive memory state changes. NULL to unset.
+/// - `user_data`: Passed to `cb` as-is.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`: memory usage is not reported (see `spring_open()`).
+/// - `CNull`: `pipeline` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_set_memory_state_callback(
+    pipeline: *const SpringPipeline,
+    cb: SpringMemoryStateCallback,
+    user_data: *mut c_void,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    match with_catch(|| pipeline.set_memory_state_callback(cb, user_data)) {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
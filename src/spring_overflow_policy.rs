@@ -0,0 +1,203 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    collections::HashMap,
+    ffi::CStr,
+    os::raw::{c_char, c_int},
+    time::Duration,
+};
+
+use ::anyhow::anyhow;
+use ::springql::{error::SpringError, Result};
+
+use crate::{spring_errno::SpringErrno, spring_pipeline::SpringPipeline, with_catch};
+
+/// What to do when a row is pushed to a full in-memory source queue.
+///
+/// Set by `spring_queue_set_overflow_policy()` or `OVERFLOW_POLICY` option of `CREATE SOURCE READER ... TYPE IN_MEMORY_QUEUE`.
+///
+/// cbindgen:prefix-with-name
+/// cbindgen:rename-all=ScreamingSnakeCase
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SpringOverflowPolicy {
+    /// Wait for room up to the block timeout, and then fail with `WouldBlock`.
+    Block = 0,
+    /// Drop the oldest row in the queue to make room.
+    DropOldest = 1,
+    /// Drop the pushed row.
+    DropNewest = 2,
+    /// Fail with `WouldBlock` immediately.
+    Fail = 3,
+}
+
+impl SpringOverflowPolicy {
+    fn from_c_int(policy: c_int) -> Option<Self> {
+        match policy {
+            0 => Some(SpringOverflowPolicy::Block),
+            1 => Some(SpringOverflowPolicy::DropOldest),
+            2 => Some(SpringOverflowPolicy::DropNewest),
+            3 => Some(SpringOverflowPolicy::Fail),
+            _ => None,
+        }
+    }
+}
+
+/// `OPTIONS` keys of `CREATE SOURCE READER ... TYPE IN_MEMORY_QUEUE` read by this library.
+const OVERFLOW_OPTION_KEYS: [&str; 3] = ["OVERFLOW_POLICY", "CAPACITY", "BLOCK_TIMEOUT_MSEC"];
+
+/// Overflow policy of a source queue.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct OverflowConfig {
+    pub(crate) policy: SpringOverflowPolicy,
+    /// Rows staged at most. Not 0.
+    pub(crate) capacity: usize,
+    /// How long `Block` policy waits for room.
+    pub(crate) block_timeout: Duration,
+}
+
+impl OverflowConfig {
+    /// # Failure
+    ///
+    /// - `SpringError::InvalidOption` when:
+    ///   - `capacity` is 0.
+    pub(crate) fn new(
+        policy: SpringOverflowPolicy,
+        capacity: u32,
+        block_timeout_msec: u32,
+    ) -> Result<Self> {
+        if capacity == 0 {
+            return Err(SpringError::InvalidOption {
+                key: "CAPACITY".to_string(),
+                value: capacity.to_string(),
+                source: anyhow!("capacity must be positive"),
+            });
+        }
+        Ok(Self {
+            policy,
+            capacity: capacity as usize,
+            block_timeout: Duration::from_millis(block_timeout_msec as u64),
+        })
+    }
+
+    /// Reads `OVERFLOW_POLICY` (`BLOCK`, `DROP_OLDEST`, `DROP_NEWEST` or `FAIL`), `CAPACITY` (rows)
+    /// and `BLOCK_TIMEOUT_MSEC` (required for `BLOCK`) options.
+    ///
+    /// # Returns
+    ///
+    /// `None` if none of the keys are in `options`.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::InvalidOption` when:
+    ///   - a key is missing or a value is invalid.
+    pub(crate) fn from_options(options: &HashMap<String, String>) -> Result<Option<Self>> {
+        if OVERFLOW_OPTION_KEYS
+            .iter()
+            .all(|key| !options.contains_key(*key))
+        {
+            return Ok(None);
+        }
+
+        let missing = |key: &str| SpringError::InvalidOption {
+            key: key.to_string(),
+            value: "(not found)".to_string(),
+            source: anyhow!("key is not found in options"),
+        };
+        let option = |key: &str| options.get(key).ok_or_else(|| missing(key));
+        let invalid = |key: &str, value: &str, e: anyhow::Error| SpringError::InvalidOption {
+            key: key.to_string(),
+            value: value.to_string(),
+            source: e,
+        };
+
+        let policy = option("OVERFLOW_POLICY")?;
+        let policy = match policy.as_str() {
+            "BLOCK" => SpringOverflowPolicy::Block,
+            "DROP_OLDEST" => SpringOverflowPolicy::DropOldest,
+            "DROP_NEWEST" => SpringOverflowPolicy::DropNewest,
+            "FAIL" => SpringOverflowPolicy::Fail,
+            _ => {
+                return Err(invalid(
+                    "OVERFLOW_POLICY",
+                    policy,
+                    anyhow!("expected BLOCK, DROP_OLDEST, DROP_NEWEST or FAIL"),
+                ))
+            }
+        };
+
+        let capacity = option("CAPACITY")?;
+        let capacity = capacity
+            .parse()
+            .map_err(|e| invalid("CAPACITY", capacity, anyhow::Error::new(e)))?;
+
+        let block_timeout_msec = match options.get("BLOCK_TIMEOUT_MSEC") {
+            Some(msec) => msec
+                .parse()
+                .map_err(|e| invalid("BLOCK_TIMEOUT_MSEC", msec, anyhow::Error::new(e)))?,
+            None if policy == SpringOverflowPolicy::Block => {
+                return Err(missing("BLOCK_TIMEOUT_MSEC"))
+            }
+            None => 0,
+        };
+
+        Self::new(policy, capacity, block_timeout_msec).map(Some)
+    }
+}
+
+/// Set the overflow policy of an in-memory source queue.
+///
+/// Rows pushed to the queue are staged on the C client side (up to `capacity` rows) and forwarded to SpringQL-core
+/// while the memory state of the pipeline is `Moderate` (see `spring_memory_usage()`).
+/// While the pipeline cannot keep up, the memory state gets `Severe` and the staging fills up.
+/// Pushing to the full staging applies `policy`. Rows dropped by the policy are counted in `SpringQueueStats.client_rows_dropped`.
+/// Rows still staged at `spring_close()` are discarded.
+///
+/// Same as `OPTIONS (NAME '...', OVERFLOW_POLICY 'BLOCK', CAPACITY '1000', BLOCK_TIMEOUT_MSEC '100')` in
+/// `CREATE SOURCE READER ... TYPE IN_MEMORY_QUEUE`. Calling again replaces the policy.
+///
+/// The memory state follows metrics reports, so it lags behind the pipeline by `web_console.report_interval_msec` in the config.
+/// Metrics reports must be received (`client.enable_metrics_receiver` in the config).
+///
+/// # Parameters
+///
+/// - `policy`: One of `SpringOverflowPolicy`.
+/// - `capacity`: Number of rows staged at most. Must be positive.
+/// - `block_timeout_msec`: How long a push waits for room with `Block` policy. Ignored for other policies.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`:
+///   - queue named `queue` is not a source queue created by `spring_command()` of this pipeline.
+///   - memory usage is not reported (see `spring_memory_usage()`).
+/// - `InvalidOption`: `policy` is not a `SpringOverflowPolicy`, or `capacity` is 0.
+/// - `CNull`: `pipeline` or `queue` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_queue_set_overflow_policy(
+    pipeline: *const SpringPipeline,
+    queue: *const c_char,
+    policy: c_int,
+    capacity: u32,
+    block_timeout_msec: u32,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, queue);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let queue = CStr::from_ptr(queue).to_string_lossy().into_owned();
+    match with_catch(|| {
+        let policy =
+            SpringOverflowPolicy::from_c_int(policy).ok_or_else(|| SpringError::InvalidOption {
+                key: "policy".to_string(),
+                value: policy.to_string(),
+                source: anyhow!("not a SpringOverflowPolicy"),
+            })?;
+        let config = OverflowConfig::new(policy, capacity, block_timeout_msec)?;
+        pipeline.set_overflow_policy(&queue, config)
+    }) {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
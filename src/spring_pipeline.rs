@@ -1,26 +1,736 @@
 // This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
 
-use ::springql::SpringPipeline as RuSpringPipeline;
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+use ::anyhow::anyhow;
+use ::springql::{
+    error::SpringError, Result, SpringPipeline as RuSpringPipeline,
+    SpringSinkRow as RuSpringSinkRow, SpringSourceRow as RuSpringSourceRow,
+};
+
+use crate::{
+    handle,
+    health_monitor::HealthMonitor,
+    http_server::{HttpServer, Response},
+    memory_monitor::MemoryMonitor,
+    metrics_receiver::{MetricsReceiver, PipelineReport},
+    queue_catalog::{self, QueueDecl, QueueKind},
+    sink_queue_dispatcher::SinkQueueDispatcher,
+    sink_queue_subscription::{SinkQueueSubscription, SinkRowCallback},
+    source_queue_feeder::SourceQueueFeeder,
+    spring_config::SpringConfig,
+    spring_last_err::LastError,
+    spring_latency::SpringLatencyHistogram,
+    spring_memory::{SpringMemoryState, SpringMemoryStateCallback},
+    spring_overflow_policy::OverflowConfig,
+    spring_pipeline_metrics::prometheus_text,
+    spring_queue::SpringQueue,
+    spring_queue_stats::{QueueCounters, SpringQueueStats},
+    worker_monitor::WorkerMonitor,
+};
+
+/// Pointer to a pipeline shared with its metrics server thread.
+///
+/// Valid while the server runs: a pipeline is not moved after passed to C as a handle,
+/// and it stops the server before the rest of its fields are dropped.
+struct PipelinePtr(*const SpringPipeline);
+
+unsafe impl Send for PipelinePtr {}
+
+impl PipelinePtr {
+    unsafe fn get(&self) -> &SpringPipeline {
+        &*self.0
+    }
+}
 
 /// Pipeline (dataflow definition) in SpringQL.
 #[non_exhaustive]
 #[derive(Debug)]
-pub struct SpringPipeline(RuSpringPipeline);
+pub struct SpringPipeline {
+    /// Started by `serve_metrics()`. Dropped first, as the server thread refers to this pipeline.
+    metrics_server: Mutex<Option<HttpServer>>,
 
-impl From<RuSpringPipeline> for SpringPipeline {
-    fn from(pipeline: RuSpringPipeline) -> Self {
-        SpringPipeline(pipeline)
-    }
+    /// Dropped before `dispatchers` so that subscriber threads stop first.
+    subscriptions: Mutex<HashMap<String, SinkQueueSubscription>>,
+
+    /// Source queues with an overflow policy, whose rows are staged on the C client side.
+    /// Dropped before `pipeline` so that feeder threads stop first.
+    feeders: Mutex<HashMap<String, Arc<SourceQueueFeeder>>>,
+
+    pipeline: Arc<RuSpringPipeline>,
+
+    /// In-memory queues created by `command()`.
+    queues: Mutex<HashMap<String, QueueDecl>>,
+
+    /// Sink queues whose rows are moved to the C client side.
+    /// Started on the first blocking pop, `queue_fd()` or `subscribe()` for each queue.
+    dispatchers: Mutex<HashMap<String, Arc<SinkQueueDispatcher>>>,
+
+    /// Set by `interrupt()`. Blocking pops afterwards are interrupted immediately.
+    interrupted: AtomicBool,
+
+    /// Counters of in memory queues (both source and sink) created by `command()`.
+    /// Write-locked only by `command()`, as it is read on every row.
+    counters: RwLock<HashMap<String, Arc<QueueCounters>>>,
+
+    /// `memory.upper_limit_bytes` in the config.
+    memory_upper_limit_bytes: u64,
+
+    /// Updated by `metrics_receiver`. `None` without `metrics_receiver`.
+    memory_monitor: Option<Arc<MemoryMonitor>>,
+
+    /// Updated by `metrics_receiver`. `None` without `metrics_receiver`.
+    health_monitor: Option<Arc<HealthMonitor>>,
+
+    /// Failures on worker threads of SpringQL-core.
+    worker_monitor: Arc<WorkerMonitor>,
+
+    /// Receives metrics reports from `pipeline`.
+    /// `None` unless `client.enable_metrics_receiver` is true (and the web console is not enabled) in the config.
+    /// Dropped after `pipeline` so that reports in flight are received.
+    metrics_receiver: Option<MetricsReceiver>,
 }
 
 impl AsRef<RuSpringPipeline> for SpringPipeline {
     fn as_ref(&self) -> &RuSpringPipeline {
-        &self.0
+        &self.pipeline
     }
 }
 
 impl SpringPipeline {
-    pub(crate) fn into_ptr(self) -> *mut SpringPipeline {
-        Box::into_raw(Box::new(self))
+    /// Creates and open an in-process stream pipeline.
+    ///
+    /// If `client.enable_metrics_receiver` is true and the web console is not enabled in `config`,
+    /// metrics reports to the web console are redirected to a `MetricsReceiver`.
+    pub(crate) fn open(config: &SpringConfig) -> Result<Self> {
+        let enable_metrics_receiver = config.enable_metrics_receiver();
+        let mut config = config.as_ref().clone();
+
+        let memory_monitor = Arc::new(MemoryMonitor::new(&config.memory));
+        let health_monitor = Arc::new(HealthMonitor::new());
+
+        let metrics_receiver = if !enable_metrics_receiver || config.web_console.enable_report_post
+        {
+            None
+        } else {
+            let observer = {
+                let memory_monitor = memory_monitor.clone();
+                let health_monitor = health_monitor.clone();
+                Box::new(move |report: &PipelineReport| {
+                    memory_monitor.update(report.queue_total_bytes());
+                    health_monitor.update(report);
+                })
+            };
+            match MetricsReceiver::start(observer) {
+                Ok(metrics_receiver) => {
+                    config.web_console.enable_report_post = true;
+                    config.web_console.host = "127.0.0.1".to_string();
+                    config.web_console.port = metrics_receiver.port();
+                    Some(metrics_receiver)
+                }
+                Err(e) => {
+                    log::warn!("failed to start metrics receiver: {:?}", e);
+                    None
+                }
+            }
+        };
+        let memory_monitor = metrics_receiver.as_ref().map(|_| memory_monitor);
+        let health_monitor = metrics_receiver.as_ref().map(|_| health_monitor);
+        // before worker threads start
+        let worker_monitor = WorkerMonitor::start();
+
+        let pipeline = RuSpringPipeline::new(&config)?;
+        Ok(SpringPipeline {
+            metrics_server: Mutex::default(),
+            subscriptions: Mutex::default(),
+            feeders: Mutex::default(),
+            pipeline: Arc::new(pipeline),
+            queues: Mutex::default(),
+            dispatchers: Mutex::default(),
+            interrupted: AtomicBool::new(false),
+            counters: RwLock::default(),
+            memory_upper_limit_bytes: config.memory.upper_limit_bytes,
+            memory_monitor,
+            health_monitor,
+            worker_monitor,
+            metrics_receiver,
+        })
+    }
+
+    pub(crate) fn into_ptr(self) -> Result<*mut SpringPipeline> {
+        handle::into_handle(self)
+    }
+
+    /// Execute commands (DDL) and record in-memory queues created by them.
+    ///
+    /// Overflow policy options of an in-memory source queue are validated before the command is executed.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::InvalidOption` when:
+    ///   - overflow policy options are invalid.
+    /// - `SpringError::Unavailable` when:
+    ///   - overflow policy options are given but memory usage is not reported.
+    pub(crate) fn command(&self, sql: &str) -> Result<()> {
+        let decl = queue_catalog::parse_queue_decl(sql);
+        let overflow = match &decl {
+            Some(decl) if decl.kind == QueueKind::Source => {
+                let overflow = OverflowConfig::from_options(&decl.options)?;
+                if overflow.is_some() {
+                    self.memory_monitor()?;
+                }
+                overflow
+            }
+            _ => None,
+        };
+
+        self.pipeline.command(sql)?;
+
+        if let Some(decl) = decl {
+            let queue = decl.name.clone();
+            self.counters
+                .write()
+                .expect("another thread sharing the same pipeline got panic")
+                .entry(queue.clone())
+                .or_default();
+            self.queues
+                .lock()
+                .expect("another thread sharing the same pipeline got panic")
+                .insert(queue.clone(), decl);
+            if let Some(overflow) = overflow {
+                self.set_overflow_policy(&queue, overflow)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Push a row into an in memory queue.
+    ///
+    /// Non-blocking unless the queue is full with `Block` overflow policy.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - queue named `queue` does not exist.
+    /// - `LastError::WouldBlock` when:
+    ///   - the queue is full and its overflow policy rejects the row.
+    pub(crate) fn push(
+        &self,
+        queue: &str,
+        row: RuSpringSourceRow,
+    ) -> std::result::Result<(), LastError> {
+        match self.feeder(queue) {
+            Some(feeder) => feeder.push(row),
+            None => {
+                self.pipeline.push(queue, row)?;
+                if let Some(counters) = self.counters(queue) {
+                    counters.add_pushed(1);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Stage rows pushed to a source queue and apply `overflow` when the staging is full.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - queue named `queue` is not a source queue created by `command()`.
+    ///   - memory usage is not reported (see `memory_usage()`).
+    pub(crate) fn set_overflow_policy(&self, queue: &str, overflow: OverflowConfig) -> Result<()> {
+        if self.queue_kind(queue)? != QueueKind::Source {
+            return Err(SpringError::Unavailable {
+                resource: queue.to_string(),
+                source: anyhow!("overflow policy is only for source queues"),
+            });
+        }
+        let memory_monitor = self
+            .memory_monitor
+            .clone()
+            .ok_or_else(memory_usage_unavailable)?;
+
+        let mut feeders = self
+            .feeders
+            .lock()
+            .expect("another thread sharing the same pipeline got panic");
+        match feeders.get(queue) {
+            Some(feeder) => feeder.set_config(overflow),
+            None => {
+                let feeder = SourceQueueFeeder::start(
+                    self.pipeline.clone(),
+                    queue,
+                    overflow,
+                    self.counters(queue).unwrap_or_default(),
+                    memory_monitor,
+                );
+                feeders.insert(queue.to_string(), Arc::new(feeder));
+            }
+        }
+        Ok(())
+    }
+
+    /// Pop a row. This is a blocking function.
+    ///
+    /// Waits on the dispatcher for `queue`, so that the stream engine is not locked while waiting.
+    /// Blocking pops from multiple threads are safe.
+    ///
+    /// # Failure
+    ///
+    /// - `LastError::Interrupted` when:
+    ///   - interrupted by `interrupt()` or `interrupt_queue()`.
+    pub(crate) fn pop(&self, queue: &str) -> std::result::Result<RuSpringSinkRow, LastError> {
+        let dispatcher = self.dispatcher_or_start(queue)?;
+        Ok(dispatcher
+            .pop_timeout(None)?
+            .expect("never times out without timeout"))
+    }
+
+    /// Pop a row. This is a non-blocking function.
+    pub(crate) fn pop_non_blocking(&self, queue: &str) -> Result<Option<RuSpringSinkRow>> {
+        match self.dispatcher(queue) {
+            Some(dispatcher) => Ok(dispatcher.pop_non_blocking()),
+            None => {
+                let row = self.pipeline.pop_non_blocking(queue)?;
+                if let (Some(row), Some(counters)) = (&row, self.counters(queue)) {
+                    counters.popped(row);
+                }
+                Ok(row)
+            }
+        }
+    }
+
+    /// Pop a row, waiting until a row arrives or `timeout` elapses.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some)` when a row is popped.
+    /// - `Ok(None)` when `timeout` elapsed. `timeout == None` waits forever.
+    ///
+    /// # Failure
+    ///
+    /// - `LastError::Interrupted` when:
+    ///   - interrupted by `interrupt()` or `interrupt_queue()`.
+    pub(crate) fn pop_timeout(
+        &self,
+        queue: &str,
+        timeout: Option<Duration>,
+    ) -> std::result::Result<Option<RuSpringSinkRow>, LastError> {
+        let dispatcher = self.dispatcher_or_start(queue)?;
+        dispatcher.pop_timeout(timeout)
+    }
+
+    /// Pop up to `max` (positive) rows, waiting until at least a row arrives or `timeout` elapses.
+    ///
+    /// # Returns
+    ///
+    /// Popped rows. Empty when `timeout` elapsed.
+    ///
+    /// # Failure
+    ///
+    /// - `LastError::Interrupted` when:
+    ///   - interrupted by `interrupt()` or `interrupt_queue()`.
+    pub(crate) fn pop_batch(
+        &self,
+        queue: &str,
+        max: usize,
+        timeout: Option<Duration>,
+    ) -> std::result::Result<Vec<RuSpringSinkRow>, LastError> {
+        let mut rows = Vec::new();
+        if let Some(row) = self.pop_timeout(queue, timeout)? {
+            rows.push(row);
+            while rows.len() < max {
+                match self.pop_non_blocking(queue)? {
+                    Some(row) => rows.push(row),
+                    None => break,
+                }
+            }
+        }
+        Ok(rows)
+    }
+
+    /// File descriptor readable while `queue` has rows.
+    ///
+    /// The first call for `queue` starts a dispatcher for it.
+    pub(crate) fn queue_fd(&self, queue: &str) -> Result<i32> {
+        self.dispatcher_or_start(queue)
+            .map(|dispatcher| dispatcher.fd())
+    }
+
+    /// Deliver rows in `queue` to `callback` on a library-owned thread.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - queue named `queue` does not exist.
+    ///   - `queue` is already subscribed.
+    pub(crate) fn subscribe(
+        &self,
+        queue: &str,
+        callback: SinkRowCallback,
+        user_data: *mut c_void,
+    ) -> Result<()> {
+        let mut subscriptions = self
+            .subscriptions
+            .lock()
+            .expect("another thread sharing the same pipeline got panic");
+
+        if subscriptions.contains_key(queue) {
+            return Err(SpringError::Unavailable {
+                resource: queue.to_string(),
+                source: anyhow!("queue is already subscribed"),
+            });
+        }
+
+        let dispatcher = self.dispatcher_or_start(queue)?;
+        let subscription = SinkQueueSubscription::start(dispatcher, queue, callback, user_data);
+        subscriptions.insert(queue.to_string(), subscription);
+        Ok(())
+    }
+
+    /// Stop delivering rows in `queue` to a callback.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - `queue` is not subscribed.
+    pub(crate) fn unsubscribe(&self, queue: &str) -> Result<()> {
+        let subscription = self
+            .subscriptions
+            .lock()
+            .expect("another thread sharing the same pipeline got panic")
+            .remove(queue)
+            .ok_or_else(|| SpringError::Unavailable {
+                resource: queue.to_string(),
+                source: anyhow!("queue is not subscribed"),
+            })?;
+
+        // stops the subscriber thread outside the lock
+        drop(subscription);
+        Ok(())
+    }
+
+    /// Resolve an in memory queue (either source or sink) named `queue`.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - queue named `queue` is not created by `command()`.
+    pub(crate) fn open_queue(&self, queue: &str) -> Result<SpringQueue> {
+        self.queue_kind(queue)?;
+        Ok(SpringQueue::new(queue.to_string()))
+    }
+
+    /// Kind of an in-memory queue created by `command()`.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - queue named `queue` is not created by `command()`.
+    pub(crate) fn queue_kind(&self, queue: &str) -> Result<QueueKind> {
+        self.queues
+            .lock()
+            .expect("another thread sharing the same pipeline got panic")
+            .get(queue)
+            .map(|decl| decl.kind)
+            .ok_or_else(|| SpringError::Unavailable {
+                resource: queue.to_string(),
+                source: anyhow!("queue not found"),
+            })
+    }
+
+    /// Statistics of `queue`.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - queue named `queue` is not created by `command()`.
+    pub(crate) fn queue_stats(&self, queue: &str) -> Result<SpringQueueStats> {
+        self.queue_kind(queue)?;
+        let mut stats = self
+            .counters(queue)
+            .map(|counters| counters.stats())
+            .unwrap_or_default();
+        if let Some(dispatcher) = self.dispatcher(queue) {
+            stats.client_depth = dispatcher.depth() as u64;
+        } else if let Some(feeder) = self.feeder(queue) {
+            stats.client_depth = feeder.depth() as u64;
+        }
+        Ok(stats)
+    }
+
+    /// Statistics of all in memory queues, sorted by queue name.
+    pub(crate) fn all_queue_stats(&self) -> Vec<(String, SpringQueueStats)> {
+        let mut queues: Vec<_> = self
+            .counters
+            .read()
+            .expect("another thread sharing the same pipeline got panic")
+            .keys()
+            .cloned()
+            .collect();
+        queues.sort();
+        queues
+            .into_iter()
+            .filter_map(|queue| {
+                let stats = self.queue_stats(&queue).ok()?;
+                Some((queue, stats))
+            })
+            .collect()
+    }
+
+    /// Start recording latency of rows popped from `queue`, from the ingest time in column `ingest_time_col`.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - queue named `queue` is not a sink queue created by `command()`.
+    pub(crate) fn trace_latency(&self, queue: &str, ingest_time_col: usize) -> Result<()> {
+        if self.queue_kind(queue)? != QueueKind::Sink {
+            return Err(SpringError::Unavailable {
+                resource: queue.to_string(),
+                source: anyhow!("latency is only traced for sink queues"),
+            });
+        }
+        if let Some(counters) = self.counters(queue) {
+            counters.trace_latency(ingest_time_col);
+        }
+        Ok(())
+    }
+
+    /// Latency histogram of `queue`.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - latency of `queue` is not traced.
+    pub(crate) fn latency_histogram(&self, queue: &str) -> Result<SpringLatencyHistogram> {
+        self.counters(queue)
+            .and_then(|counters| counters.latency_histogram())
+            .ok_or_else(|| SpringError::Unavailable {
+                resource: queue.to_string(),
+                source: anyhow!("latency is not traced"),
+            })
+    }
+
+    /// Latency histograms of all traced queues, sorted by queue name.
+    pub(crate) fn all_latency_histograms(&self) -> Vec<(String, SpringLatencyHistogram)> {
+        let mut histograms: Vec<_> = self
+            .counters
+            .read()
+            .expect("another thread sharing the same pipeline got panic")
+            .iter()
+            .filter_map(|(queue, counters)| Some((queue.clone(), counters.latency_histogram()?)))
+            .collect();
+        histograms.sort_by(|(a, _), (b, _)| a.cmp(b));
+        histograms
+    }
+
+    /// The latest metrics report from SpringQL-core.
+    ///
+    /// `None` until the first report arrives, or if metrics reports are not received.
+    pub(crate) fn metrics_report(&self) -> Option<PipelineReport> {
+        self.metrics_receiver
+            .as_ref()
+            .and_then(MetricsReceiver::latest)
+    }
+
+    pub(crate) fn memory_upper_limit_bytes(&self) -> u64 {
+        self.memory_upper_limit_bytes
+    }
+
+    /// `None` if metrics reports are not received.
+    pub(crate) fn health_monitor(&self) -> Option<&HealthMonitor> {
+        self.health_monitor.as_deref()
+    }
+
+    pub(crate) fn worker_monitor(&self) -> &WorkerMonitor {
+        &self.worker_monitor
+    }
+
+    /// Memory usage in bytes, `memory.upper_limit_bytes` and the memory state.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - memory usage is not reported (`client.enable_metrics_receiver` is not true, or the web console is enabled).
+    pub(crate) fn memory_usage(&self) -> Result<(u64, u64, SpringMemoryState)> {
+        let memory_monitor = self.memory_monitor()?;
+        let (used_bytes, state) = memory_monitor.usage();
+        Ok((used_bytes, memory_monitor.upper_limit_bytes(), state))
+    }
+
+    /// Set a callback called whenever the memory state changes. `None` unsets the callback.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - memory usage is not reported (see `memory_usage()`).
+    pub(crate) fn set_memory_state_callback(
+        &self,
+        callback: SpringMemoryStateCallback,
+        user_data: *mut c_void,
+    ) -> Result<()> {
+        self.memory_monitor()?.set_callback(callback, user_data);
+        Ok(())
+    }
+
+    fn memory_monitor(&self) -> Result<&MemoryMonitor> {
+        self.memory_monitor
+            .as_deref()
+            .ok_or_else(memory_usage_unavailable)
+    }
+
+    /// Serve metrics in Prometheus text format at `GET /metrics` on `addr`. `None` stops serving.
+    ///
+    /// Must be called on a pipeline passed to C as a handle, which is not moved afterwards.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::InvalidOption` when:
+    ///   - failed to listen on `addr`.
+    /// - `SpringError::Unavailable` when:
+    ///   - metrics are already served.
+    pub(crate) fn serve_metrics(&self, addr: Option<&str>) -> Result<()> {
+        let mut metrics_server = self
+            .metrics_server
+            .lock()
+            .expect("another thread sharing the same pipeline got panic");
+
+        let addr = match addr {
+            Some(addr) => addr,
+            None => {
+                // stops the server thread (which does not lock `metrics_server`)
+                *metrics_server = None;
+                return Ok(());
+            }
+        };
+        if let Some(server) = metrics_server.as_ref() {
+            return Err(SpringError::Unavailable {
+                resource: "metrics server".to_string(),
+                source: anyhow!("already serving on {}", server.local_addr()),
+            });
+        }
+
+        let pipeline = PipelinePtr(self);
+        let server = HttpServer::start(
+            addr,
+            "springql-client-metrics-server",
+            move |request| match (request.method.as_str(), request.path.as_str()) {
+                ("GET", "/metrics") => {
+                    let pipeline = unsafe { pipeline.get() };
+                    Response::ok("text/plain; version=0.0.4", prometheus_text(pipeline))
+                }
+                _ => Response::not_found(),
+            },
+        )
+        .map_err(|e| SpringError::InvalidOption {
+            key: "addr".to_string(),
+            value: addr.to_string(),
+            source: e.into(),
+        })?;
+        *metrics_server = Some(server);
+        Ok(())
+    }
+
+    /// Interrupt all blocking pops, including ones called afterwards.
+    pub(crate) fn interrupt(&self) {
+        let dispatchers = self
+            .dispatchers
+            .lock()
+            .expect("another thread sharing the same pipeline got panic");
+
+        self.interrupted.store(true, Ordering::Release);
+        for dispatcher in dispatchers.values() {
+            dispatcher.interrupt_all();
+        }
+    }
+
+    /// Interrupt blocking pops waiting for `queue` now.
+    ///
+    /// Does nothing if no blocking pop has been called for `queue` (no dispatcher is started for it).
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Unavailable` when:
+    ///   - queue named `queue` is not created by `command()`.
+    pub(crate) fn interrupt_queue(&self, queue: &str) -> Result<()> {
+        self.queue_kind(queue)?;
+        if let Some(dispatcher) = self.dispatcher(queue) {
+            dispatcher.interrupt();
+        }
+        Ok(())
+    }
+
+    fn dispatcher_or_start(&self, queue: &str) -> Result<Arc<SinkQueueDispatcher>> {
+        let mut dispatchers = self
+            .dispatchers
+            .lock()
+            .expect("another thread sharing the same pipeline got panic");
+
+        if let Some(dispatcher) = dispatchers.get(queue) {
+            return Ok(dispatcher.clone());
+        }
+
+        // counters not shared with the pipeline for a queue not created by `command()`
+        let dispatcher = Arc::new(SinkQueueDispatcher::start(
+            self.pipeline.clone(),
+            queue,
+            self.counters(queue).unwrap_or_default(),
+        )?);
+        if self.interrupted.load(Ordering::Acquire) {
+            dispatcher.interrupt_all();
+        }
+        dispatchers.insert(queue.to_string(), dispatcher.clone());
+        Ok(dispatcher)
+    }
+
+    fn feeder(&self, queue: &str) -> Option<Arc<SourceQueueFeeder>> {
+        self.feeders
+            .lock()
+            .expect("another thread sharing the same pipeline got panic")
+            .get(queue)
+            .cloned()
+    }
+
+    fn dispatcher(&self, queue: &str) -> Option<Arc<SinkQueueDispatcher>> {
+        self.dispatchers
+            .lock()
+            .expect("another thread sharing the same pipeline got panic")
+            .get(queue)
+            .cloned()
+    }
+
+    /// `None` for a queue not created by `command()`.
+    fn counters(&self, queue: &str) -> Option<Arc<QueueCounters>> {
+        self.counters
+            .read()
+            .expect("another thread sharing the same pipeline got panic")
+            .get(queue)
+            .cloned()
+    }
+}
+
+impl Drop for SpringPipeline {
+    fn drop(&mut self) {
+        // the application may free `user_data` of the callback once the pipeline is closed
+        self.worker_monitor.set_callback(None, ptr::null_mut());
+    }
+}
+
+fn memory_usage_unavailable() -> SpringError {
+    SpringError::Unavailable {
+        resource: "memory usage".to_string(),
+        source: anyhow!(
+            "metrics reports are not received (see client.enable_metrics_receiver in the config)"
+        ),
     }
 }
@@ -0,0 +1,371 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    os::raw::{c_char, c_int},
+    time::{Duration, Instant},
+};
+
+use ::anyhow::anyhow;
+use ::serde_json::{json, Value};
+use ::springql::{error::SpringError, Result};
+
+use crate::{
+    c_mem::strcpy,
+    spring_errno::SpringErrno,
+    spring_pipeline::SpringPipeline,
+    with_catch,
+    worker_monitor::{WorkerFailure, CORE_WORKERS},
+};
+
+/// Health of a component (worker thread or task) in a pipeline.
+///
+/// cbindgen:prefix-with-name
+/// cbindgen:rename-all=ScreamingSnakeCase
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum SpringHealthStatus {
+    /// Running without recent failures.
+    Alive = 0,
+    /// A source reader which has read no rows for the stall threshold, without an error.
+    Idle = 1,
+    /// No progress for the stall threshold.
+    Stalled = 2,
+    /// Dead by a panic, or got an error and has not made progress since then.
+    Failed = 3,
+}
+
+impl SpringHealthStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            SpringHealthStatus::Alive => "alive",
+            SpringHealthStatus::Idle => "idle",
+            SpringHealthStatus::Stalled => "stalled",
+            SpringHealthStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Summary of pipeline health, got by `spring_pipeline_health()`.
+///
+/// `spring_pipeline_health_json()` gives the status and last error of each component.
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SpringHealthReport {
+    /// The worst status among components.
+    pub status: SpringHealthStatus,
+    /// Number of components (worker threads and tasks).
+    pub n_components: u32,
+    /// Number of `Alive` components.
+    pub n_alive: u32,
+    /// Number of `Idle` components.
+    pub n_idle: u32,
+    /// Number of `Stalled` components.
+    pub n_stalled: u32,
+    /// Number of `Failed` components.
+    pub n_failed: u32,
+}
+
+/// Health of a component.
+#[derive(Clone, Debug)]
+struct ComponentHealth {
+    name: String,
+    /// `worker`, `source-task`, `pump-task` or `sink-task`.
+    kind: String,
+    status: SpringHealthStatus,
+    /// Time since the last progress. `None` if not measurable.
+    progress_age: Option<Duration>,
+    last_error: Option<WorkerFailure>,
+}
+
+/// Worker thread running tasks of `kind`. Source readers run on `SourceWorker`, and pumps and sink writers on `GenericWorker`.
+fn task_worker(kind: &str) -> &'static str {
+    if kind == "source-task" {
+        "SourceWorker"
+    } else {
+        "GenericWorker"
+    }
+}
+
+/// A component is `Failed` if its worker thread panicked, or got an error not followed by progress (at `progressed_at`).
+fn is_failed(
+    panic: &Option<WorkerFailure>,
+    last_error: &Option<WorkerFailure>,
+    progressed_at: Option<Instant>,
+) -> bool {
+    panic.is_some()
+        || last_error
+            .as_ref()
+            .is_some_and(|failure| progressed_at.is_none_or(|at| failure.at > at))
+}
+
+/// # Failure
+///
+/// - `SpringError::Unavailable` when:
+///   - task health is not observed, as no metrics report is received (yet).
+fn components(
+    pipeline: &SpringPipeline,
+    stall_threshold: Duration,
+) -> Result<Vec<ComponentHealth>> {
+    let unavailable = |reason: &str| SpringError::Unavailable {
+        resource: "task health".to_string(),
+        source: anyhow!("{}", reason),
+    };
+    let health_monitor = pipeline.health_monitor().ok_or_else(|| {
+        unavailable(
+            "metrics reports are not received (see client.enable_metrics_receiver in the config)",
+        )
+    })?;
+    let report = pipeline
+        .metrics_report()
+        .ok_or_else(|| unavailable("no metrics report has arrived yet"))?;
+    let worker_monitor = pipeline.worker_monitor();
+
+    let tasks: Vec<_> = report
+        .tasks
+        .iter()
+        .map(|task| {
+            let worker = task_worker(&task.type_);
+            let panic = worker_monitor.panic(worker);
+            let last_error = panic
+                .clone()
+                .or_else(|| worker_monitor.last_failure(worker));
+            let progress = health_monitor.task_progress(&task.id);
+            let progressed_at = progress.and_then(|progress| progress.progressed_at);
+            let progress_age = progress.map(|progress| progress.age());
+
+            let status = if is_failed(&panic, &last_error, progressed_at) {
+                SpringHealthStatus::Failed
+            } else if progress_age.is_some_and(|age| age > stall_threshold) {
+                // a source reader has no input queue to tell whether rows are coming
+                if task.type_ == "source-task" {
+                    SpringHealthStatus::Idle
+                } else {
+                    SpringHealthStatus::Stalled
+                }
+            } else {
+                SpringHealthStatus::Alive
+            };
+            (
+                progressed_at,
+                ComponentHealth {
+                    name: task.id.clone(),
+                    kind: task.type_.clone(),
+                    status,
+                    progress_age,
+                    last_error,
+                },
+            )
+        })
+        .collect();
+
+    let workers: Vec<_> = CORE_WORKERS
+        .into_iter()
+        .map(|worker| {
+            let panic = worker_monitor.panic(worker);
+            let last_error = panic
+                .clone()
+                .or_else(|| worker_monitor.last_failure(worker));
+
+            // a worker recovers when tasks on it make progress after an error.
+            // metrics are reported by PerformanceMonitorWorker, and other workers serve all tasks.
+            let progressed_at = match worker {
+                "PerformanceMonitorWorker" => Some(health_monitor.last_report_at()),
+                "SourceWorker" | "GenericWorker" => tasks
+                    .iter()
+                    .filter(|(_, task)| task_worker(&task.kind) == worker)
+                    .filter_map(|(progressed_at, _)| *progressed_at)
+                    .max(),
+                _ => tasks
+                    .iter()
+                    .filter_map(|(progressed_at, _)| *progressed_at)
+                    .max(),
+            };
+            let progress_age = progressed_at
+                .filter(|_| worker == "PerformanceMonitorWorker")
+                .map(|at| at.elapsed());
+
+            let status = if is_failed(&panic, &last_error, progressed_at) {
+                SpringHealthStatus::Failed
+            } else if progress_age.is_some_and(|age| age > stall_threshold) {
+                SpringHealthStatus::Stalled
+            } else {
+                SpringHealthStatus::Alive
+            };
+
+            ComponentHealth {
+                name: worker.to_string(),
+                kind: "worker".to_string(),
+                status,
+                progress_age,
+                last_error,
+            }
+        })
+        .collect();
+
+    Ok(workers
+        .into_iter()
+        .chain(tasks.into_iter().map(|(_, task)| task))
+        .collect())
+}
+
+impl SpringHealthReport {
+    fn new(components: &[ComponentHealth]) -> Self {
+        let count = |status| components.iter().filter(|c| c.status == status).count() as u32;
+        Self {
+            status: components
+                .iter()
+                .map(|c| c.status)
+                .max()
+                .unwrap_or(SpringHealthStatus::Alive),
+            n_components: components.len() as u32,
+            n_alive: count(SpringHealthStatus::Alive),
+            n_idle: count(SpringHealthStatus::Idle),
+            n_stalled: count(SpringHealthStatus::Stalled),
+            n_failed: count(SpringHealthStatus::Failed),
+        }
+    }
+}
+
+fn health_json(components: &[ComponentHealth]) -> Value {
+    let report = SpringHealthReport::new(components);
+    let components: Vec<_> = components
+        .iter()
+        .map(|c| {
+            let last_error = c.last_error.as_ref().map(|failure| {
+                json!({
+                    "errno": failure.errno as c_int,
+                    "errno_name": failure.errno.name().to_string_lossy(),
+                    "message": failure.message,
+                    "panic": failure.is_panic,
+                    "age_msec": failure.at.elapsed().as_millis() as u64,
+                    "n_pipelines": failure.n_pipelines,
+                })
+            });
+            json!({
+                "name": c.name,
+                "kind": c.kind,
+                "status": c.status.as_str(),
+                "progress_age_msec": c.progress_age.map(|age| age.as_millis() as u64),
+                "last_error": last_error,
+            })
+        })
+        .collect();
+    json!({
+        "status": report.status.as_str(),
+        "components": components,
+    })
+}
+
+/// Get a summary of pipeline health.
+///
+/// Components are worker threads of SpringQL-core and tasks (source readers, pumps and sink writers) of the pipeline.
+///
+/// - A worker thread is `Failed` if it died by a panic, or if it got an error (e.g. a source reader failed to read)
+///   and no task on it has made progress since then. Errors are captured from log records of SpringQL-core, so they are not captured
+///   if another logger than this library's is installed. SpringQL-core does not tell which pipeline a worker thread belongs to,
+///   so all pipelines in the process report the same worker failures (see `n_pipelines` in `spring_pipeline_health_json()`).
+/// - `PerformanceMonitorWorker` is `Stalled` if metrics are not reported for `stall_threshold_msec`.
+/// - A task is `Failed` if the worker thread running it (`SourceWorker` for source readers, `GenericWorker` for pumps and sink writers)
+///   is `Failed` and the task has not made progress since the error. SpringQL-core does not tell which task an error comes from,
+///   so an error fails all tasks on the worker thread until each of them makes progress again.
+/// - A task is `Stalled` if it has not made progress for `stall_threshold_msec`. A task makes progress while rows flow through it,
+///   i.e. its average gain or rows and bytes in its input and output queues change between metrics reports,
+///   or while its input queues are empty.
+/// - A source reader is `Idle` instead of `Stalled`, as it has no input queue to tell whether rows are coming.
+///   It cannot be told apart from a source reader blocked without an error (e.g. on a silent remote host).
+///
+/// Tasks are known from metrics reports (see `spring_pipeline_metrics()`), so health is not reported without metrics reports
+/// (see `spring_open()`), nor until the first report arrives (SpringQL-core starts reporting after the first `spring_command()`).
+///
+/// `stall_threshold_msec` should be several times `web_console.report_interval_msec` in the config.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `Unavailable`: metrics reports are not received, or the first report has not arrived yet.
+/// - `CNull`: `pipeline` or `report` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_pipeline_health(
+    pipeline: *const SpringPipeline,
+    stall_threshold_msec: u32,
+    report: *mut SpringHealthReport,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, report);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let stall_threshold = Duration::from_millis(stall_threshold_msec as u64);
+    match with_catch(|| {
+        components(pipeline, stall_threshold).map(|components| SpringHealthReport::new(&components))
+    }) {
+        Ok(v) => {
+            *report = v;
+            SpringErrno::Ok
+        }
+        Err(e) => e,
+    }
+}
+
+/// Get pipeline health as a JSON string, with the status and last error of each component.
+///
+/// ```json
+/// {
+///   "status": "failed",
+///   "components": [
+///     {
+///       "name": "SourceWorker", "kind": "worker", "status": "failed", "progress_age_msec": null,
+///       "last_error": {
+///         "errno": -2, "errno_name": "ForeignIo", "panic": false, "age_msec": 120, "n_pipelines": 1,
+///         "message": "ForeignIo { foreign_info: GenericTcp(127.0.0.1:17890), source: failed to connect to remote host ... }"
+///       }
+///     },
+///     {"name": "GenericWorker", "kind": "worker", "status": "alive", "progress_age_msec": null, "last_error": null},
+///     {"name": "PerformanceMonitorWorker", "kind": "worker", "status": "alive", "progress_age_msec": 1210, "last_error": null},
+///     {"name": "MemoryStateMachineWorker", "kind": "worker", "status": "alive", "progress_age_msec": null, "last_error": null},
+///     {"name": "PurgerWorker", "kind": "worker", "status": "alive", "progress_age_msec": null, "last_error": null},
+///     {
+///       "name": "tcp_src", "kind": "source-task", "status": "failed", "progress_age_msec": 1830,
+///       "last_error": {
+///         "errno": -2, "errno_name": "ForeignIo", "panic": false, "age_msec": 120, "n_pipelines": 1,
+///         "message": "ForeignIo { foreign_info: GenericTcp(127.0.0.1:17890), source: failed to connect to remote host ... }"
+///       }
+///     },
+///     {"name": "pump_1", "kind": "pump-task", "status": "alive", "progress_age_msec": 30, "last_error": null},
+///     {"name": "queue_sink", "kind": "sink-task", "status": "alive", "progress_age_msec": 30, "last_error": null}
+///   ]
+/// }
+/// ```
+///
+/// `progress_age_msec` is milliseconds since the last progress, and null if not measurable.
+/// `last_error` is the latest error (or the panic) of a worker thread, or of the worker thread running a task,
+/// and is kept after the component recovers. Its `n_pipelines` is the number of pipelines open in the process when it occurred,
+/// and the error may be of another pipeline than `pipeline` unless it is `1` (see `spring_pipeline_health()`).
+/// See `spring_pipeline_health()` for each status.
+///
+/// # Returns
+///
+/// - `> 0`: the length of the JSON string.
+/// - `Unavailable`: metrics reports are not received, or the first report has not arrived yet.
+/// - `CNull`: `pipeline` or `buf` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+/// - `CInsufficient`: `buf_len` is too small to store the JSON string (with a trailing NUL).
+#[no_mangle]
+pub unsafe extern "C" fn spring_pipeline_health_json(
+    pipeline: *const SpringPipeline,
+    stall_threshold_msec: u32,
+    buf: *mut c_char,
+    buf_len: c_int,
+) -> c_int {
+    ensure_not_null!(SpringErrno::CNull as c_int; pipeline, buf);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; pipeline);
+
+    let stall_threshold = Duration::from_millis(stall_threshold_msec as u64);
+    match with_catch(|| {
+        components(pipeline, stall_threshold).map(|components| health_json(&components).to_string())
+    }) {
+        Ok(json) => strcpy(&json, buf, buf_len),
+        Err(e) => e as c_int,
+    }
+}
@@ -0,0 +1,471 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::CStr,
+    fmt::Write,
+    os::raw::{c_char, c_int},
+};
+
+use ::serde_json::{json, Value};
+
+use crate::{
+    c_mem::strcpy,
+    spring_errno::SpringErrno,
+    spring_last_err::LastError,
+    spring_latency::{SpringLatencyHistogram, LATENCY_BUCKET_BOUNDS_NSEC},
+    spring_pipeline::SpringPipeline,
+    spring_queue_stats::SpringQueueStats,
+    with_catch,
+};
+
+/// Summary of pipeline metrics, got by `spring_pipeline_metrics()`.
+///
+/// `spring_pipeline_metrics_json()` gives the breakdown per task and queue.
+///
+/// SpringQL-core reports only the average gain (bytes per second) of each task and rows and bytes held in each queue.
+/// Rows processed by each pump, rows read by each source reader or written by each sink writer, and errors per task are not available.
+/// Errors of source readers and sink writers are reported per worker thread by `spring_pipeline_health_json()` and `spring_poll_background_error()`.
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SpringPipelineMetrics {
+    /// Milliseconds since SpringQL-core reported the metrics below. `-1` if not reported yet.
+    pub report_age_msec: i64,
+    /// Number of tasks (sources, pumps and sinks).
+    pub n_tasks: u64,
+    /// Number of rows in queues between tasks.
+    pub queued_rows: u64,
+    /// Number of rows waiting for their windows to close.
+    pub window_rows: u64,
+    /// Bytes of rows in queues between tasks (the memory usage compared with `memory.upper_limit_bytes`).
+    pub memory_used_bytes: u64,
+    /// `memory.upper_limit_bytes` in the config.
+    pub memory_upper_limit_bytes: u64,
+
+    /// Rows pushed into in memory queues through this client.
+    pub rows_pushed: u64,
+    /// Rows popped from in memory queues through this client.
+    pub rows_popped: u64,
+}
+
+impl From<&SpringPipeline> for SpringPipelineMetrics {
+    fn from(pipeline: &SpringPipeline) -> Self {
+        let mut metrics = SpringPipelineMetrics {
+            report_age_msec: -1,
+            memory_upper_limit_bytes: pipeline.memory_upper_limit_bytes(),
+            ..Default::default()
+        };
+
+        if let Some(report) = pipeline.metrics_report() {
+            metrics.report_age_msec = report.received_at.elapsed().as_millis() as i64;
+            metrics.n_tasks = report.tasks.len() as u64;
+            for queue in &report.queues {
+                if queue.is_window {
+                    metrics.window_rows += queue.rows;
+                } else {
+                    metrics.queued_rows += queue.rows;
+                }
+            }
+            metrics.memory_used_bytes = report.queue_total_bytes();
+        }
+
+        for (_, stats) in pipeline.all_queue_stats() {
+            metrics.rows_pushed += stats.rows_pushed;
+            metrics.rows_popped += stats.rows_popped;
+        }
+        metrics
+    }
+}
+
+fn metrics_json(pipeline: &SpringPipeline) -> Value {
+    let report = pipeline.metrics_report();
+
+    let (report_age_msec, tasks, queues, memory_used_bytes) = match report {
+        Some(report) => (
+            Some(report.received_at.elapsed().as_millis() as u64),
+            report
+                .tasks
+                .iter()
+                .map(|task| {
+                    json!({
+                        "id": task.id,
+                        "type": task.type_,
+                        "avg_gain_bytes_per_sec": task.avg_gain_bytes_per_sec,
+                    })
+                })
+                .collect(),
+            report
+                .queues
+                .iter()
+                .map(|queue| {
+                    json!({
+                        "id": queue.id,
+                        "type": if queue.is_window { "window-queue" } else { "row-queue" },
+                        "upstream_task_id": queue.upstream_task_id,
+                        "downstream_task_id": queue.downstream_task_id,
+                        "rows": queue.rows,
+                        "bytes": queue.bytes,
+                    })
+                })
+                .collect(),
+            Some(report.queue_total_bytes()),
+        ),
+        None => (None, Vec::new(), Vec::new(), None),
+    };
+
+    let latency_histograms = pipeline.all_latency_histograms();
+    let in_memory_queues: Vec<_> = pipeline
+        .all_queue_stats()
+        .into_iter()
+        .map(|(name, stats)| {
+            let latency = latency_histograms
+                .iter()
+                .find(|(queue, _)| *queue == name)
+                .map(|(_, histogram)| latency_json(histogram));
+            json!({
+                "name": name,
+                "client_depth": stats.client_depth,
+                "rows_pushed": stats.rows_pushed,
+                "rows_popped": stats.rows_popped,
+                "client_rows_dropped": stats.client_rows_dropped,
+                "latency": latency,
+            })
+        })
+        .collect();
+
+    json!({
+        "report_age_msec": report_age_msec,
+        "tasks": tasks,
+        "queues": queues,
+        "in_memory_queues": in_memory_queues,
+        "memory": {
+            "used_bytes": memory_used_bytes,
+            "upper_limit_bytes": pipeline.memory_upper_limit_bytes(),
+        },
+    })
+}
+
+fn latency_json(histogram: &SpringLatencyHistogram) -> Value {
+    let buckets: Vec<_> = histogram
+        .buckets
+        .iter()
+        .enumerate()
+        .map(|(i, count)| {
+            json!({
+                "le_nsec": LATENCY_BUCKET_BOUNDS_NSEC.get(i),
+                "count": count,
+            })
+        })
+        .collect();
+    json!({
+        "count": histogram.count,
+        "sum_nsec": histogram.sum_nsec,
+        "min_nsec": histogram.min_nsec,
+        "max_nsec": histogram.max_nsec,
+        "rows_untraced": histogram.rows_untraced,
+        "buckets": buckets,
+    })
+}
+
+/// Escapes a label value in Prometheus text format.
+fn escape_label(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Metrics in [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format).
+pub(crate) fn prometheus_text(pipeline: &SpringPipeline) -> String {
+    let mut text = String::new();
+    let mut family = |name: &str, type_: &str, help: &str, samples: Vec<(String, String)>| {
+        writeln!(text, "# HELP {} {}", name, help).unwrap();
+        writeln!(text, "# TYPE {} {}", name, type_).unwrap();
+        for (labels, value) in samples {
+            writeln!(text, "{}{} {}", name, labels, value).unwrap();
+        }
+    };
+
+    let report = pipeline.metrics_report();
+    if let Some(report) = &report {
+        family(
+            "springql_metrics_report_age_seconds",
+            "gauge",
+            "Seconds since SpringQL-core reported the metrics.",
+            vec![(
+                String::new(),
+                report.received_at.elapsed().as_secs_f64().to_string(),
+            )],
+        );
+        family(
+            "springql_task_avg_gain_bytes_per_second",
+            "gauge",
+            "Average gain of bytes per second of a task.",
+            report
+                .tasks
+                .iter()
+                .map(|task| {
+                    (
+                        format!(
+                            r#"{{task="{}",type="{}"}}"#,
+                            escape_label(&task.id),
+                            escape_label(&task.type_)
+                        ),
+                        task.avg_gain_bytes_per_sec.to_string(),
+                    )
+                })
+                .collect(),
+        );
+
+        let queue_labels: Vec<_> = report
+            .queues
+            .iter()
+            .map(|queue| {
+                format!(
+                    r#"{{queue="{}",type="{}",upstream_task="{}",downstream_task="{}"}}"#,
+                    escape_label(&queue.id),
+                    if queue.is_window {
+                        "window-queue"
+                    } else {
+                        "row-queue"
+                    },
+                    escape_label(&queue.upstream_task_id),
+                    escape_label(&queue.downstream_task_id)
+                )
+            })
+            .collect();
+        family(
+            "springql_queue_rows",
+            "gauge",
+            "Rows in a queue between tasks.",
+            queue_labels
+                .iter()
+                .zip(&report.queues)
+                .map(|(labels, queue)| (labels.clone(), queue.rows.to_string()))
+                .collect(),
+        );
+        family(
+            "springql_queue_bytes",
+            "gauge",
+            "Bytes of rows in a queue between tasks.",
+            queue_labels
+                .iter()
+                .zip(&report.queues)
+                .map(|(labels, queue)| (labels.clone(), queue.bytes.to_string()))
+                .collect(),
+        );
+        family(
+            "springql_memory_used_bytes",
+            "gauge",
+            "Bytes of rows in queues between tasks, compared with memory.upper_limit_bytes.",
+            vec![(String::new(), report.queue_total_bytes().to_string())],
+        );
+    }
+    family(
+        "springql_memory_upper_limit_bytes",
+        "gauge",
+        "memory.upper_limit_bytes in the config.",
+        vec![(
+            String::new(),
+            pipeline.memory_upper_limit_bytes().to_string(),
+        )],
+    );
+
+    let in_memory_queues = pipeline.all_queue_stats();
+    let in_memory_samples = |value: fn(&SpringQueueStats) -> u64| {
+        in_memory_queues
+            .iter()
+            .map(|(queue, stats)| {
+                (
+                    format!(r#"{{queue="{}"}}"#, escape_label(queue)),
+                    value(stats).to_string(),
+                )
+            })
+            .collect()
+    };
+    family(
+        "springql_in_memory_queue_client_depth",
+        "gauge",
+        "Rows buffered on the client side for an in memory queue.",
+        in_memory_samples(|stats| stats.client_depth),
+    );
+    family(
+        "springql_in_memory_queue_rows_pushed_total",
+        "counter",
+        "Rows pushed into an in memory queue through the client.",
+        in_memory_samples(|stats| stats.rows_pushed),
+    );
+    family(
+        "springql_in_memory_queue_rows_popped_total",
+        "counter",
+        "Rows popped from an in memory queue through the client.",
+        in_memory_samples(|stats| stats.rows_popped),
+    );
+    family(
+        "springql_in_memory_queue_client_rows_dropped_total",
+        "counter",
+        "Rows dropped on the client side by the overflow policy of an in memory queue.",
+        in_memory_samples(|stats| stats.client_rows_dropped),
+    );
+
+    let latency_histograms = pipeline.all_latency_histograms();
+    if !latency_histograms.is_empty() {
+        let name = "springql_in_memory_queue_latency_seconds";
+        writeln!(
+            text,
+            "# HELP {} Latency from ingestion to pop of rows in an in memory sink queue.",
+            name
+        )
+        .unwrap();
+        writeln!(text, "# TYPE {} histogram", name).unwrap();
+        for (queue, histogram) in latency_histograms {
+            let queue = escape_label(&queue);
+            let mut cumulative = 0;
+            for (i, count) in histogram.buckets.iter().enumerate() {
+                cumulative += count;
+                let le = LATENCY_BUCKET_BOUNDS_NSEC.get(i).map_or_else(
+                    || "+Inf".to_string(),
+                    |nsec| (*nsec as f64 * 1e-9).to_string(),
+                );
+                writeln!(
+                    text,
+                    r#"{}_bucket{{queue="{}",le="{}"}} {}"#,
+                    name, queue, le, cumulative
+                )
+                .unwrap();
+            }
+            writeln!(
+                text,
+                r#"{}_sum{{queue="{}"}} {}"#,
+                name,
+                queue,
+                histogram.sum_nsec as f64 * 1e-9
+            )
+            .unwrap();
+            writeln!(
+                text,
+                r#"{}_count{{queue="{}"}} {}"#,
+                name, queue, histogram.count
+            )
+            .unwrap();
+        }
+    }
+
+    text
+}
+
+/// Get a summary of pipeline metrics.
+///
+/// Metrics from SpringQL-core are received every `web_console.report_interval_msec`
+/// if `client.enable_metrics_receiver` is true in the config (see `spring_open()`).
+/// Otherwise, only the counters of in memory queues are available.
+/// See `SpringPipelineMetrics` for metrics not provided by SpringQL-core (e.g. row counts per pump).
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `CNull`: `pipeline` or `metrics` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_pipeline_metrics(
+    pipeline: *const SpringPipeline,
+    metrics: *mut SpringPipelineMetrics,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline, metrics);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    match with_catch(|| Ok::<_, LastError>(SpringPipelineMetrics::from(pipeline))) {
+        Ok(v) => {
+            *metrics = v;
+            SpringErrno::Ok
+        }
+        Err(e) => e,
+    }
+}
+
+/// Get pipeline metrics as a JSON string.
+///
+/// ```json
+/// {
+///   "report_age_msec": 8,
+///   "tasks": [
+///     {"id": "queue_src", "type": "source-task", "avg_gain_bytes_per_sec": 748229.625},
+///     {"id": "pump_1", "type": "pump-task", "avg_gain_bytes_per_sec": 0.0},
+///     {"id": "queue_sink", "type": "sink-task", "avg_gain_bytes_per_sec": -1980383.0}
+///   ],
+///   "queues": [
+///     {
+///       "id": "pump_1-source_1", "type": "row-queue", "upstream_task_id": "queue_src", "downstream_task_id": "pump_1",
+///       "rows": 0, "bytes": 0
+///     },
+///     {
+///       "id": "queue_sink", "type": "row-queue", "upstream_task_id": "pump_1", "downstream_task_id": "queue_sink",
+///       "rows": 0, "bytes": 0
+///     }
+///   ],
+///   "in_memory_queues": [
+///     {"name": "q_sink", "client_depth": 0, "rows_pushed": 0, "rows_popped": 3, "client_rows_dropped": 0, "latency": null},
+///     {"name": "q_src", "client_depth": 0, "rows_pushed": 3, "rows_popped": 0, "client_rows_dropped": 0, "latency": null}
+///   ],
+///   "memory": {"used_bytes": 0, "upper_limit_bytes": 10000000}
+/// }
+/// ```
+///
+/// Queues of type `window-queue` hold rows waiting for their windows to close.
+/// `latency` of an in memory queue is null unless traced by `spring_latency_trace()`. Otherwise, it has the fields of `SpringLatencyHistogram`
+/// and `buckets` as `[{"le_nsec": 50000, "count": 1}, ..., {"le_nsec": null, "count": 0}]`.
+/// `report_age_msec` and `memory.used_bytes` are null, and `tasks` and `queues` are empty, until SpringQL-core reports metrics.
+/// See `spring_pipeline_metrics()` for when metrics are reported and `SpringPipelineMetrics` for each value.
+///
+/// # Returns
+///
+/// - `> 0`: the length of the JSON string.
+/// - `CNull`: `pipeline` or `buf` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+/// - `CInsufficient`: `buf_len` is too small to store the JSON string (with a trailing NUL).
+#[no_mangle]
+pub unsafe extern "C" fn spring_pipeline_metrics_json(
+    pipeline: *const SpringPipeline,
+    buf: *mut c_char,
+    buf_len: c_int,
+) -> c_int {
+    ensure_not_null!(SpringErrno::CNull as c_int; pipeline, buf);
+    ensure_live!(SpringErrno::CInvalidHandle as c_int; pipeline);
+
+    match with_catch(|| Ok::<_, LastError>(metrics_json(pipeline).to_string())) {
+        Ok(json) => strcpy(&json, buf, buf_len),
+        Err(e) => e as c_int,
+    }
+}
+
+/// Serve pipeline metrics in [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format)
+/// at `GET /metrics` on a library-owned thread.
+///
+/// Exposes the same metrics as `spring_pipeline_metrics_json()` (`springql_task_*`, `springql_queue_*`, `springql_memory_*`
+/// and `springql_in_memory_queue_*`). Metrics from SpringQL-core are omitted until they are reported.
+/// Latency traced by `spring_latency_trace()` is a histogram `springql_in_memory_queue_latency_seconds`.
+///
+/// # Parameters
+///
+/// - `addr`: Address to listen on (e.g. `127.0.0.1:9187`). NULL to stop serving.
+///
+/// # Returns
+///
+/// - `Ok`: on success.
+/// - `InvalidOption`: failed to listen on `addr`.
+/// - `Unavailable`: metrics of `pipeline` are already served. Stop serving first to change `addr`.
+/// - `CNull`: `pipeline` is a NULL pointer.
+/// - `CInvalidHandle`: `pipeline` is already freed.
+#[no_mangle]
+pub unsafe extern "C" fn spring_metrics_serve(
+    pipeline: *const SpringPipeline,
+    addr: *const c_char,
+) -> SpringErrno {
+    ensure_not_null!(SpringErrno::CNull; pipeline);
+    ensure_live!(SpringErrno::CInvalidHandle; pipeline);
+
+    let addr = (!addr.is_null()).then(|| CStr::from_ptr(addr).to_string_lossy().into_owned());
+    match with_catch(|| pipeline.serve_metrics(addr.as_deref())) {
+        Ok(()) => SpringErrno::Ok,
+        Err(e) => e,
+    }
+}
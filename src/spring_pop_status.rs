@@ -0,0 +1,17 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+/// Result of a pop with timeout.
+///
+/// cbindgen:prefix-with-name
+/// cbindgen:rename-all=ScreamingSnakeCase
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SpringPopStatus {
+    /// A row is popped.
+    Row = 0,
+    /// No row arrived until the timeout elapsed.
+    Timeout = 1,
+    /// Error occurred. Check spring_last_err() for details.
+    Error = -1,
+}
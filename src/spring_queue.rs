@@ -0,0 +1,24 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use ::springql::Result;
+
+use crate::handle;
+
+/// Handle of an in memory queue, validated by `spring_queue_open()`. Only holds the queue name.
+#[non_exhaustive]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SpringQueue(String);
+
+impl SpringQueue {
+    pub(crate) fn new(name: String) -> Self {
+        Self(name)
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.0
+    }
+
+    pub(crate) fn into_ptr(self) -> Result<*mut SpringQueue> {
+        handle::into_handle(self)
+    }
+}
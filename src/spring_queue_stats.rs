@@ -0,0 +1,100 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Mutex, MutexGuard,
+};
+
+use ::springql::SpringSinkRow as RuSpringSinkRow;
+
+use crate::spring_latency::{LatencyTrace, SpringLatencyHistogram};
+
+/// Statistics of an in memory queue on the C client side, filled by `spring_queue_stats()`.
+///
+/// Counters only include rows which went through this client. They are not statistics of the queue inside SpringQL-core:
+/// SpringQL-core does not expose rows or bytes held in its in memory queues.
+/// Rows and bytes held in the queues between tasks are in the metrics report (see `spring_pipeline_metrics()`).
+#[repr(C)]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct SpringQueueStats {
+    /// Number of rows held on the C client side.
+    ///
+    /// - Sink queues are buffered (up to 1024 rows) after the first blocking pop, `spring_queue_fd()` or `spring_subscribe()`.
+    /// - Source queues are staged with an overflow policy (see `spring_queue_set_overflow_policy()`).
+    ///
+    /// Always 0 for other queues.
+    pub client_depth: u64,
+    /// Total number of rows pushed by `spring_push()`, `spring_push_batch()` or `spring_queue_push()`.
+    pub rows_pushed: u64,
+    /// Total number of rows popped by any pop function or delivered to a subscriber.
+    pub rows_popped: u64,
+    /// Total number of rows dropped on the C client side by the overflow policy of a source queue (see `spring_queue_set_overflow_policy()`).
+    ///
+    /// Always 0 for sink queues. Rows purged inside SpringQL-core on memory pressure are not counted.
+    pub client_rows_dropped: u64,
+}
+
+/// Counters of an in memory queue, shared among the pipeline and dispatchers.
+#[derive(Debug, Default)]
+pub(crate) struct QueueCounters {
+    rows_pushed: AtomicU64,
+    rows_popped: AtomicU64,
+    rows_dropped: AtomicU64,
+
+    /// Set by `trace_latency()` for a sink queue.
+    latency: Mutex<Option<LatencyTrace>>,
+    /// Whether `latency` is set, so that popping untraced rows does not take the lock.
+    latency_traced: AtomicBool,
+}
+
+impl QueueCounters {
+    pub(crate) fn add_pushed(&self, n: u64) {
+        self.rows_pushed.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_popped(&self, n: u64) {
+        self.rows_popped.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_dropped(&self, n: u64) {
+        self.rows_dropped.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Counts a popped row and records its latency if traced.
+    pub(crate) fn popped(&self, row: &RuSpringSinkRow) {
+        self.add_popped(1);
+        if !self.latency_traced.load(Ordering::Acquire) {
+            return;
+        }
+        if let Some(latency) = self.lock_latency().as_mut() {
+            latency.record(row);
+        }
+    }
+
+    /// Starts (or restarts) recording latency of popped rows.
+    pub(crate) fn trace_latency(&self, ingest_time_col: usize) {
+        *self.lock_latency() = Some(LatencyTrace::new(ingest_time_col));
+        self.latency_traced.store(true, Ordering::Release);
+    }
+
+    /// `None` if latency is not traced.
+    pub(crate) fn latency_histogram(&self) -> Option<SpringLatencyHistogram> {
+        self.lock_latency().as_ref().map(LatencyTrace::histogram)
+    }
+
+    fn lock_latency(&self) -> MutexGuard<'_, Option<LatencyTrace>> {
+        self.latency
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Snapshot of counters. `client_depth` is left 0.
+    pub(crate) fn stats(&self) -> SpringQueueStats {
+        SpringQueueStats {
+            client_depth: 0,
+            rows_pushed: self.rows_pushed.load(Ordering::Relaxed),
+            rows_popped: self.rows_popped.load(Ordering::Relaxed),
+            client_rows_dropped: self.rows_dropped.load(Ordering::Relaxed),
+        }
+    }
+}
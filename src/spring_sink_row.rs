@@ -2,6 +2,8 @@
 
 use springql::{Result, SpringSinkRow as RuSpringSinkRow, SpringValue};
 
+use crate::handle;
+
 /// Row object to pop from an in memory queue.
 #[non_exhaustive]
 #[derive(Debug)]
@@ -21,7 +23,7 @@ impl SpringSinkRow {
         self.0.get_not_null_by_index(i_col)
     }
 
-    pub(crate) fn into_ptr(self) -> *mut Self {
-        Box::into_raw(Box::new(self))
+    pub(crate) fn into_ptr(self) -> Result<*mut Self> {
+        handle::into_handle(self)
     }
 }
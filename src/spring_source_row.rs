@@ -1,6 +1,8 @@
 // This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
 
-use ::springql::SpringSourceRow as RuSpringSourceRow;
+use ::springql::{Result, SpringSourceRow as RuSpringSourceRow};
+
+use crate::handle;
 
 /// Row object to push into an in memory queue.
 #[non_exhaustive]
@@ -19,7 +21,7 @@ impl From<SpringSourceRow> for RuSpringSourceRow {
 }
 
 impl SpringSourceRow {
-    pub fn into_ptr(self) -> *mut SpringSourceRow {
-        Box::into_raw(Box::new(self))
+    pub fn into_ptr(self) -> Result<*mut SpringSourceRow> {
+        handle::into_handle(self)
     }
 }
@@ -1,6 +1,8 @@
 // This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
 
-use ::springql::SpringSourceRowBuilder as RuSpringSourceRowBuilder;
+use ::springql::{Result, SpringSourceRowBuilder as RuSpringSourceRowBuilder};
+
+use crate::handle;
 
 /// Builder of SpringSourceRow
 #[non_exhaustive]
@@ -19,7 +21,7 @@ impl From<SpringSourceRowBuilder> for RuSpringSourceRowBuilder {
 }
 
 impl SpringSourceRowBuilder {
-    pub fn into_ptr(self) -> *mut SpringSourceRowBuilder {
-        Box::into_raw(Box::new(self))
+    pub fn into_ptr(self) -> Result<*mut SpringSourceRowBuilder> {
+        handle::into_handle(self)
     }
 }
@@ -0,0 +1,162 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::{c_void, CStr},
+    os::raw::{c_char, c_float, c_int, c_long, c_short, c_uint},
+    ptr, slice,
+};
+
+use ::anyhow::anyhow;
+use ::springql::{
+    error::SpringError, Result, SpringSourceRow as RuSpringSourceRow,
+    SpringSourceRowBuilder as RuSpringSourceRowBuilder,
+};
+
+/// Type of a C struct member to encode into a column.
+///
+/// cbindgen:prefix-with-name
+/// cbindgen:rename-all=ScreamingSnakeCase
+#[non_exhaustive]
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SpringColumnType {
+    /// `short` member. Encoded into a SMALLINT column.
+    Short = 0,
+    /// `int` member. Encoded into an INTEGER column.
+    Int = 1,
+    /// `long` member. Encoded into a BIGINT column.
+    Long = 2,
+    /// `unsigned int` member. Encoded into an UNSIGNED INTEGER column.
+    UnsignedInt = 3,
+    /// `float` member. Encoded into a FLOAT column.
+    Float = 4,
+    /// `bool` member. Encoded into a BOOLEAN column.
+    Bool = 5,
+    /// `char[len]` member. Encoded into a TEXT column up to the first NUL byte (or `len` bytes).
+    Text = 6,
+    /// `uint8_t[len]` member. Encoded into a BLOB column.
+    Blob = 7,
+}
+
+impl SpringColumnType {
+    fn from_c_int(column_type: c_int) -> Option<Self> {
+        match column_type {
+            0 => Some(SpringColumnType::Short),
+            1 => Some(SpringColumnType::Int),
+            2 => Some(SpringColumnType::Long),
+            3 => Some(SpringColumnType::UnsignedInt),
+            4 => Some(SpringColumnType::Float),
+            5 => Some(SpringColumnType::Bool),
+            6 => Some(SpringColumnType::Text),
+            7 => Some(SpringColumnType::Blob),
+            _ => None,
+        }
+    }
+}
+
+/// A C struct member to encode into a column.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SpringStructColumn {
+    /// Column name. NUL-terminated string.
+    pub name: *const c_char,
+    /// Type of the member. One of `SpringColumnType`.
+    pub column_type: c_int,
+    /// Offset of the member from the head of the struct (`offsetof()`).
+    pub offset: usize,
+    /// Size of the member in bytes. Only used for `TEXT` and `BLOB`.
+    pub len: usize,
+}
+
+/// Layout descriptor of a C struct.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SpringStructLayout {
+    /// Array of members to encode into columns.
+    pub columns: *const SpringStructColumn,
+    /// Number of elements in `columns`.
+    pub n_columns: usize,
+}
+
+impl SpringStructLayout {
+    /// # Returns
+    ///
+    /// - `Err`: the name of the NULL pointer in the layout.
+    pub(crate) unsafe fn validate(&self) -> std::result::Result<(), &'static str> {
+        if self.n_columns == 0 {
+            return Ok(());
+        }
+        if self.columns.is_null() {
+            return Err("layout->columns");
+        }
+        let columns = slice::from_raw_parts(self.columns, self.n_columns);
+        if columns.iter().any(|column| column.name.is_null()) {
+            return Err("layout->columns[i].name");
+        }
+        Ok(())
+    }
+
+    /// Encodes the struct pointed by `s` into a source row.
+    ///
+    /// Members are read unaligned so that packed structs are also supported.
+    ///
+    /// # Failure
+    ///
+    /// - `SpringError::Sql` when:
+    ///   - the same column name appears twice in the layout.
+    /// - `SpringError::InvalidOption` when:
+    ///   - a column type is not a `SpringColumnType`.
+    pub(crate) unsafe fn to_source_row(&self, s: *const c_void) -> Result<RuSpringSourceRow> {
+        let columns = if self.n_columns == 0 {
+            &[]
+        } else {
+            slice::from_raw_parts(self.columns, self.n_columns)
+        };
+
+        let s = s as *const u8;
+        let mut builder = RuSpringSourceRowBuilder::default();
+        for column in columns {
+            let name = CStr::from_ptr(column.name).to_string_lossy().into_owned();
+            let p = s.add(column.offset);
+
+            let column_type =
+                SpringColumnType::from_c_int(column.column_type).ok_or_else(|| {
+                    SpringError::InvalidOption {
+                        key: format!("column_type of {}", name),
+                        value: column.column_type.to_string(),
+                        source: anyhow!("not a SpringColumnType"),
+                    }
+                })?;
+            builder = match column_type {
+                SpringColumnType::Short => {
+                    builder.add_column(name, ptr::read_unaligned(p as *const c_short))?
+                }
+                SpringColumnType::Int => {
+                    builder.add_column(name, ptr::read_unaligned(p as *const c_int))?
+                }
+                SpringColumnType::Long => {
+                    builder.add_column(name, ptr::read_unaligned(p as *const c_long))?
+                }
+                SpringColumnType::UnsignedInt => {
+                    builder.add_column(name, ptr::read_unaligned(p as *const c_uint))?
+                }
+                SpringColumnType::Float => {
+                    builder.add_column(name, ptr::read_unaligned(p as *const c_float))?
+                }
+                SpringColumnType::Bool => builder.add_column(name, ptr::read(p) != 0)?,
+                SpringColumnType::Text => {
+                    let bytes = slice::from_raw_parts(p, column.len);
+                    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+                    let v = String::from_utf8_lossy(&bytes[..len]).into_owned();
+                    builder.add_column(name, v)?
+                }
+                SpringColumnType::Blob => {
+                    let v = slice::from_raw_parts(p, column.len).to_vec();
+                    builder.add_column(name, v)?
+                }
+            };
+        }
+
+        Ok(builder.build())
+    }
+}
@@ -1,6 +1,32 @@
 // This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
 
+mod http_server;
+mod invalid_handle;
+mod log_file;
+mod null_pointer;
+mod panic;
+mod spring_background_error;
 mod spring_config;
+mod spring_errno;
+mod spring_error;
+mod spring_latency;
+mod spring_log;
+mod spring_memory;
+mod spring_metrics_serve;
 mod spring_open;
+mod spring_overflow_policy;
+mod spring_pipeline_health;
+mod spring_pipeline_interrupt;
+mod spring_pipeline_metrics;
+mod spring_pop_batch;
+mod spring_pop_multithread;
+mod spring_pop_timeout;
+mod spring_push_batch;
+mod spring_push_json;
+mod spring_queue;
+mod spring_queue_fd;
+mod spring_queue_stats;
 mod spring_sink_row;
 mod spring_source_row_builder;
+mod spring_source_row_from_struct;
+mod spring_subscribe;
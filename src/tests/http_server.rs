@@ -0,0 +1,120 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::http_server::{HttpServer, Response};
+
+fn start() -> HttpServer {
+    HttpServer::start("127.0.0.1:0", "test-http-server", |request| {
+        match request.path.as_str() {
+            "/panic" => panic!("handler panic"),
+            _ => Response::ok("text/plain", format!("{} bytes", request.body.len())),
+        }
+    })
+    .unwrap()
+}
+
+fn send(addr: SocketAddr, request: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response);
+    response
+}
+
+#[test]
+fn test_http_server_body() {
+    let server = start();
+    let response = send(
+        server.local_addr(),
+        "POST /task-graph HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello",
+    );
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\n5 bytes"));
+}
+
+#[test]
+fn test_http_server_rejects_invalid_requests() {
+    let server = start();
+    let addr = server.local_addr();
+
+    let response = send(
+        addr,
+        "POST /task-graph HTTP/1.1\r\nContent-Length: 18446744073709551615\r\n\r\n",
+    );
+    assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+
+    let response = send(
+        addr,
+        &format!(
+            "GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(16 * 1024)
+        ),
+    );
+    assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+
+    for request in [
+        "POST /task-graph HTTP/1.1\r\nContent-Length: many\r\n\r\n",
+        "POST /task-graph HTTP/1.1\r\nContent-Length: -1\r\n\r\n",
+        "GET\r\n\r\n",
+    ] {
+        let response = send(addr, request);
+        assert!(
+            response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+            "{:?}",
+            request
+        );
+    }
+}
+
+#[test]
+fn test_http_server_survives_handler_panic() {
+    let server = start();
+    let addr = server.local_addr();
+
+    let response = send(addr, "GET /panic HTTP/1.1\r\n\r\n");
+    assert!(response.is_empty());
+
+    let response = send(addr, "GET / HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+}
+
+#[test]
+fn test_http_server_request_timeout() {
+    let server = start();
+    let addr = server.local_addr();
+
+    // a client trickling bytes is cut off at the deadline of the whole request
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"POST /task-graph HTTP/1.1\r\n").unwrap();
+    let started = Instant::now();
+    for _ in 0..20 {
+        if stream.write_all(b"X-Slow: 1\r\n").is_err() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response);
+    assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+    assert!(started.elapsed() < Duration::from_secs(3));
+
+    let response = send(addr, "GET / HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+}
+
+#[test]
+fn test_http_server_drop_with_idle_connection() {
+    let server = start();
+    let _idle = TcpStream::connect(server.local_addr()).unwrap();
+    thread::sleep(Duration::from_millis(100));
+
+    let started = Instant::now();
+    drop(server);
+    assert!(started.elapsed() < Duration::from_secs(3));
+}
@@ -0,0 +1,171 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::ffi::{CStr, CString};
+
+use crate::{spring_error::*, spring_last_err::spring_last_err, *};
+
+/// Asserts the last error is `CInvalidHandle` for the argument `arg`.
+unsafe fn assert_invalid_handle_err(arg: &str) {
+    let mut errno = SpringErrno::Ok;
+    let mut errmsg = [0; 1024];
+    assert!(spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int) > 0);
+    assert_eq!(errno, SpringErrno::CInvalidHandle);
+
+    let errmsg = CStr::from_ptr(errmsg.as_ptr()).to_str().unwrap();
+    assert_eq!(
+        errmsg,
+        format!(
+            "CInvalidHandle: `{}` is already freed or not created by this library",
+            arg
+        )
+    );
+}
+
+#[test]
+fn test_double_close() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let name = CString::new("q_invalid_handle_missing").unwrap();
+        let mut is_err = false;
+        spring_pop_non_blocking(pipeline, name.as_ptr(), &mut is_err);
+        let err = spring_last_err_detail();
+        assert_eq!(spring_error_free(err), SpringErrno::Ok);
+        assert_eq!(spring_error_free(err), SpringErrno::CInvalidHandle);
+        assert_invalid_handle_err("err");
+
+        assert_eq!(spring_close(pipeline), SpringErrno::Ok);
+        assert_eq!(spring_close(pipeline), SpringErrno::CInvalidHandle);
+        assert_invalid_handle_err("pipeline");
+
+        assert_eq!(spring_config_close(config), SpringErrno::Ok);
+        assert_eq!(spring_config_close(config), SpringErrno::CInvalidHandle);
+        assert_invalid_handle_err("config");
+    }
+}
+
+#[test]
+fn test_use_after_close() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        spring_close(pipeline);
+
+        let sql = CString::new("CREATE SOURCE STREAM s (n INTEGER NOT NULL);").unwrap();
+        assert_eq!(
+            spring_command(pipeline, sql.as_ptr()),
+            SpringErrno::CInvalidHandle
+        );
+        assert_invalid_handle_err("pipeline");
+
+        spring_config_close(config);
+        assert!(spring_open(config).is_null());
+        assert_invalid_handle_err("config");
+    }
+}
+
+#[test]
+fn test_source_row_consumed() {
+    unsafe {
+        let builder = spring_source_row_builder();
+        let row = spring_source_row_build(builder);
+        assert!(!row.is_null());
+        assert!(spring_source_row_build(builder).is_null());
+        assert_invalid_handle_err("builder");
+
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        // `row` is freed even though the queue does not exist
+        let queue = CString::new("q_invalid_handle_push").unwrap();
+        assert_eq!(
+            spring_push(pipeline, queue.as_ptr(), row),
+            SpringErrno::Unavailable
+        );
+        assert_eq!(
+            spring_push(pipeline, queue.as_ptr(), row),
+            SpringErrno::CInvalidHandle
+        );
+        assert_invalid_handle_err("row");
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_stale_handle_after_reuse() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        spring_config_close(config);
+
+        for sql in [
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+            "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);",
+            "CREATE PUMP pump_1 AS INSERT INTO sink_1 (n) SELECT STREAM source_1.n FROM source_1;",
+            "CREATE SINK WRITER queue_sink FOR sink_1 TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_stale_sink');",
+            "CREATE SOURCE READER queue_src FOR source_1 TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_stale_src');",
+        ] {
+            let sql = CString::new(sql).unwrap();
+            assert_eq!(spring_command(pipeline, sql.as_ptr()), SpringErrno::Ok);
+        }
+
+        let queue_src = CString::new("q_stale_src").unwrap();
+        for n in 1..=2 {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            assert_eq!(
+                spring_push_json(pipeline, queue_src.as_ptr(), json.as_ptr()),
+                SpringErrno::Ok
+            );
+        }
+
+        let queue_sink = CString::new("q_stale_sink").unwrap();
+        let row_1 = spring_pop(pipeline, queue_sink.as_ptr());
+        assert_eq!(spring_sink_row_close(row_1), SpringErrno::Ok);
+
+        // the slot of `row_1` is reused by `row_2`
+        let row_2 = spring_pop(pipeline, queue_sink.as_ptr());
+        assert!(!row_2.is_null());
+        assert_ne!(row_1, row_2);
+
+        assert_eq!(spring_sink_row_close(row_1), SpringErrno::CInvalidHandle);
+        assert_invalid_handle_err("row");
+
+        let mut n = 0;
+        assert_eq!(spring_column_int(row_2, 0, &mut n), SpringErrno::Ok);
+        assert_eq!(n, 2);
+        assert_eq!(spring_sink_row_close(row_2), SpringErrno::Ok);
+
+        spring_close(pipeline);
+    }
+}
+
+#[test]
+fn test_foreign_pointer() {
+    unsafe {
+        // not created by this library
+        let mut fake = [0_u64; 16];
+        let row = fake.as_mut_ptr() as *mut SpringSinkRow;
+
+        let mut n = 0;
+        assert_eq!(
+            spring_column_int(row, 0, &mut n),
+            SpringErrno::CInvalidHandle
+        );
+        assert_invalid_handle_err("row");
+        assert_eq!(spring_sink_row_close(row), SpringErrno::CInvalidHandle);
+        assert_invalid_handle_err("row");
+
+        // a handle of another type
+        let config = spring_config_default();
+        let pipeline = config as *mut SpringPipeline;
+        assert_eq!(
+            spring_pipeline_interrupt(pipeline),
+            SpringErrno::CInvalidHandle
+        );
+        assert_invalid_handle_err("pipeline");
+        assert_eq!(spring_config_close(config), SpringErrno::Ok);
+    }
+}
@@ -0,0 +1,25 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{env, fs, io::Write, process};
+
+use crate::log_file::RotatingFile;
+
+#[test]
+fn test_rotating_file() {
+    let dir = env::temp_dir().join(format!("springql-client-test-rotating-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("test.log");
+
+    let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+    for s in ["aaaaaaaa", "bbbbbbbb", "cccc", "dddddddd"] {
+        file.write_all(s.as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+
+    let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(read("test.log"), "dddddddd");
+    assert_eq!(read("test.log.1"), "cccc");
+    assert_eq!(read("test.log.2"), "bbbbbbbb");
+    assert!(!dir.join("test.log.3").exists());
+}
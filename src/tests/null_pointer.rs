@@ -0,0 +1,510 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{ffi::CString, ptr::null_mut};
+
+use crate::{spring_error::*, spring_last_err::spring_last_err, *};
+
+fn null<T>() -> *mut T {
+    null_mut()
+}
+
+/// Asserts the last error is `CNull` for the argument `arg`.
+unsafe fn assert_null_err(arg: &str) {
+    let mut errno = SpringErrno::Ok;
+    let mut errmsg = [0; 1024];
+    assert!(spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int) > 0);
+    assert_eq!(errno, SpringErrno::CNull);
+
+    let errmsg = std::ffi::CStr::from_ptr(errmsg.as_ptr()).to_str().unwrap();
+    assert_eq!(errmsg, format!("CNull: `{}` is a NULL pointer", arg));
+}
+
+/// Runs `f` with an open pipeline.
+unsafe fn with_pipeline(f: impl FnOnce(*mut SpringPipeline)) {
+    let config = spring_config_default();
+    let pipeline = spring_open(config);
+    f(pipeline);
+    spring_close(pipeline);
+    spring_config_close(config);
+}
+
+#[test]
+fn test_spring_config_toml_null() {
+    unsafe {
+        assert!(spring_config_toml(null()).is_null());
+        assert_null_err("overwrite_config_toml");
+    }
+}
+
+#[test]
+fn test_spring_config_close_null() {
+    unsafe {
+        assert_eq!(spring_config_close(null()), SpringErrno::CNull);
+        assert_null_err("config");
+    }
+}
+
+#[test]
+fn test_spring_open_null() {
+    unsafe {
+        assert!(spring_open(null()).is_null());
+        assert_null_err("config");
+    }
+}
+
+#[test]
+fn test_spring_close_null() {
+    unsafe {
+        assert_eq!(spring_close(null()), SpringErrno::CNull);
+        assert_null_err("pipeline");
+    }
+}
+
+#[test]
+fn test_spring_command_null() {
+    unsafe {
+        let sql = CString::new("CREATE SOURCE STREAM s (n INTEGER NOT NULL);").unwrap();
+        assert_eq!(spring_command(null(), sql.as_ptr()), SpringErrno::CNull);
+        assert_null_err("pipeline");
+
+        with_pipeline(|pipeline| {
+            assert_eq!(spring_command(pipeline, null()), SpringErrno::CNull);
+            assert_null_err("sql");
+        });
+    }
+}
+
+#[test]
+fn test_spring_pop_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            assert!(spring_pop(pipeline, null()).is_null());
+            assert_null_err("queue");
+        });
+    }
+}
+
+#[test]
+fn test_spring_pop_non_blocking_null() {
+    unsafe {
+        let queue = CString::new("q").unwrap();
+        let mut is_err = false;
+        assert!(spring_pop_non_blocking(null(), queue.as_ptr(), &mut is_err).is_null());
+        assert!(is_err);
+        assert_null_err("pipeline");
+
+        with_pipeline(|pipeline| {
+            assert!(spring_pop_non_blocking(pipeline, queue.as_ptr(), null()).is_null());
+            assert_null_err("is_err");
+        });
+    }
+}
+
+#[test]
+fn test_spring_pop_timeout_null() {
+    unsafe {
+        let queue = CString::new("q").unwrap();
+        let mut status = SpringPopStatus::Row;
+        assert!(spring_pop_timeout(null(), queue.as_ptr(), 0, &mut status).is_null());
+        assert_eq!(status, SpringPopStatus::Error);
+        assert_null_err("pipeline");
+
+        with_pipeline(|pipeline| {
+            assert!(spring_pop_timeout(pipeline, queue.as_ptr(), 0, null()).is_null());
+            assert_null_err("status");
+        });
+    }
+}
+
+#[test]
+fn test_spring_pop_batch_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            let queue = CString::new("q").unwrap();
+            assert_eq!(
+                spring_pop_batch(pipeline, queue.as_ptr(), null(), 8, 0),
+                SpringErrno::CNull as c_int
+            );
+            assert_null_err("out_rows");
+        });
+    }
+}
+
+#[test]
+fn test_spring_queue_fd_null() {
+    unsafe {
+        let queue = CString::new("q").unwrap();
+        assert_eq!(
+            spring_queue_fd(null(), queue.as_ptr()),
+            SpringErrno::CNull as c_int
+        );
+        assert_null_err("pipeline");
+    }
+}
+
+#[test]
+fn test_spring_subscribe_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            let queue = CString::new("q").unwrap();
+            assert_eq!(
+                spring_subscribe(pipeline, queue.as_ptr(), None, null()),
+                SpringErrno::CNull
+            );
+            assert_null_err("callback");
+        });
+    }
+}
+
+#[test]
+fn test_spring_unsubscribe_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            assert_eq!(spring_unsubscribe(pipeline, null()), SpringErrno::CNull);
+            assert_null_err("queue");
+        });
+    }
+}
+
+#[test]
+fn test_spring_pipeline_interrupt_null() {
+    unsafe {
+        assert_eq!(spring_pipeline_interrupt(null()), SpringErrno::CNull);
+        assert_null_err("pipeline");
+    }
+}
+
+#[test]
+fn test_spring_queue_interrupt_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            assert_eq!(spring_queue_interrupt(pipeline, null()), SpringErrno::CNull);
+            assert_null_err("queue");
+        });
+    }
+}
+
+#[test]
+fn test_spring_push_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            let queue = CString::new("q").unwrap();
+            assert_eq!(
+                spring_push(pipeline, queue.as_ptr(), null()),
+                SpringErrno::CNull
+            );
+            assert_null_err("row");
+
+            // `row` is freed
+            let row = spring_source_row_build(spring_source_row_builder());
+            assert_eq!(spring_push(pipeline, null(), row), SpringErrno::CNull);
+            assert_null_err("queue");
+        });
+    }
+}
+
+#[test]
+fn test_spring_push_batch_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            let queue = CString::new("q").unwrap();
+            assert_eq!(
+                spring_push_batch(pipeline, queue.as_ptr(), null(), 1, null()),
+                SpringErrno::CNull
+            );
+            assert_null_err("rows");
+
+            let rows = [null(), null()];
+            let mut statuses = [SpringErrno::Ok; 2];
+            assert_eq!(
+                spring_push_batch(
+                    pipeline,
+                    queue.as_ptr(),
+                    rows.as_ptr(),
+                    2,
+                    statuses.as_mut_ptr()
+                ),
+                SpringErrno::CNull
+            );
+            assert_eq!(statuses, [SpringErrno::CNull; 2]);
+            assert_null_err("rows[1]");
+
+            // `rows` are freed
+            let rows = [spring_source_row_build(spring_source_row_builder())];
+            assert_eq!(
+                spring_push_batch(pipeline, null(), rows.as_ptr(), 1, null()),
+                SpringErrno::CNull
+            );
+            assert_null_err("queue");
+            assert_eq!(
+                spring_push(pipeline, queue.as_ptr(), rows[0]),
+                SpringErrno::CInvalidHandle
+            );
+        });
+    }
+}
+
+#[test]
+fn test_spring_push_json_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            let queue = CString::new("q").unwrap();
+            assert_eq!(
+                spring_push_json(pipeline, queue.as_ptr(), null()),
+                SpringErrno::CNull
+            );
+            assert_null_err("json");
+        });
+    }
+}
+
+#[test]
+fn test_spring_push_json_lines_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            let queue = CString::new("q").unwrap();
+            assert_eq!(
+                spring_push_json_lines(pipeline, queue.as_ptr(), null(), 1, null()),
+                SpringErrno::CNull
+            );
+            assert_null_err("buf");
+        });
+    }
+}
+
+#[test]
+fn test_spring_queue_stats_null() {
+    unsafe {
+        let queue = CString::new("q").unwrap();
+        let mut stats = SpringQueueStats::default();
+        assert_eq!(
+            spring_queue_stats(null(), queue.as_ptr(), &mut stats),
+            SpringErrno::CNull
+        );
+        assert_null_err("pipeline");
+    }
+}
+
+#[test]
+fn test_spring_queue_open_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            assert!(spring_queue_open(pipeline, null()).is_null());
+            assert_null_err("name");
+        });
+    }
+}
+
+#[test]
+fn test_spring_queue_close_null() {
+    unsafe {
+        assert_eq!(spring_queue_close(null()), SpringErrno::CNull);
+        assert_null_err("queue");
+    }
+}
+
+#[test]
+fn test_spring_queue_push_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            let row = spring_source_row_build(spring_source_row_builder());
+            assert_eq!(spring_queue_push(pipeline, null(), row), SpringErrno::CNull);
+            assert_null_err("queue");
+        });
+    }
+}
+
+#[test]
+fn test_spring_queue_pop_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            assert!(spring_queue_pop(pipeline, null()).is_null());
+            assert_null_err("queue");
+        });
+    }
+}
+
+#[test]
+fn test_spring_queue_pop_non_blocking_null() {
+    unsafe {
+        with_pipeline(|pipeline| {
+            let mut is_err = false;
+            assert!(spring_queue_pop_non_blocking(pipeline, null(), &mut is_err).is_null());
+            assert!(is_err);
+            assert_null_err("queue");
+        });
+    }
+}
+
+#[test]
+fn test_spring_source_row_from_json_null() {
+    unsafe {
+        assert!(spring_source_row_from_json(null()).is_null());
+        assert_null_err("json");
+    }
+}
+
+#[test]
+fn test_spring_source_row_from_struct_null() {
+    unsafe {
+        let s = 0_i32;
+        assert!(spring_source_row_from_struct(null(), &s as *const i32 as *const c_void).is_null());
+        assert_null_err("layout");
+
+        let layout = SpringStructLayout {
+            columns: null(),
+            n_columns: 1,
+        };
+        assert!(
+            spring_source_row_from_struct(&layout, &s as *const i32 as *const c_void).is_null()
+        );
+        assert_null_err("layout->columns");
+    }
+}
+
+#[test]
+fn test_spring_source_row_add_column_blob_null() {
+    unsafe {
+        let v = [0_u8; 4];
+        let column_name = CString::new("c").unwrap();
+        assert!(spring_source_row_add_column_blob(
+            null(),
+            column_name.as_ptr(),
+            v.as_ptr() as *const c_void,
+            v.len() as c_int
+        )
+        .is_null());
+        assert_null_err("builder");
+
+        // `builder` is freed
+        let builder = spring_source_row_builder();
+        assert!(
+            spring_source_row_add_column_blob(builder, column_name.as_ptr(), null(), 4).is_null()
+        );
+        assert_null_err("v");
+    }
+}
+
+#[test]
+fn test_spring_source_row_build_null() {
+    unsafe {
+        assert!(spring_source_row_build(null()).is_null());
+        assert_null_err("builder");
+    }
+}
+
+#[test]
+fn test_spring_sink_row_close_null() {
+    unsafe {
+        assert_eq!(spring_sink_row_close(null()), SpringErrno::CNull);
+        assert_null_err("row");
+    }
+}
+
+#[test]
+fn test_spring_column_null() {
+    unsafe {
+        let mut short = 0;
+        assert_eq!(
+            spring_column_short(null(), 0, &mut short),
+            SpringErrno::CNull
+        );
+        assert_null_err("row");
+        let mut int = 0;
+        assert_eq!(spring_column_int(null(), 0, &mut int), SpringErrno::CNull);
+        assert_null_err("row");
+        let mut long = 0;
+        assert_eq!(spring_column_long(null(), 0, &mut long), SpringErrno::CNull);
+        assert_null_err("row");
+        let mut unsigned_int = 0;
+        assert_eq!(
+            spring_column_unsigned_int(null(), 0, &mut unsigned_int),
+            SpringErrno::CNull
+        );
+        assert_null_err("row");
+        let mut text = [0; 8];
+        assert_eq!(
+            spring_column_text(null(), 0, text.as_mut_ptr(), text.len() as c_int),
+            SpringErrno::CNull as c_int
+        );
+        assert_null_err("row");
+        let mut blob = [0_u8; 8];
+        assert_eq!(
+            spring_column_blob(
+                null(),
+                0,
+                blob.as_mut_ptr() as *mut c_void,
+                blob.len() as c_int
+            ),
+            SpringErrno::CNull as c_int
+        );
+        assert_null_err("row");
+        let mut b = false;
+        assert_eq!(spring_column_bool(null(), 0, &mut b), SpringErrno::CNull);
+        assert_null_err("row");
+        let mut float = 0.0;
+        assert_eq!(
+            spring_column_float(null(), 0, &mut float),
+            SpringErrno::CNull
+        );
+        assert_null_err("row");
+    }
+}
+
+#[test]
+fn test_spring_last_err_null() {
+    unsafe {
+        let mut errmsg = [0; 8];
+        assert_eq!(
+            spring_last_err(null(), errmsg.as_mut_ptr(), errmsg.len() as c_int),
+            SpringErrno::CNull as c_int
+        );
+        let mut errno = SpringErrno::Ok;
+        assert_eq!(
+            spring_last_err(&mut errno, null(), 8),
+            SpringErrno::CNull as c_int
+        );
+    }
+}
+
+#[test]
+fn test_spring_error_null() {
+    unsafe {
+        assert_eq!(spring_error_free(null()), SpringErrno::CNull);
+        assert_null_err("err");
+        assert_eq!(spring_error_errno(null()), SpringErrno::CNull);
+        assert_null_err("err");
+
+        let mut buf = [0; 8];
+        assert_eq!(
+            spring_error_message(null(), buf.as_mut_ptr(), buf.len() as c_int),
+            SpringErrno::CNull as c_int
+        );
+        assert_null_err("err");
+        assert_eq!(
+            spring_error_cause_count(null()),
+            SpringErrno::CNull as c_int
+        );
+        assert_null_err("err");
+        assert_eq!(
+            spring_error_cause(null(), 0, buf.as_mut_ptr(), buf.len() as c_int),
+            SpringErrno::CNull as c_int
+        );
+        assert_null_err("err");
+        assert_eq!(
+            spring_error_sql_position(null(), null(), null(), null()),
+            SpringErrno::CNull
+        );
+        assert_null_err("err");
+
+        // records the error to get its detail
+        assert!(spring_source_row_from_json(null()).is_null());
+        let err = spring_last_err_detail();
+        assert_eq!(
+            spring_error_message(err, null(), 8),
+            SpringErrno::CNull as c_int
+        );
+        assert_null_err("buf");
+        spring_error_free(err);
+    }
+}
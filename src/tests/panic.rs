@@ -0,0 +1,47 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::ffi::CStr;
+
+use crate::{spring_error::*, spring_last_err::spring_last_err, *};
+
+#[test]
+fn test_panic_location_and_backtrace() {
+    unsafe {
+        let res = with_catch(|| -> Result<(), LastError> { panic!("test panic") });
+        assert_eq!(res, Err(SpringErrno::Unknown));
+
+        let err = spring_last_err_detail();
+        assert!(!err.is_null());
+        let mut backtrace = [0; 65536];
+        assert!(spring_error_backtrace(err, backtrace.as_mut_ptr(), backtrace.len() as c_int) > 0);
+        assert_eq!(
+            spring_error_backtrace(err, backtrace.as_mut_ptr(), 1),
+            SpringErrno::CInsufficient as c_int
+        );
+        assert_eq!(spring_error_free(err), SpringErrno::Ok);
+
+        // too small buffer keeps the error
+        let mut errno = SpringErrno::Ok;
+        let mut errmsg = [0; 8];
+        assert_eq!(
+            spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int),
+            SpringErrno::CInsufficient as c_int
+        );
+
+        let mut errmsg = [0; 1024];
+        assert!(spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int) > 0);
+        assert_eq!(errno, SpringErrno::Unknown);
+
+        let errmsg = CStr::from_ptr(errmsg.as_ptr()).to_string_lossy();
+        assert!(errmsg.contains("test panic"), "{}", errmsg);
+        assert!(errmsg.contains("(at src/tests/panic.rs:"), "{}", errmsg);
+        assert!(!errmsg.contains("stack backtrace"), "{}", errmsg);
+
+        // taken
+        let mut errmsg = [0; 1024];
+        assert_eq!(
+            spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int),
+            SpringErrno::Ok as c_int
+        );
+    }
+}
@@ -0,0 +1,319 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::{c_void, CStr, CString},
+    net::TcpListener,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{spring_background_error::*, worker_monitor::CORE_ERROR_TARGET, *};
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn open() -> *mut SpringPipeline {
+    let config = spring_config_default();
+    let pipeline = spring_open(config);
+    spring_config_close(config);
+    pipeline
+}
+
+/// Polls background errors until one starting with `prefix`.
+/// Errors of other pipelines in this process are skipped.
+unsafe fn poll_until(pipeline: *const SpringPipeline, prefix: &str) -> (SpringErrno, String) {
+    let (errno, _, error) = poll_until_with_n_pipelines(pipeline, prefix);
+    (errno, error)
+}
+
+unsafe fn poll_until_with_n_pipelines(
+    pipeline: *const SpringPipeline,
+    prefix: &str,
+) -> (SpringErrno, u32, String) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut buf = [0; 1024];
+    loop {
+        let mut errno = SpringErrno::Unknown;
+        let mut n_pipelines = u32::MAX;
+        let len = spring_poll_background_error(
+            pipeline,
+            &mut errno,
+            &mut n_pipelines,
+            buf.as_mut_ptr(),
+            buf.len() as c_int,
+        );
+        assert!(len >= 0);
+        if len == 0 {
+            assert_eq!(errno, SpringErrno::Ok);
+            assert_eq!(n_pipelines, 0);
+            assert!(Instant::now() < deadline, "timeout: {}", prefix);
+            thread::sleep(Duration::from_millis(20));
+            continue;
+        }
+        let error = CStr::from_ptr(buf.as_ptr()).to_str().unwrap().to_string();
+        assert_eq!(error.len(), len as usize);
+        assert!(n_pipelines >= 1);
+        if error.starts_with(prefix) {
+            return (errno, n_pipelines, error);
+        }
+    }
+}
+
+/// Logs an error as if a worker thread of SpringQL-core got it.
+fn log_worker_error(worker: &str, message: &'static str) {
+    thread::Builder::new()
+        .name(worker.to_string())
+        .spawn(move || {
+            log::error!(
+                target: CORE_ERROR_TARGET,
+                "{}",
+                message
+            )
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn test_spring_poll_background_error() {
+    unsafe {
+        let pipeline = open();
+
+        // a remote host which closes the connection right after accepted
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let remote = thread::spawn(move || drop(listener.accept().unwrap()));
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_bg_err');
+            ",
+        );
+        command(
+            pipeline,
+            &format!(
+                "
+                CREATE SOURCE READER tcp_src FOR source_1
+                    TYPE NET_CLIENT OPTIONS (PROTOCOL 'TCP', REMOTE_HOST '127.0.0.1', REMOTE_PORT '{}');
+                ",
+                port
+            ),
+        );
+        remote.join().unwrap();
+
+        // the debug format of `SpringError` logged by SpringQL-core (see `test_core_version()`)
+        let (errno, _) = poll_until(pipeline, "SourceWorker: InvalidFormat { s: ");
+        assert_eq!(errno, SpringErrno::InvalidFormat);
+
+        // too small buffer keeps the error
+        log_worker_error("GenericWorker", "Sql { bg_err_insufficient }");
+        let mut buf = [0; 4];
+        let mut errno = SpringErrno::Unknown;
+        assert_eq!(
+            spring_poll_background_error(
+                pipeline,
+                &mut errno,
+                ptr::null_mut(),
+                buf.as_mut_ptr(),
+                buf.len() as c_int
+            ),
+            SpringErrno::CInsufficient as c_int
+        );
+        assert_eq!(errno, SpringErrno::Unknown);
+        let (errno, error) = poll_until(pipeline, "GenericWorker: Sql { bg_err_insufficient }");
+        assert_eq!(errno, SpringErrno::Sql);
+        assert_eq!(error, "GenericWorker: Sql { bg_err_insufficient }");
+
+        assert_eq!(
+            spring_poll_background_error(
+                pipeline,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0
+            ),
+            SpringErrno::CNull as c_int
+        );
+
+        spring_close(pipeline);
+    }
+}
+
+static RECEIVED: Mutex<Vec<(SpringErrno, String, String, u32)>> = Mutex::new(Vec::new());
+
+unsafe extern "C" fn on_background_error(
+    errno: SpringErrno,
+    component: *const c_char,
+    message: *const c_char,
+    n_pipelines: u32,
+    user_data: *mut c_void,
+) {
+    assert_eq!(user_data as usize, 42);
+    let component = CStr::from_ptr(component).to_str().unwrap().to_string();
+    let message = CStr::from_ptr(message).to_str().unwrap().to_string();
+    if message.contains("bg_err_callback") {
+        RECEIVED
+            .lock()
+            .unwrap()
+            .push((errno, component, message, n_pipelines));
+    }
+}
+
+#[test]
+fn test_spring_set_background_error_callback() {
+    unsafe {
+        let pipeline = open();
+
+        assert_eq!(
+            spring_set_background_error_callback(
+                pipeline,
+                Some(on_background_error),
+                42 as *mut c_void
+            ),
+            SpringErrno::Ok
+        );
+        log_worker_error("PurgerWorker", "ForeignIo { bg_err_callback }");
+        {
+            let received = RECEIVED.lock().unwrap();
+            assert_eq!(received.len(), 1);
+            let (errno, component, message, n_pipelines) = &received[0];
+            assert_eq!(*errno, SpringErrno::ForeignIo);
+            assert_eq!(component, "PurgerWorker");
+            assert_eq!(message, "ForeignIo { bg_err_callback }");
+            // other tests may open pipelines concurrently
+            assert!(*n_pipelines >= 1);
+        }
+
+        // errors are kept for polling again after the callback is unset
+        assert_eq!(
+            spring_set_background_error_callback(pipeline, None, ptr::null_mut()),
+            SpringErrno::Ok
+        );
+        log_worker_error("PurgerWorker", "ForeignIo { bg_err_callback 2 }");
+        assert_eq!(RECEIVED.lock().unwrap().len(), 1);
+        let (errno, _) = poll_until(pipeline, "PurgerWorker: ForeignIo { bg_err_callback 2 }");
+        assert_eq!(errno, SpringErrno::ForeignIo);
+
+        // an error is received by all pipelines, which tell how many may have got it
+        let other = open();
+        log_worker_error("PurgerWorker", "ForeignIo { bg_err_callback 3 }");
+        for pipeline in [pipeline, other] {
+            let (_, n_pipelines, _) = poll_until_with_n_pipelines(
+                pipeline,
+                "PurgerWorker: ForeignIo { bg_err_callback 3 }",
+            );
+            assert!(n_pipelines >= 2);
+        }
+        spring_close(other);
+
+        assert_eq!(
+            spring_set_background_error_callback(ptr::null(), None, ptr::null_mut()),
+            SpringErrno::CNull
+        );
+
+        spring_close(pipeline);
+    }
+}
+
+static SLOW_CALLBACK_STARTED: AtomicBool = AtomicBool::new(false);
+static SLOW_CALLBACK_RETURNED: AtomicBool = AtomicBool::new(false);
+
+unsafe extern "C" fn on_background_error_slowly(
+    _errno: SpringErrno,
+    _component: *const c_char,
+    message: *const c_char,
+    _n_pipelines: u32,
+    pipeline: *mut c_void,
+) {
+    let message = CStr::from_ptr(message).to_str().unwrap();
+    if message.contains("bg_err_slow") {
+        SLOW_CALLBACK_STARTED.store(true, Ordering::SeqCst);
+        // replacing the callback from inside it does not wait for itself
+        assert_eq!(
+            spring_set_background_error_callback(
+                pipeline as *const SpringPipeline,
+                Some(on_background_error_slowly),
+                pipeline
+            ),
+            SpringErrno::Ok
+        );
+        // longer than closing a pipeline takes by itself
+        thread::sleep(Duration::from_secs(1));
+        SLOW_CALLBACK_RETURNED.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_spring_close_waits_for_background_error_callback() {
+    unsafe {
+        let pipeline = open();
+        assert_eq!(
+            spring_set_background_error_callback(
+                pipeline,
+                Some(on_background_error_slowly),
+                pipeline as *mut c_void
+            ),
+            SpringErrno::Ok
+        );
+
+        let worker =
+            thread::spawn(|| log_worker_error("PurgerWorker", "ForeignIo { bg_err_slow }"));
+        while !SLOW_CALLBACK_STARTED.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // `user_data` (here the pipeline itself) is not used after `spring_close()` returns
+        spring_close(pipeline);
+        assert!(SLOW_CALLBACK_RETURNED.load(Ordering::SeqCst));
+        worker.join().unwrap();
+    }
+}
+
+/// Version of SpringQL-core whose log records of errors in background tasks are verified.
+const CORE_VERSION: &str = "0.18.1";
+
+/// Errors are captured from log records of SpringQL-core, whose target and format are not a stable API.
+#[test]
+fn test_core_version() {
+    let lock: toml::Value =
+        std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.lock"))
+            .unwrap()
+            .parse()
+            .unwrap();
+    let version = lock["package"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|package| package["name"].as_str() == Some("springql-core"))
+        .and_then(|package| package["version"].as_str())
+        .unwrap();
+    assert_eq!(
+        version, CORE_VERSION,
+        "SpringQL-core is upgraded: check the log target (`CORE_ERROR_TARGET`) and the message format \
+         of errors in background tasks (`test_spring_poll_background_error()`), and then update `CORE_VERSION`"
+    );
+}
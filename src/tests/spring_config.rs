@@ -2,7 +2,7 @@
 
 use std::ffi::CString;
 
-use crate::*;
+use crate::{spring_last_err::spring_last_err, *};
 
 #[test]
 fn test_spring_config_default() {
@@ -22,3 +22,29 @@ fn test_spring_config_toml() {
         spring_config_close(config);
     }
 }
+
+#[test]
+fn test_spring_config_toml_invalid() {
+    unsafe fn assert_invalid(toml: &str, expected: SpringErrno) {
+        let toml = CString::new(toml).unwrap();
+        let config = spring_config_toml(toml.as_ptr());
+        assert!(config.is_null());
+
+        let mut errno = SpringErrno::Ok;
+        let mut errmsg = [0; 1024];
+        spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int);
+        assert_eq!(errno, expected);
+    }
+
+    unsafe {
+        assert_invalid("[memory\nupper_limit_bytes = 1", SpringErrno::InvalidFormat);
+        assert_invalid(
+            "[memory]\nupper_limit_bytes = \"many\"",
+            SpringErrno::InvalidConfig,
+        );
+        assert_invalid(
+            "[client]\nenable_metrics_receiver = 1",
+            SpringErrno::InvalidConfig,
+        );
+    }
+}
@@ -0,0 +1,75 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::ffi::{CStr, CString};
+
+use crate::{
+    spring_errno::{spring_errno_name, spring_strerror},
+    spring_last_err::spring_last_err,
+    *,
+};
+
+unsafe fn errno_name(errno_: c_int) -> &'static str {
+    CStr::from_ptr(spring_errno_name(errno_)).to_str().unwrap()
+}
+
+unsafe fn last_errmsg() -> String {
+    let mut errno = SpringErrno::Ok;
+    let mut errmsg = [0; 4096];
+    assert!(spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int) > 0);
+    CStr::from_ptr(errmsg.as_ptr())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn test_spring_errno_name() {
+    unsafe {
+        assert_eq!(errno_name(SpringErrno::Ok as c_int), "Ok");
+        assert_eq!(errno_name(SpringErrno::Sql as c_int), "Sql");
+        assert_eq!(
+            errno_name(SpringErrno::CInsufficient as c_int),
+            "CInsufficient"
+        );
+        assert_eq!(errno_name(SpringErrno::CNull as c_int), "CNull");
+        assert_eq!(errno_name(1), "(unknown errno)");
+
+        let n_known = (-128..=0)
+            .filter(|errno_| errno_name(*errno_) != "(unknown errno)")
+            .count();
+        assert_eq!(n_known, 19);
+    }
+}
+
+#[test]
+fn test_spring_strerror() {
+    unsafe {
+        let s = CStr::from_ptr(spring_strerror(SpringErrno::CNull as c_int));
+        assert_eq!(s.to_str().unwrap(), "invalid NULL pointer");
+        let s = CStr::from_ptr(spring_strerror(-100));
+        assert_eq!(s.to_str().unwrap(), "(unknown errno)");
+    }
+}
+
+#[test]
+fn test_spring_last_err_message() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let queue = CString::new("q_errno_missing").unwrap();
+        let mut is_err = false;
+        spring_pop_non_blocking(pipeline, queue.as_ptr(), &mut is_err);
+        assert!(is_err);
+        assert_eq!(
+            last_errmsg(),
+            "Unavailable: requested q_errno_missing but its not available: queue not found"
+        );
+
+        let sql = CString::new("CREATE STREAM").unwrap();
+        assert_eq!(spring_command(pipeline, sql.as_ptr()), SpringErrno::Sql);
+        assert!(last_errmsg().starts_with("Sql: failed to parse SQL: "));
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
@@ -0,0 +1,93 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::ffi::{CStr, CString};
+
+use crate::{spring_error::*, *};
+
+unsafe fn message(err: *const SpringError) -> String {
+    let mut buf = [0; 1024];
+    assert!(spring_error_message(err, buf.as_mut_ptr(), buf.len() as c_int) > 0);
+    CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+}
+
+unsafe fn causes(err: *const SpringError) -> Vec<String> {
+    let n = spring_error_cause_count(err);
+    assert!(n >= 0);
+    (0..n)
+        .map(|i| {
+            let mut buf = [0; 4096];
+            assert!(spring_error_cause(err, i, buf.as_mut_ptr(), buf.len() as c_int) > 0);
+            CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn test_spring_last_err_detail_sql() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let sql =
+            CString::new("CREATE SOURCE STREAM source_1 (\n  n INTEGER NOT NULL,,\n);").unwrap();
+        assert_eq!(spring_command(pipeline, sql.as_ptr()), SpringErrno::Sql);
+
+        let err = spring_last_err_detail();
+        assert!(!err.is_null());
+        assert_eq!(spring_error_errno(err), SpringErrno::Sql);
+        assert_eq!(message(err), "SQL error");
+
+        let causes = causes(err);
+        assert!(!causes.is_empty());
+        assert!(spring_error_cause(err, causes.len() as c_int, [0; 8].as_mut_ptr(), 8) < 0);
+
+        let (mut offset, mut line, mut column) = (0, 0, 0);
+        assert_eq!(
+            spring_error_sql_position(err, &mut offset, &mut line, &mut column),
+            SpringErrno::Ok
+        );
+        assert_eq!(line, 2);
+        assert_eq!(column, 22);
+        assert_eq!(offset, 53);
+        assert_eq!(&sql.to_str().unwrap()[offset as usize..], ",\n);");
+
+        assert_eq!(spring_error_free(err), SpringErrno::Ok);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_last_err_detail_unavailable() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let queue = CString::new("q_error_missing").unwrap();
+        let mut is_err = false;
+        let row = spring_pop_non_blocking(pipeline, queue.as_ptr(), &mut is_err);
+        assert!(row.is_null());
+        assert!(is_err);
+
+        let err = spring_last_err_detail();
+        assert!(!err.is_null());
+        assert_eq!(spring_error_errno(err), SpringErrno::Unavailable);
+        assert_eq!(
+            spring_error_sql_position(err, ptr::null_mut(), ptr::null_mut(), ptr::null_mut()),
+            SpringErrno::Unavailable
+        );
+        assert_eq!(spring_error_free(err), SpringErrno::Ok);
+
+        // does not clear the last error
+        let mut errno = SpringErrno::Ok;
+        let mut errmsg = [0; 1024];
+        spring_last_err::spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int);
+        assert_eq!(errno, SpringErrno::Unavailable);
+
+        assert!(spring_last_err_detail().is_null());
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
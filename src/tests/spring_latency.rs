@@ -0,0 +1,166 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::{CStr, CString},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{spring_latency::*, spring_pipeline_metrics::*, *};
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn push(pipeline: *const SpringPipeline, queue: &CStr, n: i32) {
+    let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+    let row = spring_source_row_from_json(json.as_ptr());
+    assert_eq!(spring_push(pipeline, queue.as_ptr(), row), SpringErrno::Ok);
+}
+
+fn now_unix_nsec() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as i64
+}
+
+#[test]
+fn test_spring_latency() {
+    unsafe {
+        let config = spring_config_toml(CString::new("").unwrap().as_ptr());
+        let pipeline = spring_open(config);
+        spring_config_close(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(
+            pipeline,
+            "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL, ingest_time TIMESTAMP NOT NULL);",
+        );
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n, ingest_time)
+                SELECT STREAM source_1.n, source_1.ptime FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_latency');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_latency');
+            ",
+        );
+
+        let queue_src = CString::new("q_src_latency").unwrap();
+        let queue_sink = CString::new("q_sink_latency").unwrap();
+
+        let mut histogram = SpringLatencyHistogram::default();
+        assert_eq!(
+            spring_latency_histogram(pipeline, queue_sink.as_ptr(), &mut histogram),
+            SpringErrno::Unavailable
+        );
+        let queue_unknown = CString::new("q_unknown_latency").unwrap();
+        for queue in [&queue_src, &queue_unknown] {
+            assert_eq!(
+                spring_latency_trace(pipeline, queue.as_ptr(), 1),
+                SpringErrno::Unavailable
+            );
+        }
+        assert_eq!(
+            spring_latency_trace(pipeline, queue_sink.as_ptr(), 1),
+            SpringErrno::Ok
+        );
+
+        let pushed_at = now_unix_nsec();
+        for n in 1..=3 {
+            push(pipeline, &queue_src, n);
+        }
+        for _ in 1..=3 {
+            let row = spring_pop(pipeline, queue_sink.as_ptr());
+            assert!(!row.is_null());
+
+            let mut ingest_time = 0;
+            assert_eq!(
+                spring_sink_row_ingest_time(row, 1, &mut ingest_time),
+                SpringErrno::Ok
+            );
+            assert!(pushed_at <= ingest_time && ingest_time <= now_unix_nsec());
+
+            // not a timestamp
+            assert_eq!(
+                spring_sink_row_ingest_time(row, 0, &mut ingest_time),
+                SpringErrno::Sql
+            );
+            spring_sink_row_close(row);
+        }
+
+        assert_eq!(
+            spring_latency_histogram(pipeline, queue_sink.as_ptr(), &mut histogram),
+            SpringErrno::Ok
+        );
+        assert_eq!(histogram.count, 3);
+        assert_eq!(histogram.rows_untraced, 0);
+        assert_eq!(histogram.buckets.iter().sum::<u64>(), 3);
+        assert!(histogram.min_nsec <= histogram.max_nsec);
+        assert!(histogram.sum_nsec >= histogram.max_nsec);
+
+        let mut buf = [0; 8192];
+        let len = spring_pipeline_metrics_json(pipeline, buf.as_mut_ptr(), buf.len() as c_int);
+        assert!(len > 0);
+        let json: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(buf.as_ptr()).to_str().unwrap()).unwrap();
+        let in_memory_queues = json["in_memory_queues"].as_array().unwrap();
+        let sink = in_memory_queues
+            .iter()
+            .find(|queue| queue["name"] == "q_sink_latency")
+            .unwrap();
+        assert_eq!(sink["latency"]["count"], 3);
+        assert_eq!(
+            sink["latency"]["buckets"].as_array().unwrap().len(),
+            SPRING_LATENCY_BUCKETS
+        );
+        let src = in_memory_queues
+            .iter()
+            .find(|queue| queue["name"] == "q_src_latency")
+            .unwrap();
+        assert!(src["latency"].is_null());
+
+        let text = prometheus_text(handle::get(pipeline).unwrap());
+        assert!(text.contains(
+            r#"springql_in_memory_queue_latency_seconds_count{queue="q_sink_latency"} 3"#
+        ));
+        assert!(text.contains(
+            r#"springql_in_memory_queue_latency_seconds_bucket{queue="q_sink_latency",le="+Inf"} 3"#
+        ));
+
+        // tracing again resets the histogram. rows without an ingest time are counted as untraced.
+        assert_eq!(
+            spring_latency_trace(pipeline, queue_sink.as_ptr(), 0),
+            SpringErrno::Ok
+        );
+        push(pipeline, &queue_src, 4);
+        let row = spring_pop(pipeline, queue_sink.as_ptr());
+        spring_sink_row_close(row);
+        assert_eq!(
+            spring_latency_histogram(pipeline, queue_sink.as_ptr(), &mut histogram),
+            SpringErrno::Ok
+        );
+        assert_eq!(histogram.count, 0);
+        assert_eq!(histogram.rows_untraced, 1);
+
+        spring_close(pipeline);
+    }
+}
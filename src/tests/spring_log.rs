@@ -0,0 +1,177 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    env,
+    ffi::{CStr, CString},
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+};
+
+use crate::{spring_log::*, *};
+
+const TARGET: &str = "springql_client::tests::spring_log";
+
+/// Tests replacing the logger of this process run one by one.
+static LOGGER_LOCK: Mutex<()> = Mutex::new(());
+
+struct LogRecord {
+    level: SpringLogLevel,
+    message: String,
+    has_file: bool,
+    line: c_int,
+    user_data: usize,
+}
+
+static RECORDS: Mutex<Vec<LogRecord>> = Mutex::new(Vec::new());
+
+unsafe extern "C" fn on_log(
+    level: SpringLogLevel,
+    target: *const c_char,
+    message: *const c_char,
+    file: *const c_char,
+    line: c_int,
+    user_data: *mut c_void,
+) {
+    // records from other tests running in parallel
+    if CStr::from_ptr(target).to_str().unwrap() != TARGET {
+        return;
+    }
+    let message = CStr::from_ptr(message).to_string_lossy().into_owned();
+    RECORDS.lock().unwrap().push(LogRecord {
+        level,
+        message,
+        has_file: !file.is_null(),
+        line,
+        user_data: user_data as usize,
+    });
+}
+
+#[test]
+fn test_spring_set_log_callback() {
+    let _lock = LOGGER_LOCK.lock().unwrap();
+    let mut user_data = 0;
+    let user_data = &mut user_data as *mut i32 as *mut c_void;
+
+    unsafe {
+        assert_eq!(
+            spring_set_log_callback(Some(on_log), user_data, SpringLogLevel::Info as c_int),
+            SpringErrno::Ok
+        );
+
+        log::warn!(target: TARGET, "warn {}", 1);
+        log::debug!(target: TARGET, "debug {}", 2);
+
+        assert_eq!(
+            spring_set_log_callback(None, ptr::null_mut(), SpringLogLevel::Off as c_int),
+            SpringErrno::Ok
+        );
+        log::warn!(target: TARGET, "warn {}", 3);
+    }
+
+    let records = RECORDS.lock().unwrap();
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert_eq!(record.level, SpringLogLevel::Warn);
+    assert_eq!(record.message, "warn 1");
+    assert!(record.has_file);
+    assert!(record.line > 0);
+    assert_eq!(record.user_data, user_data as usize);
+}
+
+#[test]
+fn test_spring_set_log_callback_invalid_level() {
+    unsafe {
+        assert_eq!(
+            spring_set_log_callback(Some(on_log), ptr::null_mut(), 6),
+            SpringErrno::InvalidOption
+        );
+        assert_eq!(
+            spring_set_log_callback(Some(on_log), ptr::null_mut(), -1),
+            SpringErrno::InvalidOption
+        );
+    }
+}
+
+fn log_path(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("springql-client-test-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    let _ = fs::remove_file(&path);
+    path
+}
+
+unsafe fn log_init_file(level: &str, path: &Path) -> SpringErrno {
+    let level = CString::new(level).unwrap();
+    let path = CString::new(path.to_str().unwrap()).unwrap();
+    spring_log_init(
+        SpringLogTarget::File as c_int,
+        level.as_ptr(),
+        path.as_ptr(),
+    )
+}
+
+#[test]
+fn test_spring_log_init_file() {
+    let _lock = LOGGER_LOCK.lock().unwrap();
+    let path = log_path("test_spring_log_init_file.log");
+
+    unsafe {
+        let level = format!("warn,{}=debug", TARGET);
+        assert_eq!(log_init_file(&level, &path), SpringErrno::Ok);
+
+        log::debug!(target: TARGET, "debug {}", 1);
+        log::trace!(target: TARGET, "trace {}", 2);
+        log::info!(target: "springql_client::tests::other", "info {}", 3);
+        log::logger().flush();
+
+        spring_set_log_callback(None, ptr::null_mut(), SpringLogLevel::Off as c_int);
+    }
+
+    let log = fs::read_to_string(&path).unwrap();
+    let lines: Vec<_> = log.lines().collect();
+    assert_eq!(lines.len(), 1, "{}", log);
+    assert!(lines[0].starts_with('['));
+    assert!(lines[0].contains(&format!(
+        " DEBUG tests::spring_log::test_spring_log_init_file {}] debug 1",
+        TARGET
+    )));
+}
+
+#[test]
+fn test_spring_log_init_env() {
+    let _lock = LOGGER_LOCK.lock().unwrap();
+    let path = log_path("test_spring_log_init_env.log");
+
+    unsafe {
+        env::set_var("SPRINGQL_LOG", format!("{}=trace", TARGET));
+        assert_eq!(log_init_file("error", &path), SpringErrno::Ok);
+        env::remove_var("SPRINGQL_LOG");
+
+        log::trace!(target: TARGET, "trace {}", 1);
+        log::logger().flush();
+
+        spring_set_log_callback(None, ptr::null_mut(), SpringLogLevel::Off as c_int);
+    }
+
+    let log = fs::read_to_string(&path).unwrap();
+    assert!(log.contains("] trace 1"), "{}", log);
+}
+
+#[test]
+fn test_spring_log_init_invalid() {
+    unsafe {
+        assert_eq!(
+            spring_log_init(2, ptr::null(), ptr::null()),
+            SpringErrno::InvalidOption
+        );
+        assert_eq!(
+            spring_log_init(SpringLogTarget::File as c_int, ptr::null(), ptr::null()),
+            SpringErrno::CNull
+        );
+
+        let path = log_path("no_such_dir").join("test.log");
+        assert_eq!(log_init_file("info", &path), SpringErrno::InvalidOption);
+    }
+}
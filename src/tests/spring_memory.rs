@@ -0,0 +1,135 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{ffi::CString, sync::Mutex};
+
+use ::springql::SpringMemoryConfig;
+
+use crate::{memory_monitor::MemoryMonitor, spring_memory::*, *};
+
+unsafe fn open(toml: &str) -> *mut SpringPipeline {
+    let toml = CString::new(toml).unwrap();
+    let config = spring_config_toml(toml.as_ptr());
+    let pipeline = spring_open(config);
+    spring_config_close(config);
+    assert!(!pipeline.is_null());
+    pipeline
+}
+
+#[test]
+fn test_spring_memory_usage() {
+    unsafe {
+        let pipeline = open(
+            "[client]\nenable_metrics_receiver = true\n[memory]\nupper_limit_bytes = 1_000_000",
+        );
+
+        let mut used = u64::MAX;
+        let mut limit = 0;
+        let mut state = SpringMemoryState::Critical;
+        assert_eq!(
+            spring_memory_usage(pipeline, &mut used, &mut limit, &mut state),
+            SpringErrno::Ok
+        );
+        assert_eq!(used, 0);
+        assert_eq!(limit, 1_000_000);
+        assert_eq!(state, SpringMemoryState::Moderate);
+
+        // outputs are optional
+        assert_eq!(
+            spring_memory_usage(
+                pipeline,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut()
+            ),
+            SpringErrno::Ok
+        );
+
+        spring_close(pipeline);
+    }
+}
+
+#[test]
+fn test_spring_memory_usage_not_reported() {
+    // metrics receiver is disabled by default, and does not start if the web console is enabled
+    for toml in [
+        "",
+        "[client]\nenable_metrics_receiver = true\n[web_console]\nenable_report_post = true",
+    ] {
+        unsafe {
+            let pipeline = open(toml);
+
+            let mut used = 0;
+            assert_eq!(
+                spring_memory_usage(
+                    pipeline,
+                    &mut used,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut()
+                ),
+                SpringErrno::Unavailable
+            );
+            assert_eq!(
+                spring_set_memory_state_callback(pipeline, None, std::ptr::null_mut()),
+                SpringErrno::Unavailable
+            );
+
+            spring_close(pipeline);
+        }
+    }
+}
+
+static TRANSITIONS: Mutex<Vec<(SpringMemoryState, u64, u64)>> = Mutex::new(Vec::new());
+
+unsafe extern "C" fn on_memory_state(
+    state: SpringMemoryState,
+    used_bytes: u64,
+    upper_limit_bytes: u64,
+    user_data: *mut c_void,
+) {
+    assert_eq!(*(user_data as *const i32), 42);
+    TRANSITIONS
+        .lock()
+        .unwrap()
+        .push((state, used_bytes, upper_limit_bytes));
+}
+
+#[test]
+fn test_memory_state_transitions() {
+    let monitor = MemoryMonitor::new(&SpringMemoryConfig {
+        upper_limit_bytes: 1000,
+        moderate_to_severe_percent: 60,
+        severe_to_critical_percent: 95,
+        critical_to_severe_percent: 80,
+        severe_to_moderate_percent: 40,
+        memory_state_transition_interval_msec: 10,
+        performance_metrics_summary_report_interval_msec: 10,
+    });
+
+    let mut user_data = 42;
+    monitor.set_callback(
+        Some(on_memory_state),
+        &mut user_data as *mut i32 as *mut c_void,
+    );
+
+    // Moderate -> Severe -> Critical -> Severe -> Moderate, with hysteresis
+    for used_bytes in [500, 700, 900, 960, 850, 700, 500, 300, 500] {
+        monitor.update(used_bytes);
+    }
+    assert_eq!(monitor.usage(), (500, SpringMemoryState::Moderate));
+
+    assert_eq!(
+        *TRANSITIONS.lock().unwrap(),
+        vec![
+            (SpringMemoryState::Severe, 700, 1000),
+            (SpringMemoryState::Critical, 960, 1000),
+            (SpringMemoryState::Severe, 700, 1000),
+            (SpringMemoryState::Moderate, 300, 1000),
+        ]
+    );
+
+    // unset
+    monitor.set_callback(None, std::ptr::null_mut());
+    monitor.update(700);
+    assert_eq!(monitor.usage(), (700, SpringMemoryState::Severe));
+    assert_eq!(TRANSITIONS.lock().unwrap().len(), 4);
+}
@@ -0,0 +1,122 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::CString,
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{spring_pipeline_metrics::spring_metrics_serve, *};
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+fn free_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+fn http_get(addr: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, addr).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_spring_metrics_serve() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        spring_config_close(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_serve');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_serve');
+            ",
+        );
+
+        let queue_src = CString::new("q_src_serve").unwrap();
+        for n in 1..=2 {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            let row = spring_source_row_from_json(json.as_ptr());
+            assert_eq!(
+                spring_push(pipeline, queue_src.as_ptr(), row),
+                SpringErrno::Ok
+            );
+        }
+
+        let addr = free_addr();
+        let c_addr = CString::new(addr.clone()).unwrap();
+        assert_eq!(
+            spring_metrics_serve(pipeline, c_addr.as_ptr()),
+            SpringErrno::Ok
+        );
+        assert_eq!(
+            spring_metrics_serve(pipeline, c_addr.as_ptr()),
+            SpringErrno::Unavailable
+        );
+
+        let response = http_get(&addr, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("# TYPE springql_in_memory_queue_rows_pushed_total counter\n"));
+        assert!(response
+            .contains("springql_in_memory_queue_rows_pushed_total{queue=\"q_src_serve\"} 2\n"));
+        assert!(response.contains("springql_memory_upper_limit_bytes 10000000\n"));
+
+        let response = http_get(&addr, "/");
+        assert!(
+            response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+            "{}",
+            response
+        );
+
+        assert_eq!(spring_metrics_serve(pipeline, ptr::null()), SpringErrno::Ok);
+        assert!(TcpStream::connect(&addr).is_err());
+
+        spring_close(pipeline);
+    }
+}
+
+#[test]
+fn test_spring_metrics_serve_invalid_addr() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        spring_config_close(config);
+
+        let addr = CString::new("not an address").unwrap();
+        assert_eq!(
+            spring_metrics_serve(pipeline, addr.as_ptr()),
+            SpringErrno::InvalidOption
+        );
+
+        spring_close(pipeline);
+    }
+}
@@ -0,0 +1,302 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::CString,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use ::springql::{
+    SpringConfig as RuSpringConfig, SpringPipeline as RuSpringPipeline,
+    SpringSourceRow as RuSpringSourceRow,
+};
+
+use crate::{
+    memory_monitor::MemoryMonitor,
+    source_queue_feeder::SourceQueueFeeder,
+    spring_last_err::LastError,
+    spring_overflow_policy::{OverflowConfig, SpringOverflowPolicy, *},
+    spring_queue_stats::QueueCounters,
+    *,
+};
+
+/// Statements creating a pipeline from `source_1` to an in-memory sink queue `q_{name}_sink`.
+fn pipeline_sqls(name: &str) -> [String; 4] {
+    [
+        "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);".to_string(),
+        "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);".to_string(),
+        "CREATE PUMP pump_1 AS INSERT INTO sink_1 (n) SELECT STREAM source_1.n FROM source_1;"
+            .to_string(),
+        format!(
+            "CREATE SINK WRITER queue_sink FOR sink_1 TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_{}_sink');",
+            name
+        ),
+    ]
+}
+
+/// `CREATE SOURCE READER` for an in-memory source queue `q_{name}_src`.
+fn source_reader_sql(name: &str, options: &str) -> String {
+    format!(
+        "CREATE SOURCE READER queue_src FOR source_1 TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_{}_src'{});",
+        name, options
+    )
+}
+
+fn ru_pipeline(name: &str) -> Arc<RuSpringPipeline> {
+    let pipeline = RuSpringPipeline::new(&RuSpringConfig::default()).unwrap();
+    for sql in pipeline_sqls(name) {
+        pipeline.command(sql).unwrap();
+    }
+    pipeline.command(source_reader_sql(name, "")).unwrap();
+    Arc::new(pipeline)
+}
+
+/// Memory monitor in `Severe` state, so that the feeder holds rows.
+fn severe_memory_monitor() -> Arc<MemoryMonitor> {
+    let mut config = RuSpringConfig::default().memory;
+    config.upper_limit_bytes = 1000;
+    let monitor = MemoryMonitor::new(&config);
+    monitor.update(900);
+    Arc::new(monitor)
+}
+
+fn row(n: i32) -> RuSpringSourceRow {
+    RuSpringSourceRow::from_json(&format!(r#"{{"n": {}}}"#, n)).unwrap()
+}
+
+fn start(
+    pipeline: Arc<RuSpringPipeline>,
+    name: &str,
+    policy: SpringOverflowPolicy,
+    memory_monitor: Arc<MemoryMonitor>,
+) -> (SourceQueueFeeder, Arc<QueueCounters>) {
+    let counters = Arc::new(QueueCounters::default());
+    let feeder = SourceQueueFeeder::start(
+        pipeline,
+        &format!("q_{}_src", name),
+        OverflowConfig::new(policy, 2, 50).unwrap(),
+        counters.clone(),
+        memory_monitor,
+    );
+    (feeder, counters)
+}
+
+#[test]
+fn test_overflow_policy_fail() {
+    let (feeder, counters) = start(
+        ru_pipeline("overflow_fail"),
+        "overflow_fail",
+        SpringOverflowPolicy::Fail,
+        severe_memory_monitor(),
+    );
+
+    feeder.push(row(1)).unwrap();
+    feeder.push(row(2)).unwrap();
+    assert!(matches!(
+        feeder.push(row(3)),
+        Err(LastError::WouldBlock(queue)) if queue == "q_overflow_fail_src"
+    ));
+
+    assert_eq!(feeder.depth(), 2);
+    let stats = counters.stats();
+    assert_eq!(stats.rows_pushed, 2);
+    assert_eq!(stats.client_rows_dropped, 0);
+}
+
+#[test]
+fn test_overflow_policy_block() {
+    let (feeder, _counters) = start(
+        ru_pipeline("overflow_block"),
+        "overflow_block",
+        SpringOverflowPolicy::Block,
+        severe_memory_monitor(),
+    );
+
+    feeder.push(row(1)).unwrap();
+    feeder.push(row(2)).unwrap();
+
+    let started = Instant::now();
+    assert!(matches!(feeder.push(row(3)), Err(LastError::WouldBlock(_))));
+    assert!(started.elapsed() >= Duration::from_millis(50));
+    assert_eq!(feeder.depth(), 2);
+}
+
+#[test]
+fn test_overflow_policy_drop() {
+    for (name, policy, expected_n) in [
+        (
+            "overflow_drop_oldest",
+            SpringOverflowPolicy::DropOldest,
+            [2, 3],
+        ),
+        (
+            "overflow_drop_newest",
+            SpringOverflowPolicy::DropNewest,
+            [1, 2],
+        ),
+    ] {
+        let pipeline = ru_pipeline(name);
+        let memory_monitor = severe_memory_monitor();
+        let (feeder, counters) = start(pipeline.clone(), name, policy, memory_monitor.clone());
+
+        for n in 1..=3 {
+            feeder.push(row(n)).unwrap();
+        }
+        assert_eq!(feeder.depth(), 2);
+        let stats = counters.stats();
+        assert_eq!(
+            stats.rows_pushed,
+            2 + (policy == SpringOverflowPolicy::DropOldest) as u64
+        );
+        assert_eq!(stats.client_rows_dropped, 1);
+
+        // staged rows are forwarded once the memory state gets back to Moderate
+        memory_monitor.update(0);
+        let mut popped = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while popped.len() < 2 && Instant::now() < deadline {
+            match pipeline
+                .pop_non_blocking(&format!("q_{}_sink", name))
+                .unwrap()
+            {
+                Some(row) => popped.push(row.get_not_null_by_index::<i32>(0).unwrap()),
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        assert_eq!(popped, expected_n, "{:?}", policy);
+        assert_eq!(feeder.depth(), 0);
+    }
+}
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) -> SpringErrno {
+    let sql = CString::new(sql).unwrap();
+    spring_command(pipeline, sql.as_ptr())
+}
+
+unsafe fn open(toml: &str, name: &str) -> *mut SpringPipeline {
+    let toml = CString::new(toml).unwrap();
+    let config = spring_config_toml(toml.as_ptr());
+    let pipeline = spring_open(config);
+    spring_config_close(config);
+    assert!(!pipeline.is_null());
+
+    for sql in pipeline_sqls(name) {
+        assert_eq!(command(pipeline, &sql), SpringErrno::Ok);
+    }
+    pipeline
+}
+
+#[test]
+fn test_overflow_policy_options() {
+    unsafe {
+        let pipeline = open(
+            "[client]\nenable_metrics_receiver = true",
+            "overflow_options",
+        );
+
+        // invalid options are rejected before the reader is created
+        for options in [
+            ", OVERFLOW_POLICY 'SPILL', CAPACITY '10'",
+            ", OVERFLOW_POLICY 'FAIL'",
+            ", OVERFLOW_POLICY 'FAIL', CAPACITY '0'",
+            ", OVERFLOW_POLICY 'FAIL', CAPACITY '-1'",
+            ", OVERFLOW_POLICY 'BLOCK', CAPACITY '10'",
+        ] {
+            assert_eq!(
+                command(pipeline, &source_reader_sql("overflow_options", options)),
+                SpringErrno::InvalidOption,
+                "{}",
+                options
+            );
+        }
+        assert_eq!(
+            command(
+                pipeline,
+                &source_reader_sql("overflow_options", ", OVERFLOW_POLICY 'FAIL', CAPACITY '1'")
+            ),
+            SpringErrno::Ok
+        );
+
+        let src = CString::new("q_overflow_options_src").unwrap();
+        let sink = CString::new("q_overflow_options_sink").unwrap();
+        let unknown = CString::new("q_unknown").unwrap();
+        assert_eq!(
+            spring_queue_set_overflow_policy(
+                pipeline,
+                src.as_ptr(),
+                SpringOverflowPolicy::DropOldest as c_int,
+                1,
+                0
+            ),
+            SpringErrno::Ok
+        );
+        assert_eq!(
+            spring_queue_set_overflow_policy(
+                pipeline,
+                src.as_ptr(),
+                SpringOverflowPolicy::DropOldest as c_int,
+                0,
+                0
+            ),
+            SpringErrno::InvalidOption
+        );
+        assert_eq!(
+            spring_queue_set_overflow_policy(pipeline, src.as_ptr(), 100, 1, 0),
+            SpringErrno::InvalidOption
+        );
+        for queue in [&sink, &unknown] {
+            assert_eq!(
+                spring_queue_set_overflow_policy(
+                    pipeline,
+                    queue.as_ptr(),
+                    SpringOverflowPolicy::Fail as c_int,
+                    1,
+                    0
+                ),
+                SpringErrno::Unavailable
+            );
+        }
+
+        spring_close(pipeline);
+    }
+}
+
+#[test]
+fn test_overflow_policy_without_memory_usage() {
+    unsafe {
+        let pipeline = open(
+            "[web_console]\nenable_report_post = true",
+            "overflow_no_memory",
+        );
+
+        assert_eq!(
+            command(
+                pipeline,
+                &source_reader_sql(
+                    "overflow_no_memory",
+                    ", OVERFLOW_POLICY 'FAIL', CAPACITY '1'"
+                )
+            ),
+            SpringErrno::Unavailable
+        );
+        assert_eq!(
+            command(pipeline, &source_reader_sql("overflow_no_memory", "")),
+            SpringErrno::Ok
+        );
+
+        let src = CString::new("q_overflow_no_memory_src").unwrap();
+        assert_eq!(
+            spring_queue_set_overflow_policy(
+                pipeline,
+                src.as_ptr(),
+                SpringOverflowPolicy::Fail as c_int,
+                1,
+                0
+            ),
+            SpringErrno::Unavailable
+        );
+
+        spring_close(pipeline);
+    }
+}
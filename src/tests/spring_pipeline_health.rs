@@ -0,0 +1,352 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::{CStr, CString},
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    health_monitor::HealthMonitor,
+    metrics_receiver::{PipelineReport, QueueReport, TaskReport},
+    spring_pipeline_health::*,
+    *,
+};
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn open() -> *mut SpringPipeline {
+    let toml = CString::new(
+        "[client]\nenable_metrics_receiver = true\n[web_console]\nreport_interval_msec = 50",
+    )
+    .unwrap();
+    let config = spring_config_toml(toml.as_ptr());
+    let pipeline = spring_open(config);
+    spring_config_close(config);
+    pipeline
+}
+
+unsafe fn health_json_with_threshold(
+    pipeline: *const SpringPipeline,
+    stall_threshold_msec: u32,
+) -> serde_json::Value {
+    try_health_json(pipeline, stall_threshold_msec).expect("health is not reported")
+}
+
+/// `None` until the first metrics report arrives.
+unsafe fn try_health_json(
+    pipeline: *const SpringPipeline,
+    stall_threshold_msec: u32,
+) -> Option<serde_json::Value> {
+    let mut buf = [0; 8192];
+    let len = spring_pipeline_health_json(
+        pipeline,
+        stall_threshold_msec,
+        buf.as_mut_ptr(),
+        buf.len() as c_int,
+    );
+    if len == SpringErrno::Unavailable as c_int {
+        return None;
+    }
+    assert!(len > 0);
+    let json = CStr::from_ptr(buf.as_ptr()).to_str().unwrap();
+    Some(serde_json::from_str(json).unwrap())
+}
+
+fn component<'a>(health: &'a serde_json::Value, name: &str) -> &'a serde_json::Value {
+    health["components"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == name)
+        .unwrap_or_else(|| panic!("{} not found in {}", name, health))
+}
+
+/// Waits until `f` returns true for health of `pipeline`.
+unsafe fn wait_health(
+    pipeline: *const SpringPipeline,
+    f: impl Fn(&serde_json::Value) -> bool,
+) -> serde_json::Value {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let health = try_health_json(pipeline, 10_000);
+        if let Some(health) = health.as_ref().filter(|health| f(health)) {
+            return health.clone();
+        }
+        assert!(Instant::now() < deadline, "timeout: {:?}", health);
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn test_spring_pipeline_health() {
+    unsafe {
+        let pipeline = open();
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_health');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_health');
+            ",
+        );
+
+        let health = wait_health(pipeline, |health| {
+            health["components"].as_array().unwrap().len() == 5 + 3
+        });
+        for worker in ["GenericWorker", "PerformanceMonitorWorker"] {
+            assert_eq!(component(&health, worker)["kind"], "worker");
+        }
+        assert!(component(&health, "PerformanceMonitorWorker")["progress_age_msec"].is_u64());
+        for (task, kind) in [
+            ("queue_src", "source-task"),
+            ("pump_1", "pump-task"),
+            ("queue_sink", "sink-task"),
+        ] {
+            let task = component(&health, task);
+            assert_eq!(task["kind"], kind);
+            assert_eq!(task["status"], "alive");
+        }
+
+        let mut report = SpringHealthReport {
+            status: SpringHealthStatus::Failed,
+            n_components: 0,
+            n_alive: 0,
+            n_idle: 0,
+            n_stalled: 0,
+            n_failed: 0,
+        };
+        assert_eq!(
+            spring_pipeline_health(pipeline, 10_000, &mut report),
+            SpringErrno::Ok
+        );
+        assert_eq!(report.n_components, 8);
+        assert_eq!(
+            report.n_alive + report.n_idle + report.n_stalled + report.n_failed,
+            report.n_components
+        );
+
+        // no rows come to the source reader, while the pump and the sink writer have nothing to do
+        thread::sleep(Duration::from_millis(500));
+        let health = health_json_with_threshold(pipeline, 300);
+        assert_eq!(component(&health, "queue_src")["status"], "idle");
+        assert_eq!(component(&health, "pump_1")["status"], "alive");
+        assert_eq!(component(&health, "queue_sink")["status"], "alive");
+        assert_eq!(health["status"], "idle");
+
+        spring_close(pipeline);
+    }
+}
+
+#[test]
+fn test_spring_pipeline_health_source_reader_error() {
+    unsafe {
+        let pipeline = open();
+
+        // a remote host which closes the connection right after accepted
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let remote = thread::spawn(move || drop(listener.accept().unwrap()));
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_health_err');
+            ",
+        );
+        command(
+            pipeline,
+            &format!(
+                "
+                CREATE SOURCE READER tcp_src FOR source_1
+                    TYPE NET_CLIENT OPTIONS (PROTOCOL 'TCP', REMOTE_HOST '127.0.0.1', REMOTE_PORT '{}');
+                ",
+                port
+            ),
+        );
+        remote.join().unwrap();
+
+        let health = wait_health(pipeline, |health| {
+            component(health, "SourceWorker")["status"] == "failed"
+        });
+        assert_eq!(health["status"], "failed");
+        let last_error = &component(&health, "SourceWorker")["last_error"];
+        assert_ne!(last_error["errno"], 0);
+        assert!(!last_error["message"].as_str().unwrap().is_empty());
+        assert!(last_error["n_pipelines"].as_u64().unwrap() >= 1);
+
+        // the source reader does not make progress, so the error is kept regardless of the stall threshold
+        let health = wait_health(pipeline, |health| {
+            health["components"].as_array().unwrap().len() == 5 + 3
+        });
+        let tcp_src = component(&health, "tcp_src");
+        assert_eq!(tcp_src["status"], "failed");
+        assert_eq!(tcp_src["last_error"]["errno"], last_error["errno"]);
+        thread::sleep(Duration::from_millis(100));
+        let health = health_json_with_threshold(pipeline, 1);
+        assert_eq!(component(&health, "SourceWorker")["status"], "failed");
+        assert_eq!(component(&health, "tcp_src")["status"], "failed");
+
+        spring_close(pipeline);
+    }
+}
+
+#[test]
+fn test_spring_pipeline_health_worker_panic() {
+    unsafe {
+        let pipeline = open();
+        // metrics are reported after the first pipeline update
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+
+        // SpringQL-core names its worker threads
+        let worker = thread::Builder::new()
+            .name("PurgerWorker".to_string())
+            .spawn(|| panic!("purger panic"))
+            .unwrap();
+        assert!(worker.join().is_err());
+
+        let health = wait_health(pipeline, |_| true);
+        let purger = component(&health, "PurgerWorker");
+        assert_eq!(purger["status"], "failed");
+        assert_eq!(purger["last_error"]["panic"], true);
+        assert_eq!(purger["last_error"]["errno"], SpringErrno::Unknown as i32);
+        assert!(purger["last_error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("purger panic (at "));
+
+        spring_close(pipeline);
+    }
+}
+
+#[test]
+fn test_spring_pipeline_health_unavailable() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        spring_config_close(config);
+
+        let mut report = SpringHealthReport {
+            status: SpringHealthStatus::Alive,
+            n_components: 0,
+            n_alive: 0,
+            n_idle: 0,
+            n_stalled: 0,
+            n_failed: 0,
+        };
+        assert_eq!(
+            spring_pipeline_health(pipeline, 10_000, &mut report),
+            SpringErrno::Unavailable
+        );
+        assert!(try_health_json(pipeline, 10_000).is_none());
+
+        spring_close(pipeline);
+    }
+}
+
+fn task(id: &str, type_: &str, avg_gain_bytes_per_sec: f64) -> TaskReport {
+    TaskReport {
+        id: id.to_string(),
+        type_: type_.to_string(),
+        avg_gain_bytes_per_sec,
+    }
+}
+
+/// `src` -> `q` -> `sink`
+fn report(gain: f64, rows: u64, bytes: u64, received_at: Instant) -> PipelineReport {
+    PipelineReport {
+        received_at,
+        tasks: vec![
+            task("src", "source-task", gain),
+            task("sink", "sink-task", -gain),
+        ],
+        queues: vec![QueueReport {
+            id: "q".to_string(),
+            upstream_task_id: "src".to_string(),
+            downstream_task_id: "sink".to_string(),
+            is_window: false,
+            rows,
+            bytes,
+        }],
+    }
+}
+
+#[test]
+fn test_health_monitor_task_progress() {
+    let monitor = HealthMonitor::new();
+    let t0 = Instant::now() - Duration::from_secs(10);
+    let progress_age = |task| monitor.task_progress(task).unwrap().age();
+
+    monitor.update(&report(100.0, 5, 50, t0));
+    // nothing changed
+    monitor.update(&report(100.0, 5, 50, t0 + Duration::from_secs(4)));
+    assert_eq!(monitor.task_progress("src").unwrap().progressed_at, None);
+    assert!(progress_age("src") >= Duration::from_secs(10));
+    assert!(progress_age("sink") >= Duration::from_secs(10));
+    assert!(monitor.last_report_at().elapsed() >= Duration::from_secs(6));
+
+    // rows flow with a constant depth
+    monitor.update(&report(100.0, 5, 60, t0 + Duration::from_secs(8)));
+    assert!(progress_age("src") < Duration::from_secs(3));
+    assert!(progress_age("sink") < Duration::from_secs(3));
+
+    // the average gain changed
+    monitor.update(&report(120.0, 5, 60, Instant::now()));
+    assert!(progress_age("src") < Duration::from_secs(1));
+
+    assert_eq!(monitor.task_progress("unknown"), None);
+}
+
+#[test]
+fn test_health_monitor_idle_task() {
+    let monitor = HealthMonitor::new();
+    let t0 = Instant::now() - Duration::from_secs(10);
+
+    monitor.update(&report(0.0, 0, 0, t0));
+    monitor.update(&report(0.0, 0, 0, Instant::now()));
+    // the sink has nothing to do, and the source reads nothing
+    assert!(monitor.task_progress("sink").unwrap().age() < Duration::from_secs(1));
+    assert!(monitor.task_progress("src").unwrap().age() >= Duration::from_secs(10));
+}
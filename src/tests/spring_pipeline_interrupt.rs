@@ -0,0 +1,111 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{ffi::CString, thread, time::Duration};
+
+use crate::{spring_last_err::spring_last_err, spring_pop_status::SpringPopStatus, *};
+
+/// Pipeline pointer shared by threads. SpringPipeline is thread-safe.
+#[derive(Copy, Clone)]
+struct SharedPipeline(*const SpringPipeline);
+unsafe impl Send for SharedPipeline {}
+impl SharedPipeline {
+    fn get(self) -> *const SpringPipeline {
+        self.0
+    }
+}
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn setup(pipeline: *const SpringPipeline, q_sink: &str) {
+    command(
+        pipeline,
+        "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+    );
+    command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+    command(
+        pipeline,
+        "
+        CREATE PUMP pump_1 AS
+            INSERT INTO sink_1 (n)
+            SELECT STREAM source_1.n FROM source_1;
+        ",
+    );
+    command(
+        pipeline,
+        &format!(
+            "CREATE SINK WRITER queue_sink FOR sink_1 TYPE IN_MEMORY_QUEUE OPTIONS (NAME '{}');",
+            q_sink
+        ),
+    );
+}
+
+/// Blocks by `spring_pop()` in another thread and returns its errno.
+fn spawn_pop(pipeline: SharedPipeline, queue: &str) -> thread::JoinHandle<SpringErrno> {
+    let queue = CString::new(queue).unwrap();
+    thread::spawn(move || unsafe {
+        let row = spring_pop(pipeline.get(), queue.as_ptr());
+        assert!(row.is_null());
+
+        let mut errno = SpringErrno::Ok;
+        let mut errmsg = [0; 1024];
+        spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int);
+        errno
+    })
+}
+
+#[test]
+fn test_spring_pipeline_interrupt() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        setup(pipeline, "q_sink_pipeline_interrupt");
+
+        let popper = spawn_pop(SharedPipeline(pipeline), "q_sink_pipeline_interrupt");
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(spring_pipeline_interrupt(pipeline), SpringErrno::Ok);
+        assert_eq!(popper.join().unwrap(), SpringErrno::Interrupted);
+
+        // pops afterwards are also interrupted
+        let q_sink = CString::new("q_sink_pipeline_interrupt").unwrap();
+        let mut status = SpringPopStatus::Row;
+        let row = spring_pop_timeout(pipeline, q_sink.as_ptr(), -1, &mut status);
+        assert!(row.is_null());
+        assert_eq!(status, SpringPopStatus::Error);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_queue_interrupt() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        setup(pipeline, "q_sink_queue_interrupt");
+
+        let popper = spawn_pop(SharedPipeline(pipeline), "q_sink_queue_interrupt");
+        thread::sleep(Duration::from_millis(100));
+
+        let q_sink = CString::new("q_sink_queue_interrupt").unwrap();
+        assert_eq!(
+            spring_queue_interrupt(pipeline, q_sink.as_ptr()),
+            SpringErrno::Ok
+        );
+        assert_eq!(popper.join().unwrap(), SpringErrno::Interrupted);
+
+        // pops afterwards are not affected
+        let mut status = SpringPopStatus::Row;
+        let row = spring_pop_timeout(pipeline, q_sink.as_ptr(), 10, &mut status);
+        assert!(row.is_null());
+        assert_eq!(status, SpringPopStatus::Timeout);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
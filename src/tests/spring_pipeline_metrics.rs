@@ -0,0 +1,132 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::{CStr, CString},
+    thread,
+    time::Duration,
+};
+
+use crate::{spring_pipeline_metrics::*, *};
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn metrics(pipeline: *const SpringPipeline) -> SpringPipelineMetrics {
+    let mut metrics = SpringPipelineMetrics::default();
+    assert_eq!(
+        spring_pipeline_metrics(pipeline, &mut metrics),
+        SpringErrno::Ok
+    );
+    metrics
+}
+
+unsafe fn metrics_json(pipeline: *const SpringPipeline) -> serde_json::Value {
+    let mut buf = [0; 4096];
+    let len = spring_pipeline_metrics_json(pipeline, buf.as_mut_ptr(), buf.len() as c_int);
+    assert!(len > 0);
+    let json = CStr::from_ptr(buf.as_ptr()).to_str().unwrap();
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn test_spring_pipeline_metrics() {
+    unsafe {
+        let toml = CString::new(
+            "[client]\nenable_metrics_receiver = true\n[web_console]\nreport_interval_msec = 50",
+        )
+        .unwrap();
+        let config = spring_config_toml(toml.as_ptr());
+        let pipeline = spring_open(config);
+        spring_config_close(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_metrics');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_metrics');
+            ",
+        );
+
+        let queue_src = CString::new("q_src_metrics").unwrap();
+        for n in 1..=3 {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            let row = spring_source_row_from_json(json.as_ptr());
+            assert_eq!(
+                spring_push(pipeline, queue_src.as_ptr(), row),
+                SpringErrno::Ok
+            );
+        }
+
+        // wait for a report including all the tasks
+        while metrics(pipeline).n_tasks < 3 {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let m = metrics(pipeline);
+        assert!(m.report_age_msec >= 0);
+        assert_eq!(m.memory_upper_limit_bytes, 10_000_000);
+        assert_eq!(m.rows_pushed, 3);
+
+        let json = metrics_json(pipeline);
+        assert!(json["report_age_msec"].is_u64());
+        assert_eq!(json["tasks"].as_array().unwrap().len(), 3);
+        assert!(!json["queues"].as_array().unwrap().is_empty());
+        // sorted by name, including queues no row went through
+        assert_eq!(json["in_memory_queues"][0]["name"], "q_sink_metrics");
+        assert_eq!(json["in_memory_queues"][0]["rows_popped"], 0);
+        assert_eq!(json["in_memory_queues"][1]["name"], "q_src_metrics");
+        assert_eq!(json["in_memory_queues"][1]["rows_pushed"], 3);
+        assert_eq!(json["memory"]["upper_limit_bytes"], 10_000_000);
+
+        spring_close(pipeline);
+    }
+}
+
+#[test]
+fn test_spring_pipeline_metrics_not_reported() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        spring_config_close(config);
+
+        // reported every 3 seconds by default
+        let m = metrics(pipeline);
+        assert_eq!(m.report_age_msec, -1);
+        assert_eq!(m.n_tasks, 0);
+
+        let json = metrics_json(pipeline);
+        assert!(json["report_age_msec"].is_null());
+        assert!(json["memory"]["used_bytes"].is_null());
+
+        let mut buf = [0; 8];
+        assert_eq!(
+            spring_pipeline_metrics_json(pipeline, buf.as_mut_ptr(), buf.len() as c_int),
+            SpringErrno::CInsufficient as c_int
+        );
+
+        spring_close(pipeline);
+    }
+}
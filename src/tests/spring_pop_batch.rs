@@ -0,0 +1,145 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::CString,
+    ptr,
+    time::{Duration, Instant},
+};
+
+use crate::*;
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn setup(pipeline: *const SpringPipeline, q_src: &str, q_sink: &str) {
+    command(
+        pipeline,
+        "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+    );
+    command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+    command(
+        pipeline,
+        "
+        CREATE PUMP pump_1 AS
+            INSERT INTO sink_1 (n)
+            SELECT STREAM source_1.n FROM source_1;
+        ",
+    );
+    command(
+        pipeline,
+        &format!(
+            "CREATE SINK WRITER queue_sink FOR sink_1 TYPE IN_MEMORY_QUEUE OPTIONS (NAME '{}');",
+            q_sink
+        ),
+    );
+    command(
+        pipeline,
+        &format!(
+            "CREATE SOURCE READER queue_src FOR source_1 TYPE IN_MEMORY_QUEUE OPTIONS (NAME '{}');",
+            q_src
+        ),
+    );
+}
+
+#[test]
+fn test_spring_pop_batch() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        setup(pipeline, "q_src_pop_batch", "q_sink_pop_batch");
+
+        let q_src = CString::new("q_src_pop_batch").unwrap();
+        for n in 1..=3 {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            let row = spring_source_row_from_json(json.as_ptr());
+            assert_eq!(spring_push(pipeline, q_src.as_ptr(), row), SpringErrno::Ok);
+        }
+
+        let q_sink = CString::new("q_sink_pop_batch").unwrap();
+        let mut out_rows = [ptr::null_mut(); 8];
+        let mut popped = Vec::new();
+        while popped.len() < 3 {
+            let n_rows = spring_pop_batch(
+                pipeline,
+                q_sink.as_ptr(),
+                out_rows.as_mut_ptr(),
+                out_rows.len() as c_int,
+                1000,
+            );
+            assert!(n_rows > 0);
+
+            for row in &out_rows[..n_rows as usize] {
+                let mut n = 0;
+                assert_eq!(spring_column_int(*row, 0, &mut n), SpringErrno::Ok);
+                popped.push(n);
+                spring_sink_row_close(*row);
+            }
+        }
+        assert_eq!(popped, vec![1, 2, 3]);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_pop_batch_timeout() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        setup(
+            pipeline,
+            "q_src_pop_batch_timeout",
+            "q_sink_pop_batch_timeout",
+        );
+
+        let q_sink = CString::new("q_sink_pop_batch_timeout").unwrap();
+        let mut out_rows = [ptr::null_mut(); 8];
+
+        let t = Instant::now();
+        let n_rows = spring_pop_batch(
+            pipeline,
+            q_sink.as_ptr(),
+            out_rows.as_mut_ptr(),
+            out_rows.len() as c_int,
+            50,
+        );
+        assert_eq!(n_rows, 0);
+        assert!(t.elapsed() >= Duration::from_millis(50));
+
+        for max in [0, -1] {
+            assert_eq!(
+                spring_pop_batch(pipeline, q_sink.as_ptr(), out_rows.as_mut_ptr(), max, 0),
+                SpringErrno::InvalidOption as c_int
+            );
+        }
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_pop_batch_unavailable() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let q = CString::new("q_pop_batch_missing").unwrap();
+        let mut out_rows = [ptr::null_mut(); 8];
+        let n_rows = spring_pop_batch(
+            pipeline,
+            q.as_ptr(),
+            out_rows.as_mut_ptr(),
+            out_rows.len() as c_int,
+            -1,
+        );
+        assert_eq!(n_rows, SpringErrno::Unavailable as c_int);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
@@ -0,0 +1,142 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{ffi::CString, thread};
+
+use crate::*;
+
+const N_ROWS: i32 = 100;
+
+/// Pipeline pointer shared by threads. SpringPipeline is thread-safe.
+#[derive(Copy, Clone)]
+struct SharedPipeline(*const SpringPipeline);
+unsafe impl Send for SharedPipeline {}
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+/// source_1 -> sink_{i} -> `{prefix}_sink_{i}` for each `i` in `0..n_sinks`.
+unsafe fn setup(pipeline: *const SpringPipeline, prefix: &str, n_sinks: usize) {
+    command(
+        pipeline,
+        "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+    );
+    for i in 0..n_sinks {
+        command(
+            pipeline,
+            &format!("CREATE SINK STREAM sink_{} (n INTEGER NOT NULL);", i),
+        );
+        command(
+            pipeline,
+            &format!(
+                "CREATE PUMP pump_{i} AS INSERT INTO sink_{i} (n) SELECT STREAM source_1.n FROM source_1;",
+                i = i
+            ),
+        );
+        command(
+            pipeline,
+            &format!(
+                "CREATE SINK WRITER queue_sink_{i} FOR sink_{i} TYPE IN_MEMORY_QUEUE OPTIONS (NAME '{prefix}_sink_{i}');",
+                i = i,
+                prefix = prefix
+            ),
+        );
+    }
+    command(
+        pipeline,
+        &format!(
+            "CREATE SOURCE READER queue_src FOR source_1 TYPE IN_MEMORY_QUEUE OPTIONS (NAME '{}_src');",
+            prefix
+        ),
+    );
+}
+
+unsafe fn push_all(pipeline: *const SpringPipeline, prefix: &str) {
+    let q_src = CString::new(format!("{}_src", prefix)).unwrap();
+    for n in 0..N_ROWS {
+        let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+        let row = spring_source_row_from_json(json.as_ptr());
+        assert_eq!(spring_push(pipeline, q_src.as_ptr(), row), SpringErrno::Ok);
+    }
+}
+
+/// Pops `n_rows` rows from `queue` and returns their values.
+fn pop_n(pipeline: SharedPipeline, queue: String, n_rows: usize) -> Vec<i32> {
+    let queue = CString::new(queue).unwrap();
+    (0..n_rows)
+        .map(|_| unsafe {
+            let row = spring_pop(pipeline.0, queue.as_ptr());
+            assert!(!row.is_null());
+
+            let mut n = 0;
+            assert_eq!(spring_column_int(row, 0, &mut n), SpringErrno::Ok);
+            spring_sink_row_close(row);
+            n
+        })
+        .collect()
+}
+
+#[test]
+fn test_spring_pop_from_threads_on_different_queues() {
+    const N_SINKS: usize = 4;
+    let prefix = "q_pop_mt_diff";
+
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        setup(pipeline, prefix, N_SINKS);
+
+        // threads start blocking before any row is pushed
+        let shared = SharedPipeline(pipeline);
+        let poppers = (0..N_SINKS)
+            .map(|i| {
+                let queue = format!("{}_sink_{}", prefix, i);
+                thread::spawn(move || pop_n(shared, queue, N_ROWS as usize))
+            })
+            .collect::<Vec<_>>();
+
+        push_all(pipeline, prefix);
+
+        for popper in poppers {
+            let popped = popper.join().unwrap();
+            assert_eq!(popped, (0..N_ROWS).collect::<Vec<_>>());
+        }
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_pop_from_threads_on_same_queue() {
+    const N_THREADS: usize = 4;
+    let prefix = "q_pop_mt_same";
+
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        setup(pipeline, prefix, 1);
+
+        let shared = SharedPipeline(pipeline);
+        let poppers = (0..N_THREADS)
+            .map(|_| {
+                let queue = format!("{}_sink_0", prefix);
+                thread::spawn(move || pop_n(shared, queue, N_ROWS as usize / N_THREADS))
+            })
+            .collect::<Vec<_>>();
+
+        push_all(pipeline, prefix);
+
+        let mut popped = poppers
+            .into_iter()
+            .flat_map(|popper| popper.join().unwrap())
+            .collect::<Vec<_>>();
+        popped.sort_unstable();
+        assert_eq!(popped, (0..N_ROWS).collect::<Vec<_>>());
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
@@ -0,0 +1,92 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::CString,
+    time::{Duration, Instant},
+};
+
+use crate::{spring_pop_status::SpringPopStatus, *};
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+#[test]
+fn test_spring_pop_timeout() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_pop_timeout');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_pop_timeout');
+            ",
+        );
+
+        let q_sink = CString::new("q_sink_pop_timeout").unwrap();
+        let mut status = SpringPopStatus::Error;
+
+        let t = Instant::now();
+        let row = spring_pop_timeout(pipeline, q_sink.as_ptr(), 50, &mut status);
+        assert!(row.is_null());
+        assert_eq!(status, SpringPopStatus::Timeout);
+        assert!(t.elapsed() >= Duration::from_millis(50));
+
+        let q_src = CString::new("q_src_pop_timeout").unwrap();
+        let json = CString::new(r#"{"n": 1}"#).unwrap();
+        let source_row = spring_source_row_from_json(json.as_ptr());
+        assert_eq!(
+            spring_push(pipeline, q_src.as_ptr(), source_row),
+            SpringErrno::Ok
+        );
+
+        let row = spring_pop_timeout(pipeline, q_sink.as_ptr(), 5000, &mut status);
+        assert!(!row.is_null());
+        assert_eq!(status, SpringPopStatus::Row);
+        spring_sink_row_close(row);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_pop_timeout_unavailable() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let q = CString::new("q_pop_timeout_missing").unwrap();
+        let mut status = SpringPopStatus::Row;
+        let row = spring_pop_timeout(pipeline, q.as_ptr(), 10, &mut status);
+        assert!(row.is_null());
+        assert_eq!(status, SpringPopStatus::Error);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
@@ -0,0 +1,108 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::ffi::CString;
+
+use crate::*;
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn source_row(v: i32) -> *mut SpringSourceRow {
+    let json = CString::new(format!(r#"{{"n": {}}}"#, v)).unwrap();
+    let row = spring_source_row_from_json(json.as_ptr());
+    assert!(!row.is_null());
+    row
+}
+
+#[test]
+fn test_spring_push_batch() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_push_batch');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_push_batch');
+            ",
+        );
+
+        let rows = [source_row(1), source_row(2), source_row(3)];
+        let mut statuses = [SpringErrno::Unknown; 3];
+
+        let q_src = CString::new("q_src_push_batch").unwrap();
+        let errno = spring_push_batch(
+            pipeline,
+            q_src.as_ptr(),
+            rows.as_ptr(),
+            rows.len() as c_int,
+            statuses.as_mut_ptr(),
+        );
+        assert_eq!(errno, SpringErrno::Ok);
+        assert_eq!(statuses, [SpringErrno::Ok; 3]);
+
+        let q_sink = CString::new("q_sink_push_batch").unwrap();
+        for expected in 1..=3 {
+            let sink_row = spring_pop(pipeline, q_sink.as_ptr());
+            assert!(!sink_row.is_null());
+
+            let mut n = 0;
+            assert_eq!(spring_column_int(sink_row, 0, &mut n), SpringErrno::Ok);
+            assert_eq!(n, expected);
+
+            spring_sink_row_close(sink_row);
+        }
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_push_batch_unavailable() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let rows = [source_row(1), source_row(2)];
+        let mut statuses = [SpringErrno::Ok; 2];
+
+        let q = CString::new("q_push_batch_missing").unwrap();
+        let errno = spring_push_batch(
+            pipeline,
+            q.as_ptr(),
+            rows.as_ptr(),
+            rows.len() as c_int,
+            statuses.as_mut_ptr(),
+        );
+        assert_eq!(errno, SpringErrno::Unavailable);
+        assert_eq!(statuses, [SpringErrno::Unavailable; 2]);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
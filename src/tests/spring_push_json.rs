@@ -0,0 +1,128 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::ffi::CString;
+
+use crate::*;
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn setup(pipeline: *const SpringPipeline, queue_src: &str, queue_sink: &str) {
+    command(
+        pipeline,
+        "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+    );
+    command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+    command(
+        pipeline,
+        "
+        CREATE PUMP pump_1 AS
+            INSERT INTO sink_1 (n)
+            SELECT STREAM source_1.n FROM source_1;
+        ",
+    );
+    command(
+        pipeline,
+        &format!(
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME '{}');
+            ",
+            queue_sink
+        ),
+    );
+    command(
+        pipeline,
+        &format!(
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME '{}');
+            ",
+            queue_src
+        ),
+    );
+}
+
+unsafe fn pop_n(pipeline: *const SpringPipeline, queue: &str) -> i32 {
+    let queue = CString::new(queue).unwrap();
+    let row = spring_pop(pipeline, queue.as_ptr());
+    assert!(!row.is_null());
+    let mut n = 0;
+    assert_eq!(spring_column_int(row, 0, &mut n), SpringErrno::Ok);
+    spring_sink_row_close(row);
+    n
+}
+
+#[test]
+fn test_spring_push_json() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        setup(pipeline, "q_src_push_json", "q_sink_push_json");
+
+        let queue = CString::new("q_src_push_json").unwrap();
+        let json = CString::new(r#"{"n": 42}"#).unwrap();
+        assert_eq!(
+            spring_push_json(pipeline, queue.as_ptr(), json.as_ptr()),
+            SpringErrno::Ok
+        );
+        assert_eq!(pop_n(pipeline, "q_sink_push_json"), 42);
+
+        let json = CString::new("{").unwrap();
+        assert_eq!(
+            spring_push_json(pipeline, queue.as_ptr(), json.as_ptr()),
+            SpringErrno::InvalidFormat
+        );
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_push_json_lines() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+        setup(pipeline, "q_src_push_json_lines", "q_sink_push_json_lines");
+
+        let queue = CString::new("q_src_push_json_lines").unwrap();
+        // not NUL-terminated, with an empty line and CRLF
+        let buf = b"{\"n\": 1}\r\n\n{\"n\": 2}\n{\"n\": 3}";
+        let mut failed_line = -1;
+        assert_eq!(
+            spring_push_json_lines(
+                pipeline,
+                queue.as_ptr(),
+                buf.as_ptr() as *const c_char,
+                buf.len() as c_int,
+                &mut failed_line
+            ),
+            SpringErrno::Ok
+        );
+        assert_eq!(failed_line, 0);
+        for n in 1..=3 {
+            assert_eq!(pop_n(pipeline, "q_sink_push_json_lines"), n);
+        }
+
+        let buf = b"{\"n\": 4}\n{\"n\": \n{\"n\": 6}\n";
+        assert_eq!(
+            spring_push_json_lines(
+                pipeline,
+                queue.as_ptr(),
+                buf.as_ptr() as *const c_char,
+                buf.len() as c_int,
+                &mut failed_line
+            ),
+            SpringErrno::InvalidFormat
+        );
+        assert_eq!(failed_line, 2);
+        assert_eq!(pop_n(pipeline, "q_sink_push_json_lines"), 4);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
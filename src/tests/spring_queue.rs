@@ -0,0 +1,199 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::ffi::CString;
+
+use crate::{
+    queue_catalog::{parse_queue_decl, QueueKind},
+    spring_last_err::spring_last_err,
+    *,
+};
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+#[test]
+fn test_spring_queue() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_handle');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_handle');
+            ",
+        );
+
+        let name = CString::new("q_src_handle").unwrap();
+        let q_src = spring_queue_open(pipeline, name.as_ptr());
+        assert!(!q_src.is_null());
+        let name = CString::new("q_sink_handle").unwrap();
+        let q_sink = spring_queue_open(pipeline, name.as_ptr());
+        assert!(!q_sink.is_null());
+
+        for n in 1..=2 {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            let row = spring_source_row_from_json(json.as_ptr());
+            assert_eq!(spring_queue_push(pipeline, q_src, row), SpringErrno::Ok);
+        }
+
+        let row = spring_queue_pop(pipeline, q_sink);
+        assert!(!row.is_null());
+        let mut n = 0;
+        assert_eq!(spring_column_int(row, 0, &mut n), SpringErrno::Ok);
+        assert_eq!(n, 1);
+        spring_sink_row_close(row);
+
+        let mut is_err = false;
+        let row = loop {
+            let row = spring_queue_pop_non_blocking(pipeline, q_sink, &mut is_err);
+            assert!(!is_err);
+            if !row.is_null() {
+                break row;
+            }
+        };
+        assert_eq!(spring_column_int(row, 0, &mut n), SpringErrno::Ok);
+        assert_eq!(n, 2);
+        spring_sink_row_close(row);
+
+        assert_eq!(spring_queue_close(q_src), SpringErrno::Ok);
+        assert_eq!(spring_queue_close(q_sink), SpringErrno::Ok);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_queue_open_unavailable() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let name = CString::new("q_handle_missing").unwrap();
+        let queue = spring_queue_open(pipeline, name.as_ptr());
+        assert!(queue.is_null());
+
+        let mut errno = SpringErrno::Ok;
+        let mut errmsg = [0; 1024];
+        spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int);
+        assert_eq!(errno, SpringErrno::Unavailable);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_queue_open_keeps_rows() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            create sink writer queue_sink for sink_1
+                type IN_MEMORY_QUEUE options (NAME 'q_sink_handle_keep');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_handle_keep');
+            ",
+        );
+
+        let q_src = CString::new("q_src_handle_keep").unwrap();
+        for n in 1..=3 {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            let row = spring_source_row_from_json(json.as_ptr());
+            assert_eq!(spring_push(pipeline, q_src.as_ptr(), row), SpringErrno::Ok);
+        }
+        // let rows reach the sink queue
+        std::thread::sleep(std::time::Duration::from_millis(300));
+
+        let name = CString::new("q_sink_handle_keep").unwrap();
+        let q_sink = spring_queue_open(pipeline, name.as_ptr());
+        assert!(!q_sink.is_null());
+
+        for expected in 1..=3 {
+            let row = spring_queue_pop(pipeline, q_sink);
+            assert!(!row.is_null());
+            let mut n = 0;
+            assert_eq!(spring_column_int(row, 0, &mut n), SpringErrno::Ok);
+            assert_eq!(n, expected);
+            spring_sink_row_close(row);
+        }
+
+        assert_eq!(spring_queue_close(q_sink), SpringErrno::Ok);
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_parse_queue_decl() {
+    let decl = parse_queue_decl(
+        "create source reader r1 for s1 type IN_MEMORY_QUEUE options (NAME 'q 1', X '');",
+    )
+    .unwrap();
+    assert_eq!(decl.name, "q 1");
+    assert_eq!(decl.kind, QueueKind::Source);
+    assert_eq!(decl.options.get("X").map(String::as_str), Some(""));
+
+    let decl =
+        parse_queue_decl("CREATE SINK WRITER w1 FOR s1\n\tTYPE IN_MEMORY_QUEUE OPTIONS(NAME'q2')")
+            .unwrap();
+    assert_eq!(decl.name, "q2");
+    assert_eq!(decl.kind, QueueKind::Sink);
+
+    assert_eq!(
+        parse_queue_decl(
+            "CREATE SINK WRITER w1 FOR s1 TYPE NET_SERVER OPTIONS (PROTOCOL 'TCP', NAME 'q3')"
+        ),
+        None
+    );
+    assert_eq!(
+        parse_queue_decl("CREATE SOURCE STREAM s1 (n INTEGER NOT NULL)"),
+        None
+    );
+}
@@ -0,0 +1,194 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::CString,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::*;
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn is_readable(fd: c_int) -> bool {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let n = libc::poll(&mut pollfd, 1, 0);
+    assert!(n >= 0);
+    n > 0
+}
+
+#[test]
+fn test_spring_queue_fd() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_queue_fd');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_queue_fd');
+            ",
+        );
+
+        let q_sink = CString::new("q_sink_queue_fd").unwrap();
+        let fd = spring_queue_fd(pipeline, q_sink.as_ptr());
+        assert!(fd >= 0);
+        assert_eq!(spring_queue_fd(pipeline, q_sink.as_ptr()), fd);
+        assert!(!is_readable(fd));
+
+        let q_src = CString::new("q_src_queue_fd").unwrap();
+        for n in 1..=2 {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            let row = spring_source_row_from_json(json.as_ptr());
+            assert_eq!(spring_push(pipeline, q_src.as_ptr(), row), SpringErrno::Ok);
+        }
+
+        let t = Instant::now();
+        while !is_readable(fd) {
+            assert!(t.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let mut popped = 0;
+        let mut is_err = false;
+        while popped < 2 {
+            let row = spring_pop_non_blocking(pipeline, q_sink.as_ptr(), &mut is_err);
+            assert!(!is_err);
+            if row.is_null() {
+                assert!(t.elapsed() < Duration::from_secs(5));
+                thread::sleep(Duration::from_millis(1));
+            } else {
+                popped += 1;
+                spring_sink_row_close(row);
+            }
+        }
+        assert!(!is_readable(fd));
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_queue_fd_unavailable() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let q = CString::new("q_queue_fd_missing").unwrap();
+        let fd = spring_queue_fd(pipeline, q.as_ptr());
+        assert_eq!(fd, SpringErrno::Unavailable as c_int);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_queue_fd_bounded_buffer() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_queue_fd_bounded');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_queue_fd_bounded');
+            ",
+        );
+
+        const N_ROWS: usize = 1500;
+        let queue_src = CString::new("q_src_queue_fd_bounded").unwrap();
+        for n in 0..N_ROWS {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            let row = spring_source_row_from_json(json.as_ptr());
+            assert_eq!(
+                spring_push(pipeline, queue_src.as_ptr(), row),
+                SpringErrno::Ok
+            );
+        }
+
+        let queue_sink = CString::new("q_sink_queue_fd_bounded").unwrap();
+        let fd = spring_queue_fd(pipeline, queue_sink.as_ptr());
+        assert!(fd >= 0);
+
+        // the dispatcher stops at 1024 rows until they are popped
+        let depth = || {
+            let mut stats = SpringQueueStats::default();
+            assert_eq!(
+                spring_queue_stats(pipeline, queue_sink.as_ptr(), &mut stats),
+                SpringErrno::Ok
+            );
+            stats.client_depth
+        };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while depth() < 1024 {
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(10));
+        }
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(depth(), 1024);
+
+        for n in 0..N_ROWS {
+            let row = spring_pop(pipeline, queue_sink.as_ptr());
+            assert!(!row.is_null());
+            let mut v = 0;
+            assert_eq!(spring_column_int(row, 0, &mut v), SpringErrno::Ok);
+            assert_eq!(v as usize, n);
+            spring_sink_row_close(row);
+        }
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
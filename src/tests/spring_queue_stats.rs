@@ -0,0 +1,131 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::ffi::CString;
+
+use crate::*;
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe fn queue_stats(pipeline: *const SpringPipeline, queue: &str) -> SpringQueueStats {
+    let queue = CString::new(queue).unwrap();
+    let mut stats = SpringQueueStats::default();
+    assert_eq!(
+        spring_queue_stats(pipeline, queue.as_ptr(), &mut stats),
+        SpringErrno::Ok
+    );
+    stats
+}
+
+#[test]
+fn test_spring_queue_stats() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_stats');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_stats');
+            ",
+        );
+
+        assert_eq!(
+            queue_stats(pipeline, "q_sink_stats"),
+            SpringQueueStats::default()
+        );
+
+        let queue_src = CString::new("q_src_stats").unwrap();
+        for n in 1..=3 {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            let row = spring_source_row_from_json(json.as_ptr());
+            assert_eq!(
+                spring_push(pipeline, queue_src.as_ptr(), row),
+                SpringErrno::Ok
+            );
+        }
+
+        let queue_sink = CString::new("q_sink_stats").unwrap();
+        let row = spring_pop(pipeline, queue_sink.as_ptr());
+        assert!(!row.is_null());
+        spring_sink_row_close(row);
+
+        // wait for the rest of rows to be buffered on the C client side
+        while queue_stats(pipeline, "q_sink_stats").client_depth < 2 {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let src_stats = queue_stats(pipeline, "q_src_stats");
+        assert_eq!(src_stats.rows_pushed, 3);
+        assert_eq!(src_stats.rows_popped, 0);
+        assert_eq!(src_stats.client_depth, 0);
+
+        let sink_stats = queue_stats(pipeline, "q_sink_stats");
+        assert_eq!(sink_stats.rows_pushed, 0);
+        assert_eq!(sink_stats.rows_popped, 1);
+        assert_eq!(sink_stats.client_rows_dropped, 0);
+        assert_eq!(sink_stats.client_depth, 2);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_queue_stats_null() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let queue = CString::new("q_stats_null").unwrap();
+        assert_eq!(
+            spring_queue_stats(pipeline, queue.as_ptr(), ptr::null_mut()),
+            SpringErrno::CNull
+        );
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_queue_stats_unknown_queue() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let queue = CString::new("q_stats_unknown").unwrap();
+        let mut stats = SpringQueueStats::default();
+        assert_eq!(
+            spring_queue_stats(pipeline, queue.as_ptr(), &mut stats),
+            SpringErrno::Unavailable
+        );
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
@@ -46,7 +46,7 @@ fn test_spring_sink_row() {
 
         let source_row = {
             let col = CString::new("b").unwrap();
-            let val = vec![0x01u8, 0x02, 0x03];
+            let val = [0x01u8, 0x02, 0x03];
 
             let builder = spring_source_row_builder();
             let builder = spring_source_row_add_column_blob(
@@ -0,0 +1,209 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::CString,
+    mem::offset_of,
+    os::raw::{c_char, c_int},
+};
+
+use crate::{
+    spring_last_err::spring_last_err,
+    spring_struct_layout::{SpringColumnType, SpringStructColumn},
+    *,
+};
+
+#[repr(C, packed)]
+struct Sensor {
+    id: c_int,
+    temperature: f32,
+    name: [u8; 8],
+    payload: [u8; 3],
+}
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+fn column(
+    name: &CString,
+    column_type: SpringColumnType,
+    offset: usize,
+    len: usize,
+) -> SpringStructColumn {
+    SpringStructColumn {
+        name: name.as_ptr(),
+        column_type: column_type as c_int,
+        offset,
+        len,
+    }
+}
+
+#[test]
+fn test_spring_source_row_from_struct() {
+    let id = CString::new("id").unwrap();
+    let temperature = CString::new("temperature").unwrap();
+    let name = CString::new("name").unwrap();
+    let payload = CString::new("payload").unwrap();
+
+    let columns = [
+        column(&id, SpringColumnType::Int, offset_of!(Sensor, id), 0),
+        column(
+            &temperature,
+            SpringColumnType::Float,
+            offset_of!(Sensor, temperature),
+            0,
+        ),
+        column(&name, SpringColumnType::Text, offset_of!(Sensor, name), 8),
+        column(
+            &payload,
+            SpringColumnType::Blob,
+            offset_of!(Sensor, payload),
+            3,
+        ),
+    ];
+    let layout = SpringStructLayout {
+        columns: columns.as_ptr(),
+        n_columns: columns.len(),
+    };
+
+    let s = Sensor {
+        id: 42,
+        temperature: 5.5,
+        name: *b"abc\0\0\0\0\0",
+        payload: [0x01, 0x02, 0x03],
+    };
+
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (id INTEGER NOT NULL, temperature FLOAT NOT NULL, name TEXT NOT NULL, payload BLOB NOT NULL);",
+        );
+        command(
+            pipeline,
+            "CREATE SINK STREAM sink_1 (id INTEGER NOT NULL, temperature FLOAT NOT NULL, name TEXT NOT NULL, payload BLOB NOT NULL);",
+        );
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (id, temperature, name, payload)
+                SELECT STREAM source_1.id, source_1.temperature, source_1.name, source_1.payload FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_struct');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_struct');
+            ",
+        );
+
+        let source_row = spring_source_row_from_struct(&layout, (&s as *const Sensor).cast());
+        assert!(!source_row.is_null());
+
+        let q_src = CString::new("q_src_struct").unwrap();
+        let errno = spring_push(pipeline, q_src.as_ptr(), source_row);
+        assert_eq!(errno, SpringErrno::Ok);
+
+        let q_sink = CString::new("q_sink_struct").unwrap();
+        let sink_row = spring_pop(pipeline, q_sink.as_ptr());
+        assert!(!sink_row.is_null());
+
+        let mut id = 0;
+        assert_eq!(spring_column_int(sink_row, 0, &mut id), SpringErrno::Ok);
+        assert_eq!(id, 42);
+
+        let mut temperature = 0.0;
+        assert_eq!(
+            spring_column_float(sink_row, 1, &mut temperature),
+            SpringErrno::Ok
+        );
+        assert_eq!(temperature, 5.5);
+
+        let mut name = [0 as c_char; 16];
+        let len = spring_column_text(sink_row, 2, name.as_mut_ptr(), name.len() as c_int);
+        assert_eq!(len, 3);
+
+        let mut payload = [0u8; 8];
+        let len = spring_column_blob(
+            sink_row,
+            3,
+            payload.as_mut_ptr().cast(),
+            payload.len() as c_int,
+        );
+        assert_eq!(len, 3);
+        assert_eq!(&payload[..3], &[0x01, 0x02, 0x03]);
+
+        spring_sink_row_close(sink_row);
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_source_row_from_struct_duplicate_column() {
+    let id = CString::new("id").unwrap();
+    let columns = [
+        column(&id, SpringColumnType::Int, offset_of!(Sensor, id), 0),
+        column(&id, SpringColumnType::Int, offset_of!(Sensor, id), 0),
+    ];
+    let layout = SpringStructLayout {
+        columns: columns.as_ptr(),
+        n_columns: columns.len(),
+    };
+    let s = Sensor {
+        id: 42,
+        temperature: 5.5,
+        name: [0; 8],
+        payload: [0; 3],
+    };
+
+    unsafe {
+        let source_row = spring_source_row_from_struct(&layout, (&s as *const Sensor).cast());
+        assert!(source_row.is_null());
+    }
+}
+
+#[test]
+fn test_spring_source_row_from_struct_invalid_column_type() {
+    let id = CString::new("id").unwrap();
+    let columns = [SpringStructColumn {
+        name: id.as_ptr(),
+        column_type: 100,
+        offset: offset_of!(Sensor, id),
+        len: 0,
+    }];
+    let layout = SpringStructLayout {
+        columns: columns.as_ptr(),
+        n_columns: columns.len(),
+    };
+    let s = Sensor {
+        id: 42,
+        temperature: 5.5,
+        name: [0; 8],
+        payload: [0; 3],
+    };
+
+    unsafe {
+        let source_row = spring_source_row_from_struct(&layout, (&s as *const Sensor).cast());
+        assert!(source_row.is_null());
+
+        let mut errno = SpringErrno::Ok;
+        let mut errmsg = [0; 1024];
+        spring_last_err(&mut errno, errmsg.as_mut_ptr(), errmsg.len() as c_int);
+        assert_eq!(errno, SpringErrno::InvalidOption);
+    }
+}
@@ -0,0 +1,118 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+use std::{
+    ffi::{c_void, CString},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::*;
+
+unsafe fn command(pipeline: *const SpringPipeline, sql: &str) {
+    let sql = CString::new(sql).unwrap();
+    let errno = spring_command(pipeline, sql.as_ptr());
+    assert_eq!(errno, SpringErrno::Ok);
+}
+
+unsafe extern "C" fn on_row(row: *mut SpringSinkRow, user_data: *mut c_void) {
+    let received = &*(user_data as *const Mutex<Vec<i32>>);
+
+    let mut n = 0;
+    assert_eq!(spring_column_int(row, 0, &mut n), SpringErrno::Ok);
+    received.lock().unwrap().push(n);
+
+    spring_sink_row_close(row);
+}
+
+#[test]
+fn test_spring_subscribe() {
+    let received = Mutex::new(Vec::<i32>::new());
+    let user_data = &received as *const Mutex<Vec<i32>> as *mut c_void;
+
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        command(
+            pipeline,
+            "CREATE SOURCE STREAM source_1 (n INTEGER NOT NULL);",
+        );
+        command(pipeline, "CREATE SINK STREAM sink_1 (n INTEGER NOT NULL);");
+        command(
+            pipeline,
+            "
+            CREATE PUMP pump_1 AS
+                INSERT INTO sink_1 (n)
+                SELECT STREAM source_1.n FROM source_1;
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SINK WRITER queue_sink FOR sink_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_sink_subscribe');
+            ",
+        );
+        command(
+            pipeline,
+            "
+            CREATE SOURCE READER queue_src FOR source_1
+                TYPE IN_MEMORY_QUEUE OPTIONS (NAME 'q_src_subscribe');
+            ",
+        );
+
+        let q_sink = CString::new("q_sink_subscribe").unwrap();
+        assert_eq!(
+            spring_subscribe(pipeline, q_sink.as_ptr(), Some(on_row), user_data),
+            SpringErrno::Ok
+        );
+        assert_eq!(
+            spring_subscribe(pipeline, q_sink.as_ptr(), Some(on_row), user_data),
+            SpringErrno::Unavailable
+        );
+
+        let q_src = CString::new("q_src_subscribe").unwrap();
+        for n in 1..=3 {
+            let json = CString::new(format!(r#"{{"n": {}}}"#, n)).unwrap();
+            let row = spring_source_row_from_json(json.as_ptr());
+            assert_eq!(spring_push(pipeline, q_src.as_ptr(), row), SpringErrno::Ok);
+        }
+
+        let t = Instant::now();
+        while received.lock().unwrap().len() < 3 {
+            assert!(t.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(*received.lock().unwrap(), vec![1, 2, 3]);
+
+        assert_eq!(
+            spring_unsubscribe(pipeline, q_sink.as_ptr()),
+            SpringErrno::Ok
+        );
+        assert_eq!(
+            spring_unsubscribe(pipeline, q_sink.as_ptr()),
+            SpringErrno::Unavailable
+        );
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
+
+#[test]
+fn test_spring_subscribe_unavailable() {
+    unsafe {
+        let config = spring_config_default();
+        let pipeline = spring_open(config);
+
+        let q = CString::new("q_subscribe_missing").unwrap();
+        assert_eq!(
+            spring_subscribe(pipeline, q.as_ptr(), Some(on_row), std::ptr::null_mut()),
+            SpringErrno::Unavailable
+        );
+
+        spring_close(pipeline);
+        spring_config_close(config);
+    }
+}
@@ -0,0 +1,301 @@
+// This file is part of https://github.com/SpringQL/SpringQL-client-c which is licensed under MIT OR Apache-2.0. See file LICENSE-MIT or LICENSE-APACHE for full license details.
+
+//! Failures on SpringQL-core worker threads.
+//!
+//! SpringQL-core logs an error in a background task (e.g. a source reader failing to read) and continues,
+//! and a panic silently kills the worker thread. Both are captured here, from log records and the panic hook.
+//!
+//! SpringQL-core does not tell which pipeline a worker thread belongs to,
+//! so failures are reported to all pipelines open in the process, with the number of them.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    ffi::{c_void, CString},
+    panic::PanicHookInfo,
+    ptr,
+    sync::{Arc, Condvar, Mutex, MutexGuard, Weak},
+    thread,
+    time::Instant,
+};
+
+use ::log::{Level, Record};
+
+use crate::{
+    spring_background_error::{BackgroundErrorCallback, SpringBackgroundErrorCallback},
+    spring_errno::SpringErrno,
+};
+
+/// Names of worker threads in SpringQL-core.
+pub(crate) const CORE_WORKERS: [&str; 5] = [
+    "SourceWorker",
+    "GenericWorker",
+    "PerformanceMonitorWorker",
+    "MemoryStateMachineWorker",
+    "PurgerWorker",
+];
+
+/// SpringQL-core logs errors in background tasks with this target.
+/// Verified with SpringQL-core 0.18.1 (see `test_core_version()`), as the target is not a stable API.
+pub(crate) const CORE_ERROR_TARGET: &str = "springql_core::stream_engine::autonomous_executor";
+
+/// Failures kept for `spring_poll_background_error()`. The oldest one is dropped on overflow.
+const MAX_PENDING_FAILURES: usize = 256;
+
+/// Opaque pointer passed to a callback as-is.
+#[derive(Copy, Clone)]
+struct UserData(*mut c_void);
+
+// The library never dereferences `user_data`. Thread safety of the pointee is up to the caller.
+unsafe impl Send for UserData {}
+
+#[derive(Default)]
+struct CallbackSlot {
+    callback: Option<(BackgroundErrorCallback, UserData)>,
+    /// Number of calls in flight, including ones of a replaced callback.
+    running: usize,
+}
+
+thread_local! {
+    /// Monitors whose callback is running on this thread (a callback may be nested by a failure inside it).
+    static RUNNING_CALLBACKS: RefCell<Vec<*const WorkerMonitor>> = const { RefCell::new(Vec::new()) };
+}
+
+/// An error logged or a panic on a worker thread.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct WorkerFailure {
+    /// One of `CORE_WORKERS`.
+    pub(crate) worker: &'static str,
+    /// `Unknown` for a panic.
+    pub(crate) errno: SpringErrno,
+    pub(crate) message: String,
+    pub(crate) is_panic: bool,
+    pub(crate) at: Instant,
+    /// Number of pipelines open when the failure was captured. The failure belongs to one of them.
+    pub(crate) n_pipelines: usize,
+}
+
+/// Latest failures of each worker, and failures not yet delivered to the application, for a pipeline.
+#[derive(Default)]
+pub(crate) struct WorkerMonitor {
+    last_failures: Mutex<HashMap<&'static str, WorkerFailure>>,
+    panicked: Mutex<HashMap<&'static str, WorkerFailure>>,
+    /// Failures to be polled. Not used while `callback` is set.
+    pending: Mutex<VecDeque<WorkerFailure>>,
+    callback: Mutex<CallbackSlot>,
+    /// Notified when a call to the callback returns.
+    callback_returned: Condvar,
+}
+
+impl std::fmt::Debug for WorkerMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkerMonitor")
+            .field("last_failures", &self.last_failures)
+            .field("panicked", &self.panicked)
+            .field("pending", &self.pending)
+            .finish_non_exhaustive()
+    }
+}
+
+static MONITORS: Mutex<Vec<Weak<WorkerMonitor>>> = Mutex::new(Vec::new());
+
+impl WorkerMonitor {
+    /// Creates a monitor receiving failures until dropped.
+    pub(crate) fn start() -> Arc<Self> {
+        let monitor = Arc::new(Self::default());
+        let mut monitors = MONITORS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        monitors.retain(|monitor| monitor.strong_count() > 0);
+        monitors.push(Arc::downgrade(&monitor));
+        monitor
+    }
+
+    /// The latest failure of `worker`.
+    pub(crate) fn last_failure(&self, worker: &str) -> Option<WorkerFailure> {
+        self.last_failures
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(worker)
+            .cloned()
+    }
+
+    /// The first panic of `worker`. A worker thread which panicked is not running anymore.
+    pub(crate) fn panic(&self, worker: &str) -> Option<WorkerFailure> {
+        self.panicked
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(worker)
+            .cloned()
+    }
+
+    /// Removes the oldest failure not yet polled if `accept` returns true for it.
+    pub(crate) fn poll(
+        &self,
+        accept: impl FnOnce(&WorkerFailure) -> bool,
+    ) -> Option<WorkerFailure> {
+        let mut pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if accept(pending.front()?) {
+            pending.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// Sets a callback to receive failures instead of `poll()`. `None` unsets the callback.
+    ///
+    /// Waits for calls to the previous callback on other threads to return, so that the caller can free its `user_data`.
+    /// Calls on the current thread (replacing the callback from inside it) are not waited for.
+    pub(crate) fn set_callback(
+        &self,
+        callback: SpringBackgroundErrorCallback,
+        user_data: *mut c_void,
+    ) {
+        let mut slot = self.lock_callback();
+        slot.callback = callback.map(|callback| (callback, UserData(user_data)));
+
+        let running_here = RUNNING_CALLBACKS.with(|running| {
+            running
+                .borrow()
+                .iter()
+                .filter(|monitor| ptr::eq(**monitor, self))
+                .count()
+        });
+        while slot.running > running_here {
+            slot = self
+                .callback_returned
+                .wait(slot)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    fn lock_callback(&self) -> MutexGuard<'_, CallbackSlot> {
+        self.callback
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn record(&self, failure: &WorkerFailure) {
+        if failure.is_panic {
+            self.panicked
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .entry(failure.worker)
+                .or_insert_with(|| failure.clone());
+        }
+        self.last_failures
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(failure.worker, failure.clone());
+
+        // the lock is not held while the callback runs, so that the callback can replace itself
+        let callback = {
+            let mut slot = self.lock_callback();
+            if slot.callback.is_some() {
+                slot.running += 1;
+            }
+            slot.callback
+        };
+        if let Some((callback, user_data)) = callback {
+            let component = to_cstring(failure.worker);
+            let message = to_cstring(&failure.message);
+            RUNNING_CALLBACKS.with(|running| running.borrow_mut().push(self));
+            unsafe {
+                callback(
+                    failure.errno,
+                    component.as_ptr(),
+                    message.as_ptr(),
+                    failure.n_pipelines as u32,
+                    user_data.0,
+                )
+            };
+            RUNNING_CALLBACKS.with(|running| running.borrow_mut().pop());
+
+            self.lock_callback().running -= 1;
+            self.callback_returned.notify_all();
+        } else {
+            let mut pending = self
+                .pending
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if pending.len() == MAX_PENDING_FAILURES {
+                pending.pop_front();
+            }
+            pending.push_back(failure.clone());
+        }
+    }
+}
+
+fn to_cstring(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).expect("NUL characters are removed")
+}
+
+/// Passes a failure of the current worker thread to all monitors.
+fn broadcast(worker: &'static str, errno: SpringErrno, message: String, is_panic: bool) {
+    let monitors: Vec<_> = MONITORS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+    let failure = WorkerFailure {
+        worker,
+        errno,
+        message,
+        is_panic,
+        at: Instant::now(),
+        n_pipelines: monitors.len(),
+    };
+    for monitor in monitors {
+        monitor.record(&failure);
+    }
+}
+
+/// Name of the current thread if it is a worker thread in SpringQL-core.
+fn current_worker() -> Option<&'static str> {
+    let thread = thread::current();
+    let name = thread.name()?;
+    CORE_WORKERS.into_iter().find(|worker| *worker == name)
+}
+
+/// Captures an error logged by a worker thread. Called by the logger for every record.
+pub(crate) fn on_log(record: &Record<'_>) {
+    if record.level() > Level::Warn || record.target() != CORE_ERROR_TARGET {
+        return;
+    }
+    if let Some(worker) = current_worker() {
+        // SpringQL-core logs the debug format of `SpringError` (e.g. `ForeignIo { .. }`)
+        let message = record.args().to_string();
+        let variant: String = message
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        broadcast(
+            worker,
+            SpringErrno::from_name(&variant).unwrap_or(SpringErrno::Unknown),
+            message,
+            false,
+        );
+    }
+}
+
+/// Captures a panic on a worker thread. Called by the panic hook.
+pub(crate) fn on_panic(info: &PanicHookInfo<'_>) {
+    if let Some(worker) = current_worker() {
+        let payload = info.payload();
+        let mut message = if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else {
+            "a panic occurred".to_string()
+        };
+        if let Some(location) = info.location() {
+            message += &format!(" (at {})", location);
+        }
+        broadcast(worker, SpringErrno::Unknown, message, true);
+    }
+}